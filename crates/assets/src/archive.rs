@@ -0,0 +1,381 @@
+use crate::provider::AssetProvider;
+use std::{
+    borrow::Cow,
+    collections::BTreeMap,
+    fmt::Debug,
+    io::{self, Write},
+    ops::{Deref, Range},
+    path::{Path, PathBuf},
+    sync::OnceLock,
+};
+
+/// Identifies an asset archive file
+const MAGIC: &[u8; 8] = b"BLDRARCH";
+const VERSION: u32 = 1;
+
+/// Size of the magic, version and entry count
+const HEADER_LEN: usize = 16;
+
+/// Packs asset variants into a single archive file.
+///
+/// Layout (all integers little-endian):
+/// - header: `BLDRARCH`, version (u32), entry count (u32)
+/// - index, sorted by path: path length (u32), path, data offset (u64), data length (u64)
+/// - the data of all entries
+///
+/// The paths are the ones constructed by
+/// [`FilePathParts::construct_path`](crate::FilePathParts::construct_path).
+#[derive(Debug, Default)]
+pub struct ArchiveWriter {
+    entries: BTreeMap<String, Vec<u8>>,
+}
+
+impl ArchiveWriter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds an entry, replacing any earlier one with the same path
+    pub fn add<P: Into<String>>(&mut self, path: P, data: Vec<u8>) {
+        self.entries.insert(path.into(), data);
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn write<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        let index_len: usize = self.entries.keys().map(|path| 4 + path.len() + 8 + 8).sum();
+
+        writer.write_all(MAGIC)?;
+        writer.write_all(&VERSION.to_le_bytes())?;
+        writer.write_all(&(self.entries.len() as u32).to_le_bytes())?;
+
+        let mut offset = (HEADER_LEN + index_len) as u64;
+        for (path, data) in &self.entries {
+            writer.write_all(&(path.len() as u32).to_le_bytes())?;
+            writer.write_all(path.as_bytes())?;
+            writer.write_all(&offset.to_le_bytes())?;
+            writer.write_all(&(data.len() as u64).to_le_bytes())?;
+            offset += data.len() as u64;
+        }
+
+        for data in self.entries.values() {
+            writer.write_all(data)?;
+        }
+        writer.flush()
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        self.write(&mut bytes)
+            .expect("Writing to a Vec should not fail");
+        bytes
+    }
+}
+
+/// The bytes backing an [`Archive`]
+pub enum ArchiveData {
+    Owned(Vec<u8>),
+    Static(&'static [u8]),
+    #[cfg(feature = "mmap")]
+    Mmap(memmap2::Mmap),
+}
+
+impl Deref for ArchiveData {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        match self {
+            ArchiveData::Owned(data) => data,
+            ArchiveData::Static(data) => data,
+            #[cfg(feature = "mmap")]
+            ArchiveData::Mmap(data) => data,
+        }
+    }
+}
+
+impl Debug for ArchiveData {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let kind = match self {
+            ArchiveData::Owned(_) => "Owned",
+            ArchiveData::Static(_) => "Static",
+            #[cfg(feature = "mmap")]
+            ArchiveData::Mmap(_) => "Mmap",
+        };
+        write!(f, "{kind}({} bytes)", self.len())
+    }
+}
+
+/// A read-only asset archive written by [`ArchiveWriter`].
+///
+/// Lookups are a binary search in the index and return slices of the archive data.
+#[derive(Debug)]
+pub struct Archive {
+    data: ArchiveData,
+    /// Sorted by path, the ranges point into `data`
+    index: Vec<(String, Range<usize>)>,
+}
+
+impl Archive {
+    /// Opens an archive file, memory-mapping it when the `mmap` feature is enabled
+    /// and otherwise reading it into memory.
+    pub fn open<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        #[cfg(feature = "mmap")]
+        {
+            let file = std::fs::File::open(path)?;
+            // SAFETY: the archive is written once by the builder and must not be
+            // modified while the application is running.
+            let mmap = unsafe { memmap2::Mmap::map(&file)? };
+            Self::from_data(ArchiveData::Mmap(mmap))
+        }
+        #[cfg(not(feature = "mmap"))]
+        {
+            Self::from_data(ArchiveData::Owned(std::fs::read(path)?))
+        }
+    }
+
+    /// Parses an archive compiled into the binary, e.g. with `include_bytes!`
+    pub fn from_static(data: &'static [u8]) -> io::Result<Self> {
+        Self::from_data(ArchiveData::Static(data))
+    }
+
+    pub fn from_data(data: ArchiveData) -> io::Result<Self> {
+        let index = parse_index(&data)?;
+        Ok(Self { data, index })
+    }
+
+    /// Returns the data for a path, without copying it
+    pub fn get(&self, path: &str) -> Option<&[u8]> {
+        let pos = self
+            .index
+            .binary_search_by(|(entry, _)| entry.as_str().cmp(path))
+            .ok()?;
+        Some(&self.data[self.index[pos].1.clone()])
+    }
+
+    /// The paths of all entries, in sorted order
+    pub fn paths(&self) -> impl Iterator<Item = &str> {
+        self.index.iter().map(|(path, _)| path.as_str())
+    }
+
+    pub fn len(&self) -> usize {
+        self.index.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.index.is_empty()
+    }
+}
+
+impl AssetProvider for Archive {
+    fn load(&self, path: &str) -> Option<Cow<'_, [u8]>> {
+        self.get(path).map(Cow::Borrowed)
+    }
+}
+
+fn invalid(msg: &str) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("Invalid asset archive: {msg}"),
+    )
+}
+
+/// Reads `N` bytes at `pos` and advances it
+fn read_array<const N: usize>(data: &[u8], pos: &mut usize) -> io::Result<[u8; N]> {
+    let bytes = data
+        .get(*pos..*pos + N)
+        .ok_or_else(|| invalid("unexpected end of index"))?;
+    *pos += N;
+    Ok(bytes.try_into().unwrap())
+}
+
+fn parse_index(data: &[u8]) -> io::Result<Vec<(String, Range<usize>)>> {
+    let mut pos = 0;
+    if &read_array::<8>(data, &mut pos)? != MAGIC {
+        return Err(invalid("missing header"));
+    }
+    let version = u32::from_le_bytes(read_array(data, &mut pos)?);
+    if version != VERSION {
+        return Err(invalid(&format!("unsupported version {version}")));
+    }
+    let count = u32::from_le_bytes(read_array(data, &mut pos)?) as usize;
+
+    // Each entry takes at least 20 bytes, which bounds the count of a corrupt index
+    let mut index = Vec::with_capacity(count.min((data.len() - pos) / 20));
+    for _ in 0..count {
+        let path_len = u32::from_le_bytes(read_array(data, &mut pos)?) as usize;
+        let path = data
+            .get(pos..pos + path_len)
+            .ok_or_else(|| invalid("unexpected end of index"))?;
+        let path = std::str::from_utf8(path)
+            .map_err(|_| invalid("path is not valid UTF-8"))?
+            .to_string();
+        pos += path_len;
+
+        let offset = u64::from_le_bytes(read_array(data, &mut pos)?) as usize;
+        let len = u64::from_le_bytes(read_array(data, &mut pos)?) as usize;
+        if offset.checked_add(len).is_none_or(|end| end > data.len()) {
+            return Err(invalid(&format!("data for {path} is out of bounds")));
+        }
+        index.push((path, offset..offset + len));
+    }
+
+    if !index.is_sorted_by(|a, b| a.0 < b.0) {
+        return Err(invalid("index is not sorted"));
+    }
+    Ok(index)
+}
+
+/// Provider that opens an archive file on first use.
+///
+/// Created with [`new`](Self::new) for an archive at a given path, or with
+/// [`global`](Self::global) for generated code, which resolves the archive file
+/// relative to the path set with [`set_asset_base_path`](crate::set_asset_base_path).
+///
+/// # Panics
+/// Panics on first use if the archive can't be opened, or if it is resolved from
+/// the asset base path and that is not set.
+#[derive(Debug)]
+pub struct ArchiveProvider {
+    location: ArchiveLocation,
+    archive: OnceLock<Archive>,
+}
+
+#[derive(Debug)]
+enum ArchiveLocation {
+    /// A file name in the process-global asset base path
+    Global(&'static str),
+    Path(PathBuf),
+}
+
+impl ArchiveProvider {
+    /// Creates a provider for the archive file at `path`
+    pub fn new<P: Into<PathBuf>>(path: P) -> Self {
+        Self {
+            location: ArchiveLocation::Path(path.into()),
+            archive: OnceLock::new(),
+        }
+    }
+
+    /// Creates a provider for the archive `file_name` in the process-global base path.
+    ///
+    /// This is what the generated code for `DataProvider::Archive` uses.
+    pub const fn global(file_name: &'static str) -> Self {
+        Self {
+            location: ArchiveLocation::Global(file_name),
+            archive: OnceLock::new(),
+        }
+    }
+
+    pub fn archive(&self) -> &Archive {
+        self.archive.get_or_init(|| {
+            let path = match &self.location {
+                ArchiveLocation::Global(file_name) => {
+                    crate::get_asset_base_path_or_panic().join(file_name)
+                }
+                ArchiveLocation::Path(path) => path.clone(),
+            };
+            Archive::open(&path)
+                .unwrap_or_else(|e| panic!("Failed to open asset archive {}: {e}", path.display()))
+        })
+    }
+}
+
+impl AssetProvider for ArchiveProvider {
+    fn load(&self, path: &str) -> Option<Cow<'_, [u8]>> {
+        self.archive().load(path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn archive_bytes() -> Vec<u8> {
+        let mut writer = ArchiveWriter::new();
+        writer.add("css/style.css.br", b"brotli css".to_vec());
+        writer.add("css/style.css", b"body { color: blue; }".to_vec());
+        writer.add("messages.json/fr.json", b"{}".to_vec());
+        writer.add("empty.txt", Vec::new());
+        writer.to_bytes()
+    }
+
+    #[test]
+    fn test_roundtrip() {
+        let archive = Archive::from_data(ArchiveData::Owned(archive_bytes())).unwrap();
+
+        assert_eq!(archive.len(), 4);
+        assert_eq!(
+            archive.get("css/style.css"),
+            Some(&b"body { color: blue; }"[..])
+        );
+        assert_eq!(archive.get("css/style.css.br"), Some(&b"brotli css"[..]));
+        assert_eq!(archive.get("messages.json/fr.json"), Some(&b"{}"[..]));
+        assert_eq!(archive.get("empty.txt"), Some(&b""[..]));
+        assert_eq!(archive.get("css/missing.css"), None);
+
+        let paths: Vec<_> = archive.paths().collect();
+        assert_eq!(
+            paths,
+            [
+                "css/style.css",
+                "css/style.css.br",
+                "empty.txt",
+                "messages.json/fr.json"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_provider_borrows() {
+        let archive = Archive::from_data(ArchiveData::Owned(archive_bytes())).unwrap();
+        let provider: &dyn AssetProvider = &archive;
+        assert!(matches!(
+            provider.load("css/style.css"),
+            Some(Cow::Borrowed(b"body { color: blue; }"))
+        ));
+    }
+
+    #[test]
+    fn test_open_file() {
+        let path =
+            std::env::temp_dir().join(format!("builder-assets-{}.archive", std::process::id()));
+        std::fs::write(&path, archive_bytes()).unwrap();
+
+        let archive = Archive::open(&path).unwrap();
+        assert_eq!(archive.get("css/style.css.br"), Some(&b"brotli css"[..]));
+
+        let provider = ArchiveProvider::new(&path);
+        assert_eq!(
+            provider.load("css/style.css").as_deref(),
+            Some(&b"body { color: blue; }"[..])
+        );
+
+        drop(archive);
+        drop(provider);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_invalid_archive() {
+        assert!(Archive::from_data(ArchiveData::Static(b"not an archive")).is_err());
+
+        // Truncated data
+        let mut bytes = archive_bytes();
+        bytes.truncate(bytes.len() - 1);
+        let err = Archive::from_data(ArchiveData::Owned(bytes)).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+
+        // A corrupt count is not trusted for the allocation of the index
+        let mut bytes = MAGIC.to_vec();
+        bytes.extend(VERSION.to_le_bytes());
+        bytes.extend(u32::MAX.to_le_bytes());
+        let err = Archive::from_data(ArchiveData::Owned(bytes)).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+}
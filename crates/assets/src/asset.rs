@@ -1,45 +1,126 @@
-use crate::{encoding::Encoding, file_path::FilePathParts};
+use crate::{
+    encoding::Encoding, file_path::FilePathParts, format::ImageFormat, provider::AssetProvider,
+    reader::AssetReader,
+};
 use icu_locid::LanguageIdentifier;
+use std::{borrow::Cow, io};
+
+#[cfg(feature = "tokio")]
+use crate::reader::AsyncAssetReader;
 
 /// An asset represents a specific variant of a file with a particular encoding and language.
 /// It contains all the information needed to load the actual file data.
 #[derive(Debug)]
-pub struct Asset {
+pub struct Asset<'a> {
     pub encoding: Encoding,
-    pub mime: &'static str,
+    pub mime: &'a str,
     pub lang: Option<LanguageIdentifier>,
-    pub file_part_paths: FilePathParts,
-    provider: &'static fn(&str) -> Option<Vec<u8>>,
+    /// The alternative image format of this variant, None for the original format
+    pub format: Option<ImageFormat>,
+    /// The theme of this variant, for stylesheets compiled with theme variants
+    pub theme: Option<&'a str>,
+    pub file_part_paths: FilePathParts<'a>,
+    provider: &'a dyn AssetProvider,
 }
 
-impl Asset {
+impl<'a> Asset<'a> {
     /// Creates a new Asset instance
     pub fn new(
         encoding: Encoding,
-        mime: &'static str,
+        mime: &'a str,
         lang: Option<LanguageIdentifier>,
-        file_part_paths: FilePathParts,
-        provider: &'static fn(&str) -> Option<Vec<u8>>,
+        file_part_paths: FilePathParts<'a>,
+        provider: &'a dyn AssetProvider,
     ) -> Self {
         Self {
             encoding,
             mime,
             lang,
+            format: None,
+            theme: None,
             file_part_paths,
             provider,
         }
     }
 
+    /// Selects the variant in an alternative image format, with its MIME type
+    pub fn with_format(mut self, format: ImageFormat) -> Self {
+        self.format = Some(format);
+        self.mime = format.mime();
+        self
+    }
+
+    /// Selects the variant of a theme
+    pub fn with_theme(mut self, theme: &'a str) -> Self {
+        self.theme = Some(theme);
+        self
+    }
+
     /// Loads and returns the data for this asset
+    ///
+    /// # Panics
+    /// Panics if the data can't be loaded, use [`try_data_for`](Self::try_data_for) to handle it.
     pub fn data_for(&self) -> Vec<u8> {
         let path = self.file_path();
-        (self.provider)(&path).expect("Asset should exist and be loadable")
+        self.provider
+            .load(&path)
+            .expect("Asset should exist and be loadable")
+            .into_owned()
+    }
+
+    /// Loads and returns the data for this asset, or the reason it couldn't be loaded
+    pub fn try_data_for(&self) -> io::Result<Vec<u8>> {
+        self.provider
+            .try_load(&self.file_path())
+            .map(Cow::into_owned)
+    }
+
+    /// Opens a streaming reader for the data of this asset, which knows its length.
+    ///
+    /// Filesystem assets are read from the file as the reader is consumed.
+    pub fn reader(&self) -> io::Result<AssetReader<'a>> {
+        self.provider.open(&self.file_path())
+    }
+
+    /// Loads the data for this asset without blocking the tokio runtime
+    #[cfg(feature = "tokio")]
+    pub async fn data_for_async(&self) -> io::Result<Vec<u8>> {
+        let path = self.file_path();
+        self.provider.load_async(&path).await.map(Cow::into_owned)
+    }
+
+    /// Opens an async streaming reader for the data of this asset, which knows its length
+    #[cfg(feature = "tokio")]
+    pub async fn async_reader(&self) -> io::Result<AsyncAssetReader<'a>> {
+        let provider = self.provider;
+        let path = self.file_path();
+        provider.open_async(&path).await
+    }
+
+    /// Returns the data for this asset without copying it, when the provider
+    /// holds it in memory (e.g. `DataProvider::IncludeBytes`). For the providers
+    /// of generated code the data lives for the whole program.
+    ///
+    /// Returns None for providers that load a copy of the data.
+    pub fn static_data_for(&self) -> Option<&'a [u8]> {
+        match self.provider.load(&self.file_path())? {
+            Cow::Borrowed(data) => Some(data),
+            Cow::Owned(_) => None,
+        }
     }
 
     /// Constructs the file system path for this specific asset variant
     pub fn file_path(&self) -> String {
-        self.file_part_paths
-            .construct_path(self.encoding, self.lang.as_ref())
+        match self.theme {
+            Some(theme) => self
+                .file_part_paths
+                .construct_theme_path(self.encoding, theme),
+            None => self.file_part_paths.construct_variant_path(
+                self.encoding,
+                self.lang.as_ref(),
+                self.format,
+            ),
+        }
     }
 
     /// Returns the URL path for this asset (same for all variants)
@@ -51,13 +132,15 @@ impl Asset {
 // Note: Asset cannot implement Clone because function pointers cannot be cloned in a meaningful way
 // However, we can provide a manual clone method if needed
 
-impl Asset {
-    /// Manual clone method since Asset contains a function pointer
+impl Asset<'_> {
+    /// Manual clone method since Asset contains a provider reference
     pub fn clone_asset(&self) -> Self {
         Self {
             encoding: self.encoding,
             mime: self.mime,
             lang: self.lang.clone(),
+            format: self.format,
+            theme: self.theme,
             file_part_paths: self.file_part_paths,
             provider: self.provider,
         }
@@ -151,6 +234,63 @@ mod tests {
         assert_eq!(data, b"body { color: blue; }");
     }
 
+    static MOCK_STATIC_PROVIDER: fn(&str) -> Option<&'static [u8]> = mock_static_provider;
+    fn mock_static_provider(path: &str) -> Option<&'static [u8]> {
+        match path {
+            "assets/style.css" => Some(b"body { color: blue; }"),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn test_asset_static_data() {
+        let parts = FilePathParts {
+            folder: Some("assets"),
+            name: "style",
+            hash: None,
+            ext: "css",
+        };
+
+        let asset = Asset::new(
+            Encoding::Identity,
+            "text/css",
+            None,
+            parts,
+            &MOCK_STATIC_PROVIDER,
+        );
+        assert_eq!(asset.static_data_for(), Some(&b"body { color: blue; }"[..]));
+        assert_eq!(asset.data_for(), b"body { color: blue; }");
+
+        // Providers that copy the data can't hand out static references
+        let asset = Asset::new(Encoding::Identity, "text/css", None, parts, &MOCK_PROVIDER);
+        assert_eq!(asset.static_data_for(), None);
+    }
+
+    #[test]
+    fn test_asset_try_data_and_reader() {
+        use std::io::Read;
+
+        let parts = FilePathParts {
+            folder: Some("assets"),
+            name: "style",
+            hash: None,
+            ext: "css",
+        };
+
+        let asset = Asset::new(Encoding::Identity, "text/css", None, parts, &MOCK_PROVIDER);
+        assert_eq!(asset.try_data_for().unwrap(), b"body { color: blue; }");
+        let mut reader = asset.reader().unwrap();
+        assert_eq!(reader.len(), 21);
+        let mut data = Vec::new();
+        reader.read_to_end(&mut data).unwrap();
+        assert_eq!(data, b"body { color: blue; }");
+
+        let missing = Asset::new(Encoding::Gzip, "text/css", None, parts, &MOCK_PROVIDER);
+        let err = missing.try_data_for().unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::NotFound);
+        assert!(missing.reader().is_err());
+    }
+
     #[test]
     fn test_asset_clone() {
         let parts = FilePathParts {
@@ -1,49 +1,138 @@
-use crate::{asset::Asset, encoding::Encoding, file_path::FilePathParts, negotiation};
+use crate::{
+    asset::Asset,
+    encoding::Encoding,
+    file_path::FilePathParts,
+    format::ImageFormat,
+    language::{self, LanguageChoice, LanguageNegotiation},
+    negotiation,
+    provider::AssetProvider,
+    response::{self, AssetResponse, RequestHeaders},
+};
 use icu_locid::LanguageIdentifier;
+use std::borrow::Cow;
+
+/// The cookie with the theme of stylesheets compiled with theme variants
+pub const THEME_COOKIE: &str = "theme";
 
 /// AssetSet represents all variants of a single asset (different encodings and languages).
 /// Previously known as Asset in the generated code.
-#[derive(Debug)]
-pub struct AssetSet {
+///
+/// New fields break struct literals, build AssetSets with the const [`new`](Self::new)
+/// to be unaffected by them.
+///
+/// The generated AssetSets are statics, the ones of an [`AssetManifest`](crate::AssetManifest)
+/// borrow from the manifest assets owned by their catalog.
+#[derive(Debug, Clone, Copy)]
+pub struct AssetSet<'a> {
     /// The absolute url path used to get this resource
-    pub url_path: &'static str,
-    pub file_path_parts: FilePathParts,
+    pub url_path: &'a str,
+    pub file_path_parts: FilePathParts<'a>,
     /// All files (langs) are always encoded with all these encodings
-    pub available_encodings: &'static [Encoding],
-    pub available_languages: Option<&'static [LanguageIdentifier]>,
-    pub mime: &'static str,
-    pub provider: &'static fn(&str) -> Option<Vec<u8>>,
+    pub available_encodings: &'a [Encoding],
+    pub available_languages: Option<&'a [LanguageIdentifier]>,
+    /// Alternative image formats, each available with all encodings and languages
+    pub available_formats: Option<&'a [ImageFormat]>,
+    /// The themes of a stylesheet compiled with theme variants, the first is the default
+    pub available_themes: Option<&'a [Cow<'a, str>]>,
+    pub mime: &'a str,
+    pub provider: &'a dyn AssetProvider,
 }
 
-impl AssetSet {
-    /// Creates a new AssetSet
-    pub fn new(
-        url_path: &'static str,
-        file_path_parts: FilePathParts,
-        available_encodings: &'static [Encoding],
-        available_languages: Option<&'static [LanguageIdentifier]>,
-        mime: &'static str,
-        provider: &'static fn(&str) -> Option<Vec<u8>>,
+impl<'a> AssetSet<'a> {
+    /// Creates a new AssetSet, add image formats and themes with
+    /// [`with_formats`](Self::with_formats) and [`with_themes`](Self::with_themes)
+    pub const fn new(
+        url_path: &'a str,
+        file_path_parts: FilePathParts<'a>,
+        available_encodings: &'a [Encoding],
+        available_languages: Option<&'a [LanguageIdentifier]>,
+        mime: &'a str,
+        provider: &'a dyn AssetProvider,
     ) -> Self {
         Self {
             url_path,
             file_path_parts,
             available_encodings,
             available_languages,
+            available_formats: None,
+            available_themes: None,
             mime,
             provider,
         }
     }
 
+    /// Adds alternative image formats, negotiated with the Accept header
+    pub const fn with_formats(mut self, formats: &'a [ImageFormat]) -> Self {
+        self.available_formats = Some(formats);
+        self
+    }
+
+    /// Adds the themes of a stylesheet, chosen with the theme cookie
+    pub const fn with_themes(mut self, themes: &'a [Cow<'a, str>]) -> Self {
+        self.available_themes = Some(themes);
+        self
+    }
+
     /// Performs content negotiation and returns the best matching Asset
     ///
     pub fn asset_for(
         &self,
         accept_encodings: Option<&str>,
         accept_languages: Option<&str>,
-    ) -> Option<Asset> {
+    ) -> Option<Asset<'a>> {
+        self.asset_for_provider(self.provider, accept_encodings, accept_languages)
+    }
+
+    /// Performs content negotiation like [`asset_for`](Self::asset_for), with the
+    /// returned Asset loading its data from the given provider instead
+    pub fn asset_for_provider<'p>(
+        &self,
+        provider: &'p dyn AssetProvider,
+        accept_encodings: Option<&str>,
+        accept_languages: Option<&str>,
+    ) -> Option<Asset<'p>>
+    where
+        'a: 'p,
+    {
+        self.asset_for_negotiated(
+            provider,
+            accept_encodings,
+            accept_languages,
+            &LanguageNegotiation::default(),
+        )
+    }
+
+    pub(crate) fn asset_for_negotiated<'p>(
+        &self,
+        provider: &'p dyn AssetProvider,
+        accept_encodings: Option<&str>,
+        accept_languages: Option<&str>,
+        negotiation: &LanguageNegotiation,
+    ) -> Option<Asset<'p>>
+    where
+        'a: 'p,
+    {
+        let headers = RequestHeaders {
+            accept_encoding: accept_encodings,
+            accept_language: accept_languages,
+            ..RequestHeaders::default()
+        };
+        self.asset_for_headers(provider, &headers, negotiation)
+    }
+
+    /// Performs content negotiation for all request headers: the encoding, language,
+    /// image format from the Accept header and theme
+    pub(crate) fn asset_for_headers<'p>(
+        &self,
+        provider: &'p dyn AssetProvider,
+        headers: &RequestHeaders,
+        negotiation: &LanguageNegotiation,
+    ) -> Option<Asset<'p>>
+    where
+        'a: 'p,
+    {
         // Negotiate encoding
-        let encoding = if let Some(enc) = accept_encodings {
+        let encoding = if let Some(enc) = headers.accept_encoding {
             negotiation::negotiate_encoding(enc, self.available_encodings)
         } else if let Some(&enc) = self.available_encodings.last() {
             enc
@@ -51,23 +140,185 @@ impl AssetSet {
             return None;
         };
 
-        // Negotiate language (if languages are available)
-        let lang = if let Some(available_languages) = self.available_languages {
-            let accepted_languages = accept_languages.unwrap_or("");
-            // Try to negotiate, fall back to first available language if no match
-            negotiation::negotiate_language(accepted_languages, available_languages)
-                .or_else(|| available_languages.first().cloned())
+        let lang = self
+            .negotiate_language(negotiation, headers)
+            .map(|choice| choice.lang);
+
+        let mut asset = Asset::new(encoding, self.mime, lang, self.file_path_parts, provider);
+        if let Some(format) = self.negotiate_format(headers.accept) {
+            asset = asset.with_format(format);
+        }
+        Some(match self.negotiate_theme(headers) {
+            Some(theme) => asset.with_theme(theme),
+            None => asset,
+        })
+    }
+
+    /// Chooses the language for the request, None for assets without languages
+    pub fn negotiate_language(
+        &self,
+        negotiation: &LanguageNegotiation,
+        headers: &RequestHeaders,
+    ) -> Option<LanguageChoice> {
+        negotiation.negotiate(headers, self.available_languages?)
+    }
+
+    /// Chooses the theme for the request, from the theme override or the
+    /// [`THEME_COOKIE`] when it names an available theme, else the first theme.
+    /// None for assets without themes.
+    pub fn negotiate_theme(&self, headers: &RequestHeaders) -> Option<&'a str> {
+        let themes = self.available_themes?;
+        let requested = headers.theme.or_else(|| {
+            headers
+                .cookie
+                .and_then(|cookie| language::cookie_value(cookie, THEME_COOKIE))
+        });
+        requested
+            .and_then(|requested| themes.iter().find(|theme| *theme == requested))
+            .or(themes.first())
+            .map(|theme| theme.as_ref())
+    }
+
+    /// Describes the HTTP response for this asset.
+    ///
+    /// - 200 with the negotiated variant and its Content-Type, Content-Encoding,
+    ///   Content-Language, Vary, Cache-Control and ETag headers
+    /// - 304 when If-None-Match matches the ETag of the negotiated variant
+    /// - 406 when the client refuses all available encodings
+    /// - 206 or 416 for requests with a Range header, which is applied to the
+    ///   selected encoding variant (404 if the variant can't be opened)
+    ///
+    /// Hashed assets are cached as immutable and get an ETag derived from the hash.
+    /// Other assets are revalidated on every use and have no ETag, as that would
+    /// require loading the data to describe the response, so they are always sent in full.
+    pub fn respond(&self, headers: &RequestHeaders) -> AssetResponse<'a> {
+        self.respond_with_provider(self.provider, headers)
+    }
+
+    /// Describes the HTTP response like [`respond`](Self::respond), with the
+    /// Asset in the response loading its data from the given provider instead
+    pub fn respond_with_provider<'p>(
+        &self,
+        provider: &'p dyn AssetProvider,
+        headers: &RequestHeaders,
+    ) -> AssetResponse<'p>
+    where
+        'a: 'p,
+    {
+        self.respond_with_languages(provider, headers, &LanguageNegotiation::default())
+    }
+
+    /// Describes the HTTP response like [`respond_with_provider`](Self::respond_with_provider),
+    /// choosing the language with the given negotiation
+    pub fn respond_with_languages<'p>(
+        &self,
+        provider: &'p dyn AssetProvider,
+        headers: &RequestHeaders,
+        negotiation: &LanguageNegotiation,
+    ) -> AssetResponse<'p>
+    where
+        'a: 'p,
+    {
+        let mut vary = String::from("Accept-Encoding");
+        if self.available_languages.is_some() {
+            vary.push_str(", Accept-Language");
+            if negotiation.cookie_name().is_some() {
+                vary.push_str(", Cookie");
+            }
+        }
+        if self.available_formats.is_some() {
+            vary.push_str(", Accept");
+        }
+        if self.available_themes.is_some() && !vary.contains("Cookie") {
+            vary.push_str(", Cookie");
+        }
+
+        let encoding = match headers.accept_encoding {
+            Some(accept) => negotiation::acceptable_encoding(accept, self.available_encodings),
+            // Without the header any encoding is acceptable, but uncompressed is the safe choice
+            None => self
+                .available_encodings
+                .iter()
+                .max_by_key(|encoding| encoding.preference_order())
+                .copied(),
+        };
+        let Some(encoding) = encoding else {
+            return AssetResponse::new(406).header("Vary", vary);
+        };
+        let language = self.negotiate_language(negotiation, headers);
+        let lang = language.as_ref().map(|choice| choice.lang.clone());
+        let format = self.negotiate_format(headers.accept);
+        let theme = self.negotiate_theme(headers);
+
+        let cache_control = if self.file_path_parts.hash.is_some() {
+            response::CACHE_CONTROL_IMMUTABLE
         } else {
-            None
+            response::CACHE_CONTROL_REVALIDATE
         };
 
-        Some(Asset::new(
-            encoding,
-            self.mime,
-            lang,
-            self.file_path_parts,
-            self.provider,
-        ))
+        let mut response = AssetResponse::new(200);
+        if let Some(hash) = self.file_path_parts.hash {
+            let etag = match theme {
+                Some(theme) => response::etag(&format!("{hash}-{theme}"), encoding, None, None),
+                None => response::etag(hash, encoding, lang.as_ref(), format),
+            };
+            if let Some(if_none_match) = headers.if_none_match
+                && response::etag_matches(if_none_match, &etag)
+            {
+                return AssetResponse::new(304)
+                    .header("ETag", etag)
+                    .header("Cache-Control", cache_control)
+                    .header("Vary", vary);
+            }
+            response = response.header("ETag", etag);
+        }
+
+        let mime = format.map(|format| format.mime()).unwrap_or(self.mime);
+        response = response.header("Content-Type", mime);
+        if encoding != Encoding::Identity {
+            response = response.header("Content-Encoding", encoding.as_str());
+        }
+        if let Some(lang) = &lang {
+            response = response.header("Content-Language", lang.to_string());
+        }
+        response = response
+            .header("Vary", vary)
+            .header("Cache-Control", cache_control)
+            .header("Accept-Ranges", "bytes");
+
+        let mut asset = Asset::new(encoding, self.mime, lang, self.file_path_parts, provider);
+        if let Some(format) = format {
+            asset = asset.with_format(format);
+        }
+        if let Some(theme) = theme {
+            asset = asset.with_theme(theme);
+        }
+        // Ranges apply to the bytes of the selected encoding variant, whose reader
+        // is kept for the body
+        let reader = match headers.range {
+            Some(_) => match asset.reader() {
+                Ok(reader) => Some(reader),
+                Err(_) => return AssetResponse::not_found(),
+            },
+            None => None,
+        };
+        response.asset = Some(asset);
+        response.language = language;
+
+        match (headers.range, reader) {
+            (Some(range), Some(reader)) => {
+                let len = reader.len();
+                response.reader = Some(reader);
+                response.with_range(range, len)
+            }
+            _ => response,
+        }
+    }
+
+    /// Chooses an alternative image format allowed by the Accept header,
+    /// None for the original format
+    pub fn negotiate_format(&self, accept: Option<&str>) -> Option<ImageFormat> {
+        negotiation::negotiate_image_format(accept?, self.available_formats?)
     }
 
     /// Gets a specific Asset variant without content negotiation
@@ -75,7 +326,7 @@ impl AssetSet {
         &self,
         encoding: Encoding,
         lang: Option<&LanguageIdentifier>,
-    ) -> Option<Asset> {
+    ) -> Option<Asset<'a>> {
         // Check if the requested encoding is available
         if !self.available_encodings.contains(&encoding) {
             return None;
@@ -96,13 +347,38 @@ impl AssetSet {
             // depending on use case, for now we'll allow it
         }
 
-        Some(Asset::new(
+        let asset = Asset::new(
             encoding,
             self.mime,
             lang.cloned(),
             self.file_path_parts,
             self.provider,
-        ))
+        );
+        Some(
+            match self.available_themes.and_then(|themes| themes.first()) {
+                Some(theme) => asset.with_theme(theme.as_ref()),
+                None => asset,
+            },
+        )
+    }
+
+    /// Gets the variant of a theme without content negotiation
+    pub fn asset_with_theme(&self, encoding: Encoding, theme: &str) -> Option<Asset<'a>> {
+        if !self.available_encodings.contains(&encoding) {
+            return None;
+        }
+        let theme = self
+            .available_themes?
+            .iter()
+            .find(|available| *available == theme)?;
+        let asset = Asset::new(
+            encoding,
+            self.mime,
+            None,
+            self.file_path_parts,
+            self.provider,
+        );
+        Some(asset.with_theme(theme.as_ref()))
     }
 
     /// Returns all available language identifiers
@@ -110,20 +386,36 @@ impl AssetSet {
         self.available_languages
     }
 
+    /// Returns all available themes, the first is the default
+    pub fn themes(&self) -> Option<&'a [Cow<'a, str>]> {
+        self.available_themes
+    }
+
     /// Returns all available encodings
     pub fn encodings(&self) -> &[Encoding] {
         self.available_encodings
     }
 
     /// Returns the MIME type for this asset
-    pub fn mime_type(&self) -> &'static str {
+    pub fn mime_type(&self) -> &'a str {
         self.mime
     }
 
     /// Returns the URL path for this asset
-    pub fn url(&self) -> &'static str {
+    pub fn url(&self) -> &'a str {
         self.url_path
     }
+
+    /// Returns the URL path without the content hash, e.g. `/js/app.js` for `/js/app.hash123=.js`
+    pub fn logical_url_path(&self) -> String {
+        match self.file_path_parts.hash {
+            Some(hash) => match self.url_path.rsplit_once(&format!(".{hash}")) {
+                Some((start, end)) => format!("{start}{end}"),
+                None => self.url_path.to_string(),
+            },
+            None => self.url_path.to_string(),
+        }
+    }
 }
 
 #[cfg(test)]
@@ -161,6 +453,32 @@ mod tests {
         ext: "css",
     };
 
+    // Built like generated code could, without depending on the list of fields
+    static CONST_ASSET: AssetSet = AssetSet::new(
+        "/img/logo.png",
+        FilePathParts {
+            folder: Some("img"),
+            name: "logo",
+            hash: None,
+            ext: "png",
+        },
+        &[Encoding::Identity],
+        Some(&TEST_LANGUAGES),
+        "image/png",
+        &MOCK_PROVIDER,
+    )
+    .with_formats(&[ImageFormat::Avif]);
+
+    #[test]
+    fn test_const_asset_set() {
+        assert_eq!(
+            CONST_ASSET.available_formats,
+            Some(&[ImageFormat::Avif][..])
+        );
+        assert_eq!(CONST_ASSET.available_languages, Some(&TEST_LANGUAGES[..]));
+        assert_eq!(CONST_ASSET.available_themes, None);
+    }
+
     #[test]
     fn test_asset_set_creation() {
         let asset_set = AssetSet::new(
@@ -281,4 +599,218 @@ mod tests {
         assert!(asset_set.languages().unwrap().contains(&langid!("en")));
         assert!(asset_set.languages().unwrap().contains(&langid!("fr")));
     }
+
+    #[test]
+    fn test_respond_hashed_asset() {
+        let asset_set = AssetSet::new(
+            "/css/style.hash123=.css",
+            TEST_PARTS_WITH_HASH,
+            &TEST_ENCODINGS_2,
+            Some(&TEST_LANGUAGES_2),
+            "text/css",
+            &MOCK_PROVIDER,
+        );
+
+        let headers = RequestHeaders::new()
+            .accept_encoding("gzip, br")
+            .accept_language("en-US");
+        let response = asset_set.respond(&headers);
+        assert_eq!(response.status, 200);
+        assert_eq!(response.header_value("content-type"), Some("text/css"));
+        assert_eq!(response.header_value("Content-Encoding"), Some("br"));
+        assert_eq!(response.header_value("Content-Language"), Some("en"));
+        assert_eq!(
+            response.header_value("Vary"),
+            Some("Accept-Encoding, Accept-Language")
+        );
+        assert_eq!(
+            response.header_value("Cache-Control"),
+            Some(response::CACHE_CONTROL_IMMUTABLE)
+        );
+        assert_eq!(response.header_value("ETag"), Some("\"hash123=-en-br\""));
+        let asset = response.asset.unwrap();
+        assert_eq!(asset.data_for(), b"compressed english css");
+
+        // Revalidation with the ETag of the negotiated variant
+        let response = asset_set.respond(&headers.if_none_match("\"hash123=-en-br\""));
+        assert_eq!(response.status, 304);
+        assert!(response.asset.is_none());
+        assert_eq!(response.header_value("ETag"), Some("\"hash123=-en-br\""));
+
+        // A different variant doesn't match
+        let headers = headers
+            .accept_language("fr")
+            .if_none_match("\"hash123=-en-br\"");
+        let response = asset_set.respond(&headers);
+        assert_eq!(response.status, 200);
+        assert_eq!(response.header_value("ETag"), Some("\"hash123=-fr-br\""));
+    }
+
+    #[test]
+    fn test_respond_unhashed_asset() {
+        let asset_set = AssetSet::new(
+            "/css/style.css",
+            TEST_PARTS,
+            &TEST_ENCODINGS,
+            None,
+            "text/css",
+            &MOCK_PROVIDER,
+        );
+
+        // Without Accept-Encoding the uncompressed variant is served
+        let response = asset_set.respond(&RequestHeaders::new());
+        assert_eq!(response.status, 200);
+        assert_eq!(response.header_value("Content-Encoding"), None);
+        assert_eq!(response.header_value("Content-Language"), None);
+        assert_eq!(response.header_value("Vary"), Some("Accept-Encoding"));
+        assert_eq!(
+            response.header_value("Cache-Control"),
+            Some(response::CACHE_CONTROL_REVALIDATE)
+        );
+        assert_eq!(response.header_value("ETag"), None);
+        assert_eq!(response.asset.unwrap().data_for(), b"body { color: blue; }");
+    }
+
+    #[test]
+    fn test_respond_image_format() {
+        static LOGO_PARTS: FilePathParts = FilePathParts {
+            folder: Some("img"),
+            name: "logo",
+            hash: Some("abc="),
+            ext: "png",
+        };
+        static IDENTITY: [Encoding; 1] = [Encoding::Identity];
+        static LOGO_PROVIDER: fn(&str) -> Option<Vec<u8>> = logo_provider;
+        fn logo_provider(path: &str) -> Option<Vec<u8>> {
+            Some(path.as_bytes().to_vec())
+        }
+        let asset_set = AssetSet::new(
+            "/img/logo.abc=.png",
+            LOGO_PARTS,
+            &IDENTITY,
+            None,
+            "image/png",
+            &LOGO_PROVIDER,
+        )
+        .with_formats(&[ImageFormat::Avif, ImageFormat::WebP]);
+
+        let headers = RequestHeaders::new().accept("image/webp,image/*,*/*;q=0.8");
+        let response = asset_set.respond(&headers);
+        assert_eq!(response.header_value("Content-Type"), Some("image/webp"));
+        assert_eq!(
+            response.header_value("Vary"),
+            Some("Accept-Encoding, Accept")
+        );
+        assert_eq!(response.header_value("ETag"), Some("\"abc=-webp\""));
+        assert_eq!(
+            response.asset.unwrap().data_for(),
+            b"img/logo.abc=.png.webp"
+        );
+
+        // Browsers without AVIF or WebP support get the original format
+        let response = asset_set.respond(&RequestHeaders::new().accept("image/*"));
+        assert_eq!(response.header_value("Content-Type"), Some("image/png"));
+        assert_eq!(response.asset.unwrap().data_for(), b"img/logo.abc=.png");
+    }
+
+    #[test]
+    fn test_respond_themes() {
+        static THEME_PARTS: FilePathParts = FilePathParts {
+            folder: None,
+            name: "main",
+            hash: Some("abc="),
+            ext: "css",
+        };
+        static THEME_PROVIDER: fn(&str) -> Option<Vec<u8>> = theme_provider;
+        fn theme_provider(path: &str) -> Option<Vec<u8>> {
+            Some(path.as_bytes().to_vec())
+        }
+        let asset_set = AssetSet::new(
+            "/main.abc=.css",
+            THEME_PARTS,
+            &TEST_ENCODINGS_2,
+            None,
+            "text/css",
+            &THEME_PROVIDER,
+        )
+        .with_themes(&[
+            Cow::Borrowed("light"),
+            Cow::Borrowed("dark"),
+            Cow::Borrowed("high-contrast"),
+        ]);
+
+        let headers = RequestHeaders::new()
+            .accept_encoding("br")
+            .cookie("lang=fr; theme=dark");
+        let response = asset_set.respond(&headers);
+        assert_eq!(
+            response.header_value("Vary"),
+            Some("Accept-Encoding, Cookie")
+        );
+        assert_eq!(response.header_value("ETag"), Some("\"abc=-dark-br\""));
+        assert_eq!(
+            response.asset.unwrap().data_for(),
+            b"main.abc=.css/dark.css.br"
+        );
+
+        // Unknown themes and requests without the cookie get the first theme
+        let response = asset_set.respond(&RequestHeaders::new().cookie("theme=sepia"));
+        assert_eq!(
+            response.asset.unwrap().data_for(),
+            b"main.abc=.css/light.css"
+        );
+        let theme = asset_set.negotiate_theme(&headers.theme("high-contrast"));
+        assert_eq!(theme, Some("high-contrast"));
+
+        let asset = asset_set
+            .asset_with_theme(Encoding::Identity, "dark")
+            .unwrap();
+        assert_eq!(asset.file_path(), "main.abc=.css/dark.css");
+        assert!(asset_set.asset_with_theme(Encoding::Gzip, "dark").is_none());
+    }
+
+    #[test]
+    fn test_respond_range() {
+        let asset_set = AssetSet::new(
+            "/css/style.css",
+            TEST_PARTS,
+            &TEST_ENCODINGS,
+            None,
+            "text/css",
+            &MOCK_PROVIDER,
+        );
+
+        // The range applies to the brotli variant: "compressed css"
+        let headers = RequestHeaders::new()
+            .accept_encoding("br")
+            .range("bytes=0-9");
+        let mut response = asset_set.respond(&headers);
+        assert_eq!(response.status, 206);
+        assert_eq!(response.header_value("Content-Encoding"), Some("br"));
+        assert_eq!(response.header_value("Content-Range"), Some("bytes 0-9/14"));
+        assert_eq!(response.body().unwrap(), b"compressed");
+
+        let headers = RequestHeaders::new().range("bytes=100-");
+        let response = asset_set.respond(&headers);
+        assert_eq!(response.status, 416);
+        assert_eq!(response.header_value("Content-Range"), Some("bytes */21"));
+    }
+
+    #[test]
+    fn test_respond_not_acceptable() {
+        static BROTLI_ONLY: [Encoding; 1] = [Encoding::Brotli];
+        let asset_set = AssetSet::new(
+            "/css/style.css",
+            TEST_PARTS,
+            &BROTLI_ONLY,
+            None,
+            "text/css",
+            &MOCK_PROVIDER,
+        );
+
+        let response = asset_set.respond(&RequestHeaders::new().accept_encoding("gzip"));
+        assert_eq!(response.status, 406);
+        assert!(response.asset.is_none());
+        assert_eq!(response.header_value("Vary"), Some("Accept-Encoding"));
+    }
 }
@@ -0,0 +1,259 @@
+use crate::provider::AssetProvider;
+#[cfg(feature = "tokio")]
+use crate::provider::BoxFuture;
+use std::{
+    borrow::Cow,
+    collections::{BTreeMap, HashMap},
+    io,
+    sync::{Arc, Mutex},
+};
+
+/// Keeps recently loaded asset variants in memory, so that providers reading from
+/// disk like [`FileSystemProvider`](crate::FileSystemProvider) are only hit once per
+/// variant path until it is evicted.
+///
+/// The cache is bounded by the number of variants and optionally by their total size,
+/// evicting the least recently used variants first. Variants larger than the size
+/// limit are loaded from the inner provider on every use.
+///
+/// [`AssetProvider`] returns a `Cow`, which can't share the cached buffer, so loads
+/// through the trait copy the cached data and only save the reads of the inner
+/// provider. Use [`load_shared`](Self::load_shared) to get the cached buffer itself.
+///
+/// ```
+/// use builder_assets::{AssetCatalog, CachedProvider, FileSystemProvider};
+/// let provider = CachedProvider::new(FileSystemProvider::new("dist"), 256)
+///     .with_max_bytes(64 * 1024 * 1024);
+/// let catalog = AssetCatalog::new().with_provider(provider);
+/// ```
+#[derive(Debug)]
+pub struct CachedProvider<P> {
+    inner: P,
+    max_entries: usize,
+    max_bytes: Option<usize>,
+    cache: Mutex<Lru>,
+}
+
+impl<P: AssetProvider> CachedProvider<P> {
+    /// Caches up to `max_entries` variants loaded from `inner`
+    pub fn new(inner: P, max_entries: usize) -> Self {
+        Self {
+            inner,
+            max_entries,
+            max_bytes: None,
+            cache: Mutex::new(Lru::default()),
+        }
+    }
+
+    /// Also limits the total size of the cached variants
+    pub fn with_max_bytes(mut self, max_bytes: usize) -> Self {
+        self.max_bytes = Some(max_bytes);
+        self
+    }
+
+    pub fn inner(&self) -> &P {
+        &self.inner
+    }
+
+    /// The number of cached variants
+    pub fn len(&self) -> usize {
+        self.lock().entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The total size of the cached variants
+    pub fn cached_bytes(&self) -> usize {
+        self.lock().bytes
+    }
+
+    /// Removes all cached variants, e.g. after the files were redeployed
+    pub fn clear(&self) {
+        *self.lock() = Lru::default();
+    }
+
+    /// Returns the cached buffer for the path without copying it, loading it from
+    /// the inner provider when it is not cached
+    pub fn load_shared(&self, path: &str) -> io::Result<Arc<[u8]>> {
+        if let Some(data) = self.lock().get(path) {
+            return Ok(data);
+        }
+        let data: Arc<[u8]> = self.inner.try_load(path)?.into();
+        self.insert(path, data.clone());
+        Ok(data)
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Lru> {
+        self.cache
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    fn insert(&self, path: &str, data: Arc<[u8]>) {
+        if self.max_entries == 0 || self.max_bytes.is_some_and(|max| data.len() > max) {
+            return;
+        }
+        let mut cache = self.lock();
+        cache.insert(path, data);
+        while cache.entries.len() > self.max_entries
+            || self.max_bytes.is_some_and(|max| cache.bytes > max)
+        {
+            cache.evict_oldest();
+        }
+    }
+}
+
+impl<P: AssetProvider> AssetProvider for CachedProvider<P> {
+    fn load(&self, path: &str) -> Option<Cow<'_, [u8]>> {
+        self.try_load(path).ok()
+    }
+
+    fn try_load(&self, path: &str) -> io::Result<Cow<'_, [u8]>> {
+        if let Some(data) = self.lock().get(path) {
+            return Ok(Cow::Owned(data.to_vec()));
+        }
+        let data = self.inner.try_load(path)?;
+        if let Cow::Borrowed(_) = data {
+            // Already in memory, nothing to gain from a copy
+            return Ok(data);
+        }
+        let data: Arc<[u8]> = data.into();
+        self.insert(path, data.clone());
+        Ok(Cow::Owned(data.to_vec()))
+    }
+
+    #[cfg(feature = "tokio")]
+    fn load_async<'a: 'p, 'p>(&'a self, path: &'p str) -> BoxFuture<'p, io::Result<Cow<'a, [u8]>>> {
+        Box::pin(async move {
+            if let Some(data) = self.lock().get(path) {
+                return Ok(Cow::Owned(data.to_vec()));
+            }
+            let data = self.inner.load_async(path).await?;
+            if let Cow::Borrowed(_) = data {
+                return Ok(data);
+            }
+            let data: Arc<[u8]> = data.into();
+            self.insert(path, data.clone());
+            Ok(Cow::Owned(data.to_vec()))
+        })
+    }
+}
+
+#[derive(Debug, Default)]
+struct Lru {
+    /// The cached data and the tick of its last use
+    entries: HashMap<String, (Arc<[u8]>, u64)>,
+    /// The paths by the tick of their last use, oldest first
+    order: BTreeMap<u64, String>,
+    tick: u64,
+    bytes: usize,
+}
+
+impl Lru {
+    fn next_tick(&mut self) -> u64 {
+        self.tick += 1;
+        self.tick
+    }
+
+    fn get(&mut self, path: &str) -> Option<Arc<[u8]>> {
+        let tick = self.next_tick();
+        let (data, last_used) = self.entries.get_mut(path)?;
+        let previous = std::mem::replace(last_used, tick);
+        let data = data.clone();
+        if let Some(path) = self.order.remove(&previous) {
+            self.order.insert(tick, path);
+        }
+        Some(data)
+    }
+
+    fn insert(&mut self, path: &str, data: Arc<[u8]>) {
+        let tick = self.next_tick();
+        self.bytes += data.len();
+        if let Some((previous, last_used)) = self.entries.insert(path.to_string(), (data, tick)) {
+            self.bytes -= previous.len();
+            self.order.remove(&last_used);
+        }
+        self.order.insert(tick, path.to_string());
+    }
+
+    fn evict_oldest(&mut self) {
+        if let Some((_, path)) = self.order.pop_first()
+            && let Some((data, _)) = self.entries.remove(&path)
+        {
+            self.bytes -= data.len();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    /// Returns the path as data and counts the loads
+    #[derive(Debug, Default)]
+    struct CountingProvider {
+        loads: AtomicUsize,
+    }
+
+    impl AssetProvider for CountingProvider {
+        fn load(&self, path: &str) -> Option<Cow<'_, [u8]>> {
+            self.loads.fetch_add(1, Ordering::Relaxed);
+            (path != "missing").then(|| Cow::Owned(path.as_bytes().to_vec()))
+        }
+    }
+
+    fn loads(provider: &CachedProvider<CountingProvider>) -> usize {
+        provider.inner().loads.load(Ordering::Relaxed)
+    }
+
+    #[test]
+    fn test_evicts_least_recently_used() {
+        let provider = CachedProvider::new(CountingProvider::default(), 2);
+        assert_eq!(provider.load("a.css").unwrap().as_ref(), b"a.css");
+        provider.load("b.css");
+        provider.load("a.css");
+        assert_eq!(loads(&provider), 2);
+
+        // b.css is the least recently used
+        provider.load("c.css");
+        assert_eq!(provider.len(), 2);
+        provider.load("a.css");
+        assert_eq!(loads(&provider), 3);
+        provider.load("b.css");
+        assert_eq!(loads(&provider), 4);
+
+        assert!(provider.try_load("missing").is_err());
+        assert_eq!(provider.len(), 2);
+    }
+
+    #[test]
+    fn test_max_bytes() {
+        let provider = CachedProvider::new(CountingProvider::default(), 10).with_max_bytes(8);
+        provider.load("a.css");
+        provider.load("b.css");
+        assert_eq!(provider.len(), 1);
+        assert_eq!(provider.cached_bytes(), 5);
+
+        // Larger than the limit, never cached
+        provider.load("long-name.css");
+        provider.load("long-name.css");
+        assert_eq!(loads(&provider), 4);
+        assert_eq!(provider.cached_bytes(), 5);
+
+        provider.clear();
+        assert!(provider.is_empty());
+        assert_eq!(provider.cached_bytes(), 0);
+    }
+
+    #[test]
+    fn test_load_shared() {
+        let provider = CachedProvider::new(CountingProvider::default(), 2);
+        let first = provider.load_shared("a.css").unwrap();
+        let second = provider.load_shared("a.css").unwrap();
+        assert!(Arc::ptr_eq(&first, &second));
+        assert_eq!(loads(&provider), 1);
+    }
+}
@@ -1,22 +1,160 @@
-use crate::asset_set::AssetSet;
-use std::collections::BTreeMap;
+use crate::{
+    asset::Asset,
+    asset_set::AssetSet,
+    language::LanguageNegotiation,
+    provider::AssetProvider,
+    response::{AssetResponse, CACHE_CONTROL_REVALIDATE, RequestHeaders},
+};
+use std::{borrow::Cow, collections::BTreeMap, fmt, sync::Arc};
 
 /// AssetCatalog provides efficient URL-based lookups for assets
 #[derive(Debug)]
 pub struct AssetCatalog {
-    assets: BTreeMap<&'static str, &'static AssetSet>,
+    /// Keyed by the url path of the AssetSet, below the mount prefix
+    assets: BTreeMap<Cow<'static, str>, CatalogEntry>,
+    /// Maps logical url paths (without the content hash) to the hashed url paths
+    logical_urls: BTreeMap<String, Cow<'static, str>>,
+    logical_url_mode: LogicalUrls,
+    /// The url prefix the catalog is mounted at, empty for the root
+    prefix: String,
+    languages: LanguageNegotiation,
+    /// Serve assets only when their files match the hash recorded at build time
+    #[cfg(feature = "integrity")]
+    integrity_check: bool,
+    /// Add Subresource Integrity to the HTML tags
+    #[cfg(feature = "integrity")]
+    subresource_integrity: bool,
 }
 
+/// How [`AssetCatalog::respond`] answers requests for the logical url of a hashed
+/// asset, e.g. `/js/app.js` for `/js/app.hash123=.js`
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum LogicalUrls {
+    /// Only the hashed url is served, logical urls get 404
+    #[default]
+    NotFound,
+    /// Logical urls redirect to the current hashed url
+    Redirect,
+    /// Logical urls serve the asset directly, revalidated instead of cached as immutable
+    Rewrite,
+}
+
+#[derive(Debug, Clone)]
+struct CatalogEntry {
+    asset_set: EntryAssetSet,
+    /// Replaces the provider of the AssetSet for lookups through this catalog
+    provider: Option<Arc<dyn AssetProvider>>,
+    /// The result of the integrity check, done once on first use
+    #[cfg(feature = "integrity")]
+    intact: std::sync::OnceLock<bool>,
+    /// The Subresource Integrity of the HTML tags, computed once on first use
+    #[cfg(feature = "integrity")]
+    sri: std::sync::OnceLock<Option<String>>,
+}
+
+/// The AssetSet of a catalog entry: a static one of the generated code, or one
+/// owned by the catalog, which is freed with the last catalog using it
+#[derive(Debug, Clone)]
+enum EntryAssetSet {
+    Static(&'static AssetSet<'static>),
+    #[cfg(feature = "manifest")]
+    Manifest(Arc<crate::manifest::ManifestAsset>),
+}
+
+impl CatalogEntry {
+    fn new(asset_set: EntryAssetSet) -> Self {
+        Self {
+            asset_set,
+            provider: None,
+            #[cfg(feature = "integrity")]
+            intact: Default::default(),
+            #[cfg(feature = "integrity")]
+            sri: Default::default(),
+        }
+    }
+
+    fn asset_set(&self) -> AssetSet<'_> {
+        match &self.asset_set {
+            EntryAssetSet::Static(asset_set) => **asset_set,
+            #[cfg(feature = "manifest")]
+            EntryAssetSet::Manifest(asset) => asset.asset_set(),
+        }
+    }
+
+    /// The AssetSet with the provider of this entry, so that its assets load their
+    /// data like the ones returned by the catalog
+    fn bound_asset_set(&self) -> AssetSet<'_> {
+        AssetSet {
+            provider: self.provider(),
+            ..self.asset_set()
+        }
+    }
+
+    fn provider(&self) -> &dyn AssetProvider {
+        match &self.provider {
+            Some(provider) => provider.as_ref(),
+            None => self.asset_set().provider,
+        }
+    }
+
+    fn set_provider(&mut self, provider: Arc<dyn AssetProvider>) {
+        self.provider = Some(provider);
+        #[cfg(feature = "integrity")]
+        {
+            self.intact = Default::default();
+            self.sri = Default::default();
+        }
+    }
+
+    /// True when both entries serve the same AssetSet from the same provider
+    fn same_as(&self, other: &CatalogEntry) -> bool {
+        let same_asset_set = match (&self.asset_set, &other.asset_set) {
+            (EntryAssetSet::Static(a), EntryAssetSet::Static(b)) => std::ptr::eq(*a, *b),
+            #[cfg(feature = "manifest")]
+            (EntryAssetSet::Manifest(a), EntryAssetSet::Manifest(b)) => a == b,
+            #[cfg(feature = "manifest")]
+            _ => false,
+        };
+        same_asset_set && std::ptr::addr_eq(self.provider(), other.provider())
+    }
+}
+
+/// The urls and logical urls served by both catalogs in [`AssetCatalog::join`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JoinConflict {
+    pub urls: Vec<String>,
+}
+
+impl fmt::Display for JoinConflict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Asset urls served by both catalogs: {}",
+            self.urls.join(", ")
+        )
+    }
+}
+
+impl std::error::Error for JoinConflict {}
+
 impl AssetCatalog {
     /// Creates a new empty AssetCatalog
     pub fn new() -> Self {
         Self {
             assets: BTreeMap::new(),
+            logical_urls: BTreeMap::new(),
+            logical_url_mode: LogicalUrls::NotFound,
+            prefix: String::new(),
+            languages: LanguageNegotiation::default(),
+            #[cfg(feature = "integrity")]
+            integrity_check: false,
+            #[cfg(feature = "integrity")]
+            subresource_integrity: false,
         }
     }
 
     /// Creates an AssetCatalog from a slice of AssetSets
-    pub fn from_assets(assets: &'static [&'static AssetSet]) -> Self {
+    pub fn from_assets(assets: &'static [&'static AssetSet<'static>]) -> Self {
         let mut catalog = Self::new();
         for asset_set in assets {
             catalog.add_asset(asset_set);
@@ -25,23 +163,372 @@ impl AssetCatalog {
     }
 
     /// Adds an AssetSet to the catalog
-    pub fn add_asset(&mut self, asset_set: &'static AssetSet) {
-        self.assets.insert(asset_set.url_path, asset_set);
+    pub fn add_asset(&mut self, asset_set: &'static AssetSet<'static>) {
+        self.insert_entry(CatalogEntry::new(EntryAssetSet::Static(asset_set)));
+    }
+
+    /// Adds an asset of a manifest, owned by the catalog
+    #[cfg(feature = "manifest")]
+    pub(crate) fn add_manifest_asset(&mut self, asset: Arc<crate::manifest::ManifestAsset>) {
+        self.insert_entry(CatalogEntry::new(EntryAssetSet::Manifest(asset)));
+    }
+
+    fn insert_entry(&mut self, entry: CatalogEntry) {
+        let url = match &entry.asset_set {
+            EntryAssetSet::Static(asset_set) => Cow::Borrowed(asset_set.url_path),
+            #[cfg(feature = "manifest")]
+            EntryAssetSet::Manifest(asset) => Cow::Owned(asset.url_path.clone()),
+        };
+        let url = self.mounted(url);
+        let logical_url = format!("{}{}", self.prefix, entry.asset_set().logical_url_path());
+        self.logical_urls.insert(logical_url, url.clone());
+        self.assets.insert(url, entry);
+    }
+
+    fn mounted(&self, url: Cow<'static, str>) -> Cow<'static, str> {
+        if self.prefix.is_empty() {
+            url
+        } else {
+            Cow::Owned(format!("{}{url}", self.prefix))
+        }
+    }
+
+    /// Mounts the catalog under a url prefix like `/static`, so that the asset
+    /// generated for `/style.css` is served at `/static/style.css`.
+    ///
+    /// Mounting an already mounted catalog nests the prefixes.
+    pub fn with_prefix(mut self, prefix: &str) -> Self {
+        let prefix = prefix.trim_end_matches('/');
+        if prefix.is_empty() {
+            return self;
+        }
+        let prefix = if prefix.starts_with('/') {
+            prefix.to_string()
+        } else {
+            format!("/{prefix}")
+        };
+
+        self.assets = std::mem::take(&mut self.assets)
+            .into_iter()
+            .map(|(url, entry)| (Cow::Owned(format!("{prefix}{url}")), entry))
+            .collect();
+        self.logical_urls = std::mem::take(&mut self.logical_urls)
+            .into_iter()
+            .map(|(logical, url)| {
+                (
+                    format!("{prefix}{logical}"),
+                    Cow::Owned(format!("{prefix}{url}")),
+                )
+            })
+            .collect();
+        self.prefix = format!("{prefix}{}", self.prefix);
+        self
+    }
+
+    /// The url prefix the catalog is mounted at, empty when mounted at the root
+    pub fn prefix(&self) -> &str {
+        &self.prefix
+    }
+
+    /// Checks if a request url is under the mount prefix of the catalog
+    pub fn is_under_prefix(&self, url_path: &str) -> bool {
+        url_path
+            .strip_prefix(self.prefix.as_str())
+            .is_some_and(|rest| rest.is_empty() || rest.starts_with('/'))
+    }
+
+    /// Sets how requests for logical urls (without the content hash) are answered
+    pub fn with_logical_urls(mut self, mode: LogicalUrls) -> Self {
+        self.logical_url_mode = mode;
+        self
+    }
+
+    /// Sets how the language of translated assets is chosen, e.g. with a default language
+    pub fn with_language_negotiation(mut self, negotiation: LanguageNegotiation) -> Self {
+        self.languages = negotiation;
+        self
+    }
+
+    pub fn language_negotiation(&self) -> &LanguageNegotiation {
+        &self.languages
+    }
+
+    /// Only serves hashed assets after checking that their files match the hash recorded
+    /// at build time, see [`AssetSet::verify`]. Each asset is checked once, on first use.
+    ///
+    /// Assets that fail the check are not returned by [`asset_for`](Self::asset_for),
+    /// and [`respond`](Self::respond) answers them with 500.
+    #[cfg(feature = "integrity")]
+    pub fn with_integrity_check(mut self) -> Self {
+        self.integrity_check = true;
+        self
+    }
+
+    /// Checks the files of all assets against their hashes, e.g. right after loading a
+    /// new manifest. Returns the urls of the assets that failed with the reason.
+    #[cfg(feature = "integrity")]
+    pub fn verify_all(&self) -> Vec<(String, crate::IntegrityError)> {
+        self.assets
+            .iter()
+            .filter_map(|(url, entry)| {
+                let result = entry.asset_set().verify(entry.provider());
+                if self.integrity_check {
+                    let _ = entry.intact.set(result.is_ok());
+                }
+                result.err().map(|err| (url.to_string(), err))
+            })
+            .collect()
+    }
+
+    /// Adds the `integrity` attribute to the HTML tags of assets served with the
+    /// same content for all requests, see [`AssetSet::subresource_integrity`]
+    #[cfg(feature = "integrity")]
+    pub fn with_subresource_integrity(mut self) -> Self {
+        self.subresource_integrity = true;
+        self
+    }
+
+    /// False when the integrity check is enabled and the files of the entry don't match its hash
+    #[cfg_attr(not(feature = "integrity"), allow(unused_variables))]
+    fn is_intact(&self, entry: &CatalogEntry) -> bool {
+        #[cfg(feature = "integrity")]
+        if self.integrity_check {
+            return *entry
+                .intact
+                .get_or_init(|| entry.asset_set().verify(entry.provider()).is_ok());
+        }
+        true
+    }
+
+    /// Loads the data of all assets in this catalog from the given provider,
+    /// e.g. a [`FileSystemProvider`](crate::FileSystemProvider) with its own base path
+    pub fn with_provider<P: AssetProvider + 'static>(mut self, provider: P) -> Self {
+        let provider: Arc<dyn AssetProvider> = Arc::new(provider);
+        for entry in self.assets.values_mut() {
+            entry.set_provider(provider.clone());
+        }
+        self
+    }
+
+    /// Loads the data of the assets generated with the `original` provider from
+    /// the given provider instead, leaving the other assets unchanged.
+    ///
+    /// The original provider is found in the `provider` field of the generated AssetSets.
+    pub fn replace_provider<P: AssetProvider + 'static>(
+        mut self,
+        original: &'static dyn AssetProvider,
+        provider: P,
+    ) -> Self {
+        let provider: Arc<dyn AssetProvider> = Arc::new(provider);
+        for entry in self.assets.values_mut() {
+            if std::ptr::addr_eq(entry.asset_set().provider, original) {
+                entry.set_provider(provider.clone());
+            }
+        }
+        self
+    }
+
+    /// Looks up an AssetSet by URL path. Its provider is the one of this catalog,
+    /// set with [`with_provider`](Self::with_provider) or [`replace_provider`](Self::replace_provider).
+    pub fn get_asset_set(&self, url_path: &str) -> Option<AssetSet<'_>> {
+        self.assets.get(url_path).map(CatalogEntry::bound_asset_set)
+    }
+
+    /// Returns the current hashed URL path for a logical url path like `/js/app.js`
+    pub fn url_for_logical(&self, logical_url_path: &str) -> Option<&str> {
+        self.logical_urls.get(logical_url_path).map(AsRef::as_ref)
+    }
+
+    /// Looks up an AssetSet by its logical url path, without the content hash
+    pub fn get_asset_set_by_logical(&self, logical_url_path: &str) -> Option<AssetSet<'_>> {
+        self.url_for_logical(logical_url_path)
+            .and_then(|url_path| self.get_asset_set(url_path))
+    }
+
+    /// Returns the hashed URL path served for a request url, following logical
+    /// urls unless they are configured as [`LogicalUrls::NotFound`]
+    pub fn resolve_url(&self, url_path: &str) -> Option<&str> {
+        if let Some((url_path, _)) = self.assets.get_key_value(url_path) {
+            return Some(url_path);
+        }
+        match self.logical_url_mode {
+            LogicalUrls::NotFound => None,
+            LogicalUrls::Redirect | LogicalUrls::Rewrite => self.url_for_logical(url_path),
+        }
+    }
+
+    /// Returns the provider used by this catalog for the asset at the URL path
+    pub fn provider_for(&self, url_path: &str) -> Option<&dyn AssetProvider> {
+        self.assets.get(url_path).map(CatalogEntry::provider)
+    }
+
+    /// Looks up an AssetSet by URL path and performs content negotiation,
+    /// returning an Asset that loads its data from this catalog's provider.
+    ///
+    /// Images are returned in their original format, see
+    /// [`asset_for_request`](Self::asset_for_request) to negotiate it with the Accept header.
+    pub fn asset_for(
+        &self,
+        url_path: &str,
+        accept_encodings: Option<&str>,
+        accept_languages: Option<&str>,
+    ) -> Option<Asset<'_>> {
+        let headers = RequestHeaders {
+            accept_encoding: accept_encodings,
+            accept_language: accept_languages,
+            ..RequestHeaders::default()
+        };
+        self.asset_for_request(url_path, &headers)
+    }
+
+    /// Looks up an AssetSet by URL path and negotiates the encoding, language, image
+    /// format and theme with the request headers, returning an Asset that loads its
+    /// data from this catalog's provider
+    pub fn asset_for_request(&self, url_path: &str, headers: &RequestHeaders) -> Option<Asset<'_>> {
+        let entry = self.assets.get(url_path)?;
+        if !self.is_intact(entry) {
+            return None;
+        }
+        entry
+            .asset_set()
+            .asset_for_headers(entry.provider(), headers, &self.languages)
+    }
+
+    /// Describes the HTTP response for the asset at the URL path, see
+    /// [`AssetSet::respond`]. Logical urls are answered as configured with
+    /// [`with_logical_urls`](Self::with_logical_urls), other unknown urls get 404.
+    ///
+    /// With [`LanguageNegotiation::with_path_segment`], a url like `/fr/style.css` serves
+    /// `/style.css` with the language from the first path segment.
+    pub fn respond(&self, url_path: &str, headers: &RequestHeaders) -> AssetResponse<'_> {
+        if self.resolve_url(url_path).is_none()
+            && let Some((lang, url_path)) = self.split_language_segment(url_path)
+        {
+            // The redirect keeps the language segment, which would be negotiated otherwise
+            if self.logical_url_mode == LogicalUrls::Redirect
+                && let Some(hashed_url_path) = self.resolve_url(&url_path)
+                && hashed_url_path != url_path
+            {
+                let rest = &hashed_url_path[self.prefix.len()..];
+                return AssetResponse::redirect(&format!("{}/{lang}{rest}", self.prefix));
+            }
+            return self.respond(&url_path, &headers.language(lang));
+        }
+
+        let Some(hashed_url_path) = self.resolve_url(url_path) else {
+            return AssetResponse::not_found();
+        };
+        if hashed_url_path != url_path && self.logical_url_mode == LogicalUrls::Redirect {
+            return AssetResponse::redirect(hashed_url_path);
+        }
+
+        let entry = &self.assets[hashed_url_path];
+        if !self.is_intact(entry) {
+            return AssetResponse::new(500);
+        }
+        let response =
+            entry
+                .asset_set()
+                .respond_with_languages(entry.provider(), headers, &self.languages);
+        if hashed_url_path == url_path {
+            response
+        } else {
+            // The content behind a logical url changes with each build
+            response.replace_header("Cache-Control", CACHE_CONTROL_REVALIDATE)
+        }
+    }
+
+    /// Checks if [`respond`](Self::respond) serves an asset for the request url, through
+    /// its hashed or logical url or with a language path segment
+    pub fn handles_url(&self, url_path: &str) -> bool {
+        self.resolve_url(url_path).is_some() || self.split_language_segment(url_path).is_some()
+    }
+
+    /// Splits the language segment off a url like `/static/fr/style.css`, returning
+    /// `fr` and `/static/style.css` when that url is in the catalog and translated to `fr`.
+    ///
+    /// Other first segments, like `api` in `/api/style.css`, are not languages.
+    fn split_language_segment<'u>(&self, url_path: &'u str) -> Option<(&'u str, String)> {
+        if !self.languages.uses_path_segment() {
+            return None;
+        }
+        let rest = url_path.strip_prefix(self.prefix.as_str())?;
+        let (segment, rest) = rest.strip_prefix('/')?.split_once('/')?;
+        let lang = segment.parse::<icu_locid::LanguageIdentifier>().ok()?;
+        let url_path = format!("{}/{rest}", self.prefix);
+        let entry = &self.assets[self.resolve_url(&url_path)?];
+        entry
+            .asset_set()
+            .available_languages?
+            .contains(&lang)
+            .then_some((segment, url_path))
+    }
+
+    /// Looks up the asset for an HTML tag by its hashed or logical url, returning the
+    /// hashed url
+    fn tag_entry(&self, url_path: &str) -> Option<(&str, &CatalogEntry)> {
+        let url_path = match self.assets.contains_key(url_path) {
+            true => url_path,
+            false => self.url_for_logical(url_path)?,
+        };
+        self.assets
+            .get_key_value(url_path)
+            .map(|(url, entry)| (url.as_ref(), entry))
+    }
+
+    #[cfg_attr(not(feature = "integrity"), allow(unused_variables))]
+    fn tag_integrity<'e>(&self, entry: &'e CatalogEntry) -> Option<&'e str> {
+        #[cfg(feature = "integrity")]
+        if self.subresource_integrity {
+            return entry
+                .sri
+                .get_or_init(|| entry.asset_set().subresource_integrity(entry.provider()))
+                .as_deref();
+        }
+        None
+    }
+
+    /// Renders the preload tag for the asset at the hashed or logical url,
+    /// see [`AssetSet::preload_tag`]
+    pub fn preload_tag(&self, url_path: &str) -> Option<String> {
+        let (url, entry) = self.tag_entry(url_path)?;
+        entry
+            .asset_set()
+            .render_preload_tag(url, self.tag_integrity(entry))
     }
 
-    /// Looks up an AssetSet by URL path
-    pub fn get_asset_set(&self, url_path: &str) -> Option<&'static AssetSet> {
-        self.assets.get(url_path).copied()
+    /// Renders `<link rel="stylesheet">` for the CSS asset at the hashed or logical url
+    pub fn stylesheet_tag(&self, url_path: &str) -> Option<String> {
+        let (url, entry) = self.tag_entry(url_path)?;
+        entry
+            .asset_set()
+            .render_stylesheet_tag(url, self.tag_integrity(entry))
     }
 
-    /// Returns an iterator over all URL paths in the catalog
-    pub fn urls(&self) -> impl Iterator<Item = &'static str> + '_ {
-        self.assets.keys().copied()
+    /// Renders `<script type="module">` for the JavaScript asset at the hashed or logical url
+    pub fn script_tag(&self, url_path: &str) -> Option<String> {
+        let (url, entry) = self.tag_entry(url_path)?;
+        entry
+            .asset_set()
+            .render_script_tag(url, self.tag_integrity(entry))
     }
 
-    /// Returns an iterator over all AssetSets in the catalog
-    pub fn asset_sets(&self) -> impl Iterator<Item = &'static AssetSet> + '_ {
-        self.assets.values().copied()
+    /// The `Link` header value preloading the asset at the hashed or logical url.
+    /// Values for several assets are joined with `, ` into one header.
+    pub fn link_header(&self, url_path: &str) -> Option<String> {
+        let (url, entry) = self.tag_entry(url_path)?;
+        entry.asset_set().render_link_header(url)
+    }
+
+    /// Returns an iterator over all URL paths in the catalog, including the mount prefix
+    pub fn urls(&self) -> impl Iterator<Item = &str> + '_ {
+        self.assets.keys().map(AsRef::as_ref)
+    }
+
+    /// Returns an iterator over all AssetSets in the catalog, with the providers of
+    /// this catalog
+    pub fn asset_sets(&self) -> impl Iterator<Item = AssetSet<'_>> {
+        self.assets.values().map(CatalogEntry::bound_asset_set)
     }
 
     /// Returns the number of assets in the catalog
@@ -60,11 +547,11 @@ impl AssetCatalog {
     }
 
     /// Returns a list of all available MIME types in the catalog
-    pub fn mime_types(&self) -> Vec<&'static str> {
+    pub fn mime_types(&self) -> Vec<&str> {
         let mut mime_types: Vec<_> = self
             .assets
             .values()
-            .map(|asset_set| asset_set.mime_type())
+            .map(|entry| entry.asset_set().mime_type())
             .collect();
         mime_types.sort_unstable();
         mime_types.dedup();
@@ -75,17 +562,54 @@ impl AssetCatalog {
     pub fn assets_by_mime_type<'a>(
         &'a self,
         mime_type: &'a str,
-    ) -> impl Iterator<Item = &'static AssetSet> + 'a {
+    ) -> impl Iterator<Item = AssetSet<'a>> {
         self.assets
             .values()
+            .map(CatalogEntry::bound_asset_set)
             .filter(move |asset_set| asset_set.mime_type() == mime_type)
-            .copied()
+    }
+
+    /// Merges the assets of another catalog into this one, at the urls they have
+    /// in the other catalog (including its mount prefix).
+    ///
+    /// Urls that both catalogs serve with a different asset or provider, and logical
+    /// urls that lead to different assets, keep the asset of this catalog and are
+    /// returned as conflicts. The logical urls of assets that are not merged are left out.
+    ///
+    /// The settings of this catalog apply to the merged assets: its language negotiation,
+    /// logical url mode and integrity checks. The ones of the other catalog are dropped,
+    /// so configure them on the catalog that the other one is merged into.
+    pub fn merge(&mut self, other: AssetCatalog) -> Vec<String> {
+        let mut conflicts = Vec::new();
+        for (url, entry) in other.assets {
+            match self.assets.get(&url) {
+                Some(existing) if existing.same_as(&entry) => {}
+                Some(_) => conflicts.push(url.into_owned()),
+                None => {
+                    self.assets.insert(url, entry);
+                }
+            }
+        }
+        for (logical_url, url) in other.logical_urls {
+            if conflicts.iter().any(|conflict| *conflict == url) {
+                continue;
+            }
+            match self.logical_urls.get(&logical_url) {
+                Some(existing) if *existing == url => {}
+                Some(_) => conflicts.push(logical_url),
+                None => {
+                    self.logical_urls.insert(logical_url, url);
+                }
+            }
+        }
+        conflicts
     }
 
     /// Joins another AssetCatalog into this one, combining all assets
     ///
-    /// If both catalogs contain assets with the same URL path, the other catalog's asset
-    /// will overwrite the existing one in this catalog.
+    /// Fails if both catalogs serve different assets at the same URL path, see
+    /// [`merge`](Self::merge) to keep the assets of this catalog instead. Like with
+    /// `merge`, the settings of this catalog apply to all assets.
     ///
     /// # Arguments
     /// * `other` - The other catalog to merge into this one
@@ -93,15 +617,17 @@ impl AssetCatalog {
     /// # Example
     /// ```
     /// use builder_assets::AssetCatalog;
-    /// let mut catalog1 = AssetCatalog::new();
-    /// let catalog2 = AssetCatalog::new();
-    /// catalog1.join(catalog2);
+    /// let catalog1 = AssetCatalog::new();
+    /// let catalog2 = AssetCatalog::new().with_prefix("/admin");
+    /// let catalog = catalog1.join(catalog2).unwrap();
     /// ```
-    pub fn join(mut self, other: AssetCatalog) -> AssetCatalog {
-        for (url_path, asset_set) in other.assets {
-            self.assets.insert(url_path, asset_set);
+    pub fn join(mut self, other: AssetCatalog) -> Result<AssetCatalog, JoinConflict> {
+        let urls = self.merge(other);
+        if urls.is_empty() {
+            Ok(self)
+        } else {
+            Err(JoinConflict { urls })
         }
-        self
     }
 }
 
@@ -114,7 +640,7 @@ impl Default for AssetCatalog {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::{encoding::Encoding, file_path::FilePathParts};
+    use crate::{encoding::Encoding, file_path::FilePathParts, format::ImageFormat};
 
     // Mock provider for testing
     static MOCK_PROVIDER: fn(&str) -> Option<Vec<u8>> = mock_provider;
@@ -146,6 +672,8 @@ mod tests {
             file_path_parts: STYLE_PARTS,
             available_encodings: &STYLE_ENCODINGS,
             available_languages: None,
+            available_formats: None,
+            available_themes: None,
             mime: "text/css",
             provider: &MOCK_PROVIDER,
         };
@@ -179,6 +707,8 @@ mod tests {
             file_path_parts: SCRIPT_PARTS,
             available_encodings: &SCRIPT_ENCODINGS,
             available_languages: None,
+            available_formats: None,
+            available_themes: None,
             mime: "application/javascript",
             provider: &MOCK_PROVIDER,
         };
@@ -213,6 +743,8 @@ mod tests {
             file_path_parts: CSS_PARTS,
             available_encodings: &CSS_ENCODINGS,
             available_languages: None,
+            available_formats: None,
+            available_themes: None,
             mime: "text/css",
             provider: &MOCK_PROVIDER,
         };
@@ -229,6 +761,8 @@ mod tests {
             file_path_parts: JS_PARTS,
             available_encodings: &JS_ENCODINGS,
             available_languages: None,
+            available_formats: None,
+            available_themes: None,
             mime: "application/javascript",
             provider: &MOCK_PROVIDER,
         };
@@ -261,6 +795,8 @@ mod tests {
             file_path_parts: CSS_PARTS,
             available_encodings: &CSS_ENCODINGS,
             available_languages: None,
+            available_formats: None,
+            available_themes: None,
             mime: "text/css",
             provider: &MOCK_PROVIDER,
         };
@@ -277,6 +813,8 @@ mod tests {
             file_path_parts: IMG_PARTS,
             available_encodings: &IMG_ENCODINGS,
             available_languages: None,
+            available_formats: None,
+            available_themes: None,
             mime: "image/png",
             provider: &MOCK_PROVIDER,
         };
@@ -308,6 +846,8 @@ mod tests {
             file_path_parts: PARTS1,
             available_encodings: &ENCODINGS1,
             available_languages: None,
+            available_formats: None,
+            available_themes: None,
             mime: "text/css",
             provider: &MOCK_PROVIDER,
         };
@@ -324,6 +864,8 @@ mod tests {
             file_path_parts: PARTS2,
             available_encodings: &ENCODINGS2,
             available_languages: None,
+            available_formats: None,
+            available_themes: None,
             mime: "application/javascript",
             provider: &MOCK_PROVIDER,
         };
@@ -354,6 +896,8 @@ mod tests {
             file_path_parts: CSS_PARTS,
             available_encodings: &CSS_ENCODINGS,
             available_languages: None,
+            available_formats: None,
+            available_themes: None,
             mime: "text/css",
             provider: &MOCK_PROVIDER,
         };
@@ -376,6 +920,8 @@ mod tests {
             file_path_parts: JS_PARTS,
             available_encodings: &JS_ENCODINGS,
             available_languages: None,
+            available_formats: None,
+            available_themes: None,
             mime: "application/javascript",
             provider: &MOCK_PROVIDER,
         };
@@ -384,7 +930,7 @@ mod tests {
         assert_eq!(catalog2.len(), 1);
 
         // Join catalog2 into catalog1
-        let catalog1 = catalog1.join(catalog2);
+        let catalog1 = catalog1.join(catalog2).unwrap();
 
         // Verify the joined catalog contains both assets
         assert_eq!(catalog1.len(), 2);
@@ -397,7 +943,7 @@ mod tests {
     }
 
     #[test]
-    fn test_catalog_join_overwrites_duplicates() {
+    fn test_catalog_join_reports_conflicts() {
         // Create first catalog with an asset
         let mut catalog1 = AssetCatalog::new();
 
@@ -413,6 +959,8 @@ mod tests {
             file_path_parts: ORIGINAL_PARTS,
             available_encodings: &ORIGINAL_ENCODINGS,
             available_languages: None,
+            available_formats: None,
+            available_themes: None,
             mime: "text/css",
             provider: &MOCK_PROVIDER,
         };
@@ -434,18 +982,474 @@ mod tests {
             file_path_parts: UPDATED_PARTS,
             available_encodings: &UPDATED_ENCODINGS,
             available_languages: None,
+            available_formats: None,
+            available_themes: None,
             mime: "text/css",
             provider: &MOCK_PROVIDER,
         };
 
         catalog2.add_asset(&UPDATED_ASSET);
 
-        // Join catalog2 into catalog1 - should overwrite
-        let catalog1 = catalog1.join(catalog2);
+        // Joining different assets at the same URL fails
+        let mut duplicate = AssetCatalog::new();
+        duplicate.add_asset(&ORIGINAL_ASSET);
+        let conflict = catalog1.join(catalog2).unwrap_err();
+        assert_eq!(conflict.urls, vec!["/style.css"]);
+        assert_eq!(
+            conflict.to_string(),
+            "Asset urls served by both catalogs: /style.css"
+        );
 
-        // Verify the catalog still has 1 asset but with updated content
-        assert_eq!(catalog1.len(), 1);
+        // Merging keeps the existing asset and reports the conflict
+        let mut catalog1 = AssetCatalog::new();
+        catalog1.add_asset(&ORIGINAL_ASSET);
+        let mut catalog2 = AssetCatalog::new();
+        catalog2.add_asset(&UPDATED_ASSET);
+        assert_eq!(catalog1.merge(catalog2), vec!["/style.css"]);
         let asset_set = catalog1.get_asset_set("/style.css").unwrap();
-        assert_eq!(asset_set.file_path_parts.name, "updated"); // Should be the new one
+        assert_eq!(asset_set.file_path_parts.name, "original");
+
+        // The same asset from the same provider is not a conflict
+        let catalog1 = catalog1.join(duplicate).unwrap();
+        assert_eq!(catalog1.len(), 1);
+    }
+
+    /// Provider with runtime state, standing in for e.g. a FileSystemProvider
+    #[derive(Debug)]
+    struct SiteProvider(&'static str);
+    impl AssetProvider for SiteProvider {
+        fn load(&self, path: &str) -> Option<std::borrow::Cow<'_, [u8]>> {
+            Some(format!("{}:{path}", self.0).into_bytes().into())
+        }
+    }
+
+    static OTHER_PROVIDER: fn(&str) -> Option<Vec<u8>> = other_provider;
+    fn other_provider(_path: &str) -> Option<Vec<u8>> {
+        Some(b"other data".to_vec())
+    }
+
+    static PAGE_PARTS: FilePathParts = FilePathParts {
+        folder: None,
+        name: "page",
+        hash: None,
+        ext: "html",
+    };
+    static FONT_PARTS: FilePathParts = FilePathParts {
+        folder: None,
+        name: "font",
+        hash: None,
+        ext: "woff2",
+    };
+    static IDENTITY: [Encoding; 1] = [Encoding::Identity];
+    static PAGE_ASSET: AssetSet = AssetSet {
+        url_path: "/page.html",
+        file_path_parts: PAGE_PARTS,
+        available_encodings: &IDENTITY,
+        available_languages: None,
+        available_formats: None,
+        available_themes: None,
+        mime: "text/html",
+        provider: &MOCK_PROVIDER,
+    };
+    static FONT_ASSET: AssetSet = AssetSet {
+        url_path: "/font.woff2",
+        file_path_parts: FONT_PARTS,
+        available_encodings: &IDENTITY,
+        available_languages: None,
+        available_formats: None,
+        available_themes: None,
+        mime: "font/woff2",
+        provider: &OTHER_PROVIDER,
+    };
+    static ASSETS: [&AssetSet; 2] = [&PAGE_ASSET, &FONT_ASSET];
+
+    #[test]
+    fn test_catalog_with_provider() {
+        let site_a = AssetCatalog::from_assets(&ASSETS).with_provider(SiteProvider("a"));
+        let site_b = AssetCatalog::from_assets(&ASSETS).with_provider(SiteProvider("b"));
+
+        let asset = site_a.asset_for("/page.html", None, None).unwrap();
+        assert_eq!(asset.data_for(), b"a:page.html");
+        let asset = site_b.asset_for("/page.html", None, None).unwrap();
+        assert_eq!(asset.data_for(), b"b:page.html");
+
+        // The AssetSets returned by the catalog load through its provider
+        let asset = site_a
+            .get_asset_set("/page.html")
+            .unwrap()
+            .asset_for(None, None);
+        assert_eq!(asset.unwrap().data_for(), b"a:page.html");
+        assert_eq!(
+            PAGE_ASSET.asset_for(None, None).unwrap().data_for(),
+            b"mock data"
+        );
+    }
+
+    #[test]
+    fn test_catalog_replace_provider() {
+        let catalog =
+            AssetCatalog::from_assets(&ASSETS).replace_provider(&OTHER_PROVIDER, SiteProvider("a"));
+
+        let asset = catalog.asset_for("/page.html", None, None).unwrap();
+        assert_eq!(asset.data_for(), b"mock data");
+        let asset = catalog.asset_for("/font.woff2", None, None).unwrap();
+        assert_eq!(asset.data_for(), b"a:font.woff2");
+
+        assert!(catalog.asset_for("/missing.css", None, None).is_none());
+        assert!(catalog.provider_for("/missing.css").is_none());
+    }
+
+    #[test]
+    fn test_catalog_respond() {
+        let catalog = AssetCatalog::from_assets(&ASSETS).with_provider(SiteProvider("a"));
+
+        let response = catalog.respond("/page.html", &RequestHeaders::new());
+        assert_eq!(response.status, 200);
+        assert_eq!(response.header_value("Content-Type"), Some("text/html"));
+        assert_eq!(response.asset.unwrap().data_for(), b"a:page.html");
+
+        let response = catalog.respond("/missing.html", &RequestHeaders::new());
+        assert_eq!(response.status, 404);
+        assert!(response.asset.is_none());
+    }
+
+    #[test]
+    fn test_catalog_join_keeps_providers() {
+        let mut site = AssetCatalog::new();
+        site.add_asset(&PAGE_ASSET);
+        let site = site.with_provider(SiteProvider("a"));
+
+        let mut fonts = AssetCatalog::new();
+        fonts.add_asset(&FONT_ASSET);
+
+        let catalog = site.join(fonts).unwrap();
+        let asset = catalog.asset_for("/page.html", None, None).unwrap();
+        assert_eq!(asset.data_for(), b"a:page.html");
+        let asset = catalog.asset_for("/font.woff2", None, None).unwrap();
+        assert_eq!(asset.data_for(), b"other data");
+    }
+
+    static HASHED_PARTS: FilePathParts = FilePathParts {
+        folder: Some("js"),
+        name: "app",
+        hash: Some("hash123="),
+        ext: "js",
+    };
+    static HASHED_ASSET: AssetSet = AssetSet {
+        url_path: "/js/app.hash123=.js",
+        file_path_parts: HASHED_PARTS,
+        available_encodings: &IDENTITY,
+        available_languages: None,
+        available_formats: None,
+        available_themes: None,
+        mime: "application/javascript",
+        provider: &MOCK_PROVIDER,
+    };
+    static HASHED_ASSETS: [&AssetSet; 2] = [&HASHED_ASSET, &PAGE_ASSET];
+
+    #[test]
+    fn test_asset_for_request_negotiates_format() {
+        static LOGO_PARTS: FilePathParts = FilePathParts {
+            folder: Some("img"),
+            name: "logo",
+            hash: None,
+            ext: "png",
+        };
+        static LOGO_ASSET: AssetSet = AssetSet {
+            url_path: "/img/logo.png",
+            file_path_parts: LOGO_PARTS,
+            available_encodings: &IDENTITY,
+            available_languages: None,
+            available_formats: Some(&[ImageFormat::Avif, ImageFormat::WebP]),
+            available_themes: None,
+            mime: "image/png",
+            provider: &MOCK_PROVIDER,
+        };
+        let mut catalog = AssetCatalog::new();
+        catalog.add_asset(&LOGO_ASSET);
+
+        let headers = RequestHeaders::new().accept("image/webp,*/*");
+        let asset = catalog
+            .asset_for_request("/img/logo.png", &headers)
+            .unwrap();
+        assert_eq!(asset.file_path(), "img/logo.png.webp");
+
+        let asset = catalog.asset_for("/img/logo.png", None, None).unwrap();
+        assert_eq!(asset.file_path(), "img/logo.png");
+    }
+
+    #[test]
+    fn test_merge_logical_url_conflicts() {
+        static REHASHED_PARTS: FilePathParts = FilePathParts {
+            hash: Some("hash456="),
+            ..HASHED_PARTS
+        };
+        static REHASHED_ASSET: AssetSet = AssetSet {
+            url_path: "/js/app.hash456=.js",
+            file_path_parts: REHASHED_PARTS,
+            ..HASHED_ASSET
+        };
+        // Served at the hashed url of HASHED_ASSET, without a logical url of its own
+        static UNHASHED_PARTS: FilePathParts = FilePathParts {
+            name: "app.hash123=",
+            hash: None,
+            ..HASHED_PARTS
+        };
+        static UNHASHED_ASSET: AssetSet = AssetSet {
+            file_path_parts: UNHASHED_PARTS,
+            ..HASHED_ASSET
+        };
+
+        // Both versions are merged, but the logical url keeps leading to this catalog's
+        let mut catalog = AssetCatalog::new();
+        catalog.add_asset(&HASHED_ASSET);
+        let mut other = AssetCatalog::new();
+        other.add_asset(&REHASHED_ASSET);
+        assert_eq!(catalog.merge(other), vec!["/js/app.js"]);
+        assert!(catalog.contains_url("/js/app.hash456=.js"));
+        assert_eq!(
+            catalog.url_for_logical("/js/app.js"),
+            Some("/js/app.hash123=.js")
+        );
+
+        // The logical url of a rejected asset is not added
+        let mut catalog = AssetCatalog::new();
+        catalog.add_asset(&UNHASHED_ASSET);
+        let mut other = AssetCatalog::new();
+        other.add_asset(&HASHED_ASSET);
+        assert_eq!(catalog.merge(other), vec!["/js/app.hash123=.js"]);
+        assert_eq!(catalog.url_for_logical("/js/app.js"), None);
+    }
+
+    #[test]
+    fn test_logical_url_lookup() {
+        let catalog = AssetCatalog::from_assets(&HASHED_ASSETS);
+
+        assert_eq!(
+            catalog.url_for_logical("/js/app.js"),
+            Some("/js/app.hash123=.js")
+        );
+        assert_eq!(catalog.url_for_logical("/page.html"), Some("/page.html"));
+        assert!(catalog.url_for_logical("/js/app.hash123=.js").is_none());
+        assert_eq!(
+            catalog
+                .get_asset_set_by_logical("/js/app.js")
+                .unwrap()
+                .url_path,
+            "/js/app.hash123=.js"
+        );
+
+        // Logical urls are not served by default
+        assert!(catalog.resolve_url("/js/app.js").is_none());
+        assert_eq!(
+            catalog.respond("/js/app.js", &RequestHeaders::new()).status,
+            404
+        );
+    }
+
+    #[test]
+    fn test_logical_url_redirect_and_rewrite() {
+        let catalog =
+            AssetCatalog::from_assets(&HASHED_ASSETS).with_logical_urls(LogicalUrls::Redirect);
+        let response = catalog.respond("/js/app.js", &RequestHeaders::new());
+        assert_eq!(response.status, 302);
+        assert_eq!(
+            response.header_value("Location"),
+            Some("/js/app.hash123=.js")
+        );
+        assert!(response.asset.is_none());
+
+        let catalog =
+            AssetCatalog::from_assets(&HASHED_ASSETS).with_logical_urls(LogicalUrls::Rewrite);
+        let response = catalog.respond("/js/app.js", &RequestHeaders::new());
+        assert_eq!(response.status, 200);
+        assert_eq!(response.header_value("Cache-Control"), Some("no-cache"));
+        assert_eq!(response.asset.unwrap().data_for(), b"mock data");
+
+        // The hashed url is still cached as immutable
+        let response = catalog.respond("/js/app.hash123=.js", &RequestHeaders::new());
+        assert_eq!(
+            response.header_value("Cache-Control"),
+            Some(crate::response::CACHE_CONTROL_IMMUTABLE)
+        );
+    }
+
+    #[test]
+    fn test_catalog_with_prefix() {
+        let catalog = AssetCatalog::from_assets(&HASHED_ASSETS)
+            .with_prefix("/static/")
+            .with_logical_urls(LogicalUrls::Redirect);
+        assert_eq!(catalog.prefix(), "/static");
+        assert!(catalog.contains_url("/static/page.html"));
+        assert!(!catalog.contains_url("/page.html"));
+        assert_eq!(
+            catalog.url_for_logical("/static/js/app.js"),
+            Some("/static/js/app.hash123=.js")
+        );
+
+        let response = catalog.respond("/static/js/app.js", &RequestHeaders::new());
+        assert_eq!(
+            response.header_value("Location"),
+            Some("/static/js/app.hash123=.js")
+        );
+        drop(response);
+
+        assert!(catalog.is_under_prefix("/static/page.html"));
+        assert!(!catalog.is_under_prefix("/statics/page.html"));
+
+        // Nested mounts and assets added after mounting
+        let mut catalog = catalog.with_prefix("admin");
+        catalog.add_asset(&FONT_ASSET);
+        assert_eq!(catalog.prefix(), "/admin/static");
+        let urls: Vec<_> = catalog.urls().collect();
+        assert_eq!(
+            urls,
+            vec![
+                "/admin/static/font.woff2",
+                "/admin/static/js/app.hash123=.js",
+                "/admin/static/page.html"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_join_mounted_catalogs() {
+        let site = AssetCatalog::from_assets(&ASSETS).with_prefix("/static");
+        let admin = AssetCatalog::from_assets(&ASSETS)
+            .with_provider(SiteProvider("admin"))
+            .with_prefix("/admin/static");
+
+        let catalog = site.join(admin).unwrap();
+        assert_eq!(catalog.len(), 4);
+        let asset = catalog
+            .asset_for("/admin/static/page.html", None, None)
+            .unwrap();
+        assert_eq!(asset.data_for(), b"admin:page.html");
+        let asset = catalog.asset_for("/static/page.html", None, None).unwrap();
+        assert_eq!(asset.data_for(), b"mock data");
+    }
+
+    static BUTTON_PARTS: FilePathParts = FilePathParts {
+        folder: None,
+        name: "button",
+        hash: None,
+        ext: "css",
+    };
+    static BUTTON_LANGUAGES: [icu_locid::LanguageIdentifier; 3] = [
+        icu_locid::langid!("de"),
+        icu_locid::langid!("en"),
+        icu_locid::langid!("fr"),
+    ];
+    static BUTTON_ASSET: AssetSet = AssetSet {
+        url_path: "/button.css",
+        file_path_parts: BUTTON_PARTS,
+        available_encodings: &IDENTITY,
+        available_languages: Some(&BUTTON_LANGUAGES),
+        available_formats: None,
+        available_themes: None,
+        mime: "text/css",
+        provider: &MOCK_PROVIDER,
+    };
+    static TRANSLATED_ASSETS: [&AssetSet; 1] = [&BUTTON_ASSET];
+
+    #[test]
+    fn test_catalog_language_negotiation() {
+        use crate::language::LanguageReason;
+        use icu_locid::langid;
+
+        let negotiation = LanguageNegotiation::new()
+            .with_default_language(langid!("en"))
+            .with_cookie("lang")
+            .with_path_segment();
+        let catalog = AssetCatalog::from_assets(&TRANSLATED_ASSETS)
+            .with_prefix("/static")
+            .with_language_negotiation(negotiation);
+
+        let headers = RequestHeaders::new().accept_language("es");
+        let response = catalog.respond("/static/button.css", &headers);
+        assert_eq!(response.header_value("Content-Language"), Some("en"));
+        assert_eq!(
+            response.header_value("Vary"),
+            Some("Accept-Encoding, Accept-Language, Cookie")
+        );
+        let language = response.language.unwrap();
+        assert_eq!(language.reason, LanguageReason::Default);
+
+        let asset = catalog
+            .asset_for("/static/button.css", None, Some("es"))
+            .unwrap();
+        assert_eq!(asset.lang, Some(langid!("en")));
+
+        // Language from the url path segment
+        let response = catalog.respond("/static/fr/button.css", &headers);
+        assert_eq!(response.status, 200);
+        assert_eq!(response.header_value("Content-Language"), Some("fr"));
+        assert_eq!(response.language.unwrap().reason, LanguageReason::Override);
+        assert!(catalog.handles_url("/static/fr/button.css"));
+        assert!(!catalog.handles_url("/static/fr/missing.css"));
+        // Only the languages of the asset are path segments
+        assert!(!catalog.handles_url("/static/es/button.css"));
+        assert!(!catalog.handles_url("/static/api/button.css"));
+        assert_eq!(
+            catalog.respond("/static/api/button.css", &headers).status,
+            404
+        );
+        assert_eq!(
+            catalog.respond("/static/fr/missing.css", &headers).status,
+            404
+        );
+    }
+
+    static HASHED_BUTTON_ASSET: AssetSet = AssetSet {
+        url_path: "/button.abc=.css",
+        file_path_parts: FilePathParts {
+            hash: Some("abc="),
+            ..BUTTON_PARTS
+        },
+        ..BUTTON_ASSET
+    };
+    static HASHED_TRANSLATED_ASSETS: [&AssetSet; 1] = [&HASHED_BUTTON_ASSET];
+
+    #[test]
+    fn test_language_segment_with_logical_url() {
+        let catalog = AssetCatalog::from_assets(&HASHED_TRANSLATED_ASSETS)
+            .with_prefix("/static")
+            .with_language_negotiation(LanguageNegotiation::new().with_path_segment())
+            .with_logical_urls(LogicalUrls::Redirect);
+
+        let headers = RequestHeaders::new().accept_language("de");
+        let response = catalog.respond("/static/fr/button.css", &headers);
+        assert_eq!(response.status, 302);
+        assert_eq!(
+            response.header_value("Location"),
+            Some("/static/fr/button.abc=.css")
+        );
+        let response = catalog.respond("/static/fr/button.abc=.css", &headers);
+        assert_eq!(response.status, 200);
+        assert_eq!(response.header_value("Content-Language"), Some("fr"));
+    }
+
+    #[test]
+    fn test_join_keeps_settings_of_self() {
+        let translated = AssetCatalog::from_assets(&HASHED_TRANSLATED_ASSETS)
+            .with_language_negotiation(LanguageNegotiation::new().with_path_segment())
+            .with_logical_urls(LogicalUrls::Redirect);
+        let headers = RequestHeaders::new();
+        assert_eq!(
+            translated.respond("/fr/button.abc=.css", &headers).status,
+            200
+        );
+        assert_eq!(translated.respond("/button.css", &headers).status, 302);
+
+        // The settings of the translated catalog are dropped
+        let catalog = AssetCatalog::from_assets(&ASSETS).join(translated).unwrap();
+        assert_eq!(catalog.respond("/fr/button.abc=.css", &headers).status, 404);
+        assert_eq!(catalog.respond("/button.css", &headers).status, 404);
+
+        let catalog = catalog
+            .with_language_negotiation(LanguageNegotiation::new().with_path_segment())
+            .with_logical_urls(LogicalUrls::Redirect);
+        assert_eq!(catalog.respond("/fr/button.abc=.css", &headers).status, 200);
+        assert_eq!(catalog.respond("/button.css", &headers).status, 302);
     }
 }
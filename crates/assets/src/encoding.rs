@@ -2,6 +2,7 @@ use std::fmt::Display;
 
 /// File encodings in order of preference
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "manifest", derive(serde::Serialize, serde::Deserialize))]
 pub enum Encoding {
     Brotli,
     Gzip,
@@ -1,22 +1,49 @@
-use crate::encoding::Encoding;
+use crate::{encoding::Encoding, format::ImageFormat};
 use icu_locid::LanguageIdentifier;
 
 /// The file path parts allows constructing a full path given encoding and optionally a language
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
-pub struct FilePathParts {
+pub struct FilePathParts<'a> {
     /// relative folder
-    pub folder: Option<&'static str>,
-    pub name: &'static str,
-    pub hash: Option<&'static str>,
-    pub ext: &'static str,
+    pub folder: Option<&'a str>,
+    pub name: &'a str,
+    pub hash: Option<&'a str>,
+    pub ext: &'a str,
 }
 
-impl FilePathParts {
+impl FilePathParts<'_> {
     /// Constructs a file path for the given encoding and optional language.
     ///
     /// Regular files: `folder/name[.hash].ext[.encoding_ext]`
     /// Translated files: `folder/name[.hash].ext/lang.ext[.encoding_ext]`
     pub fn construct_path(&self, encoding: Encoding, lang: Option<&LanguageIdentifier>) -> String {
+        self.construct_variant_path(encoding, lang, None)
+    }
+
+    /// Constructs a file path like [`construct_path`](Self::construct_path), for an
+    /// alternative image format when given: `folder/name[.hash].ext.format_ext[.encoding_ext]`
+    pub fn construct_variant_path(
+        &self,
+        encoding: Encoding,
+        lang: Option<&LanguageIdentifier>,
+        format: Option<ImageFormat>,
+    ) -> String {
+        let lang = lang.map(|lang| lang.to_string());
+        self.construct(encoding, lang.as_deref(), format)
+    }
+
+    /// Constructs the file path of a theme variant, which is stored like a translation:
+    /// `folder/name[.hash].ext/theme.ext[.encoding_ext]`
+    pub fn construct_theme_path(&self, encoding: Encoding, theme: &str) -> String {
+        self.construct(encoding, Some(theme), None)
+    }
+
+    fn construct(
+        &self,
+        encoding: Encoding,
+        variant: Option<&str>,
+        format: Option<ImageFormat>,
+    ) -> String {
         let mut path = String::new();
 
         // Add folder if present
@@ -25,34 +52,28 @@ impl FilePathParts {
             path.push('/');
         }
 
-        if let Some(lang) = lang {
-            // Translated file: folder/name[.hash].ext/lang.ext[.encoding_ext]
-            path.push_str(self.name);
-
-            // Add hash if present
-            if let Some(hash) = self.hash {
-                path.push('.');
-                path.push_str(hash);
-            }
+        path.push_str(self.name);
 
+        // Add hash if present
+        if let Some(hash) = self.hash {
             path.push('.');
-            path.push_str(self.ext);
+            path.push_str(hash);
+        }
+
+        path.push('.');
+        path.push_str(self.ext);
+
+        // Translated or themed file: folder/name[.hash].ext/variant.ext[.encoding_ext]
+        if let Some(variant) = variant {
             path.push('/');
-            path.push_str(&lang.to_string());
+            path.push_str(variant);
             path.push('.');
             path.push_str(self.ext);
-        } else {
-            // Regular file: folder/name[.hash].ext[.encoding_ext]
-            path.push_str(self.name);
-
-            // Add hash if present
-            if let Some(hash) = self.hash {
-                path.push('.');
-                path.push_str(hash);
-            }
+        }
 
+        if let Some(format) = format {
             path.push('.');
-            path.push_str(self.ext);
+            path.push_str(format.file_ending());
         }
 
         // Add encoding extension if needed
@@ -171,6 +192,40 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_image_format_variant() {
+        let parts = FilePathParts {
+            folder: Some("img"),
+            name: "logo",
+            hash: Some("abc123="),
+            ext: "png",
+        };
+
+        assert_eq!(
+            parts.construct_variant_path(Encoding::Identity, None, Some(ImageFormat::Avif)),
+            "img/logo.abc123=.png.avif"
+        );
+        assert_eq!(
+            parts.construct_variant_path(Encoding::Identity, None, None),
+            "img/logo.abc123=.png"
+        );
+    }
+
+    #[test]
+    fn test_theme_variant() {
+        let parts = FilePathParts {
+            folder: Some("css"),
+            name: "main",
+            hash: Some("abc123="),
+            ext: "css",
+        };
+
+        assert_eq!(
+            parts.construct_theme_path(Encoding::Brotli, "dark"),
+            "css/main.abc123=.css/dark.css.br"
+        );
+    }
+
     #[test]
     fn test_url_path_construction() {
         let parts = FilePathParts {
@@ -0,0 +1,55 @@
+use std::fmt::Display;
+
+/// Alternative image formats, served instead of the original format of an asset
+/// when the Accept header lists them. In order of preference.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "manifest", derive(serde::Serialize, serde::Deserialize))]
+pub enum ImageFormat {
+    Avif,
+    WebP,
+}
+
+impl Display for ImageFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.file_ending())
+    }
+}
+
+impl ImageFormat {
+    /// The ending appended to the file name of the original format,
+    /// e.g. `logo.png.avif` for `logo.png`
+    pub fn file_ending(&self) -> &'static str {
+        match self {
+            ImageFormat::Avif => "avif",
+            ImageFormat::WebP => "webp",
+        }
+    }
+
+    pub fn mime(&self) -> &'static str {
+        match self {
+            ImageFormat::Avif => "image/avif",
+            ImageFormat::WebP => "image/webp",
+        }
+    }
+
+    /// Returns the preference order for this format.
+    /// Lower numbers have higher preference.
+    pub fn preference_order(&self) -> u8 {
+        match self {
+            ImageFormat::Avif => 0,
+            ImageFormat::WebP => 1,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_image_format() {
+        assert_eq!(ImageFormat::Avif.to_string(), "avif");
+        assert_eq!(ImageFormat::WebP.mime(), "image/webp");
+        assert!(ImageFormat::Avif.preference_order() < ImageFormat::WebP.preference_order());
+    }
+}
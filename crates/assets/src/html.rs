@@ -0,0 +1,222 @@
+use crate::asset_set::AssetSet;
+use std::fmt::Write;
+
+impl AssetSet<'_> {
+    /// The request destination of the asset, used as the `as` attribute when preloading it.
+    /// None for assets that are not preloaded, like HTML pages.
+    pub fn preload_as(&self) -> Option<&'static str> {
+        match self.mime {
+            "text/css" => Some("style"),
+            "application/javascript" | "text/javascript" => Some("script"),
+            mime if mime.starts_with("font/") => Some("font"),
+            mime if mime.starts_with("image/") => Some("image"),
+            "application/json" | "application/wasm" | "application/manifest+json" => Some("fetch"),
+            _ => None,
+        }
+    }
+
+    /// Fonts and fetches are requested in CORS mode, so their preloads need `crossorigin`
+    /// to be reused by the request that follows
+    fn is_cors_request(&self) -> bool {
+        matches!(self.preload_as(), Some("font" | "fetch"))
+    }
+
+    fn is_script(&self) -> bool {
+        self.preload_as() == Some("script")
+    }
+
+    /// Renders `<link rel="preload">` for the asset, with the `as`, `type` and `crossorigin`
+    /// attributes the browser needs to reuse the preloaded response.
+    /// Scripts are preloaded as modules with `<link rel="modulepreload">`.
+    pub fn preload_tag(&self) -> Option<String> {
+        self.render_preload_tag(self.url_path, None)
+    }
+
+    /// Renders `<link rel="stylesheet">` for CSS assets
+    pub fn stylesheet_tag(&self) -> Option<String> {
+        self.render_stylesheet_tag(self.url_path, None)
+    }
+
+    /// Renders `<script type="module">` for JavaScript assets
+    pub fn script_tag(&self) -> Option<String> {
+        self.render_script_tag(self.url_path, None)
+    }
+
+    /// The value of a `Link` header preloading the asset, e.g. for 103 Early Hints
+    pub fn link_header(&self) -> Option<String> {
+        self.render_link_header(self.url_path)
+    }
+
+    pub(crate) fn render_preload_tag(&self, href: &str, integrity: Option<&str>) -> Option<String> {
+        let preload_as = self.preload_as()?;
+        let mut tag = if self.is_script() {
+            format!(r#"<link rel="modulepreload" href="{}""#, escape(href))
+        } else {
+            format!(
+                r#"<link rel="preload" href="{}" as="{preload_as}""#,
+                escape(href)
+            )
+        };
+        if preload_as == "font" {
+            write!(tag, r#" type="{}""#, self.mime).unwrap();
+        }
+        if self.is_cors_request() {
+            tag.push_str(" crossorigin");
+        }
+        push_integrity(&mut tag, integrity);
+        tag.push('>');
+        Some(tag)
+    }
+
+    pub(crate) fn render_stylesheet_tag(
+        &self,
+        href: &str,
+        integrity: Option<&str>,
+    ) -> Option<String> {
+        if self.preload_as() != Some("style") {
+            return None;
+        }
+        let mut tag = format!(r#"<link rel="stylesheet" href="{}""#, escape(href));
+        push_integrity(&mut tag, integrity);
+        tag.push('>');
+        Some(tag)
+    }
+
+    pub(crate) fn render_script_tag(&self, src: &str, integrity: Option<&str>) -> Option<String> {
+        if !self.is_script() {
+            return None;
+        }
+        let mut tag = format!(r#"<script type="module" src="{}""#, escape(src));
+        push_integrity(&mut tag, integrity);
+        tag.push_str("></script>");
+        Some(tag)
+    }
+
+    pub(crate) fn render_link_header(&self, href: &str) -> Option<String> {
+        let preload_as = self.preload_as()?;
+        let mut header = if self.is_script() {
+            format!("<{href}>; rel=modulepreload")
+        } else {
+            format!("<{href}>; rel=preload; as={preload_as}")
+        };
+        if preload_as == "font" {
+            write!(header, "; type=\"{}\"", self.mime).unwrap();
+        }
+        if self.is_cors_request() {
+            header.push_str("; crossorigin");
+        }
+        Some(header)
+    }
+}
+
+fn push_integrity(tag: &mut String, integrity: Option<&str>) {
+    if let Some(integrity) = integrity {
+        write!(tag, r#" integrity="{}""#, escape(integrity)).unwrap();
+    }
+}
+
+/// Escapes a value for a double quoted HTML attribute
+fn escape(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('"', "&quot;")
+        .replace('<', "&lt;")
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{AssetCatalog, AssetSet, Encoding, FilePathParts};
+
+    static PROVIDER: fn(&str) -> Option<Vec<u8>> = |_| None;
+    static IDENTITY: [Encoding; 1] = [Encoding::Identity];
+
+    fn asset_set(
+        url_path: &'static str,
+        ext: &'static str,
+        mime: &'static str,
+    ) -> AssetSet<'static> {
+        let parts = FilePathParts {
+            folder: None,
+            name: "asset",
+            hash: None,
+            ext,
+        };
+        AssetSet::new(url_path, parts, &IDENTITY, None, mime, &PROVIDER)
+    }
+
+    #[test]
+    fn test_tags_from_mime_type() {
+        let css = asset_set("/app.css", "css", "text/css");
+        assert_eq!(
+            css.stylesheet_tag().unwrap(),
+            r#"<link rel="stylesheet" href="/app.css">"#
+        );
+        assert_eq!(
+            css.preload_tag().unwrap(),
+            r#"<link rel="preload" href="/app.css" as="style">"#
+        );
+        assert!(css.script_tag().is_none());
+
+        let js = asset_set("/app.js", "js", "application/javascript");
+        assert_eq!(
+            js.script_tag().unwrap(),
+            r#"<script type="module" src="/app.js"></script>"#
+        );
+        assert_eq!(
+            js.preload_tag().unwrap(),
+            r#"<link rel="modulepreload" href="/app.js">"#
+        );
+        assert_eq!(js.link_header().unwrap(), "</app.js>; rel=modulepreload");
+
+        let font = asset_set("/inter.woff2", "woff2", "font/woff2");
+        assert_eq!(
+            font.preload_tag().unwrap(),
+            r#"<link rel="preload" href="/inter.woff2" as="font" type="font/woff2" crossorigin>"#
+        );
+        assert_eq!(
+            font.link_header().unwrap(),
+            r#"</inter.woff2>; rel=preload; as=font; type="font/woff2"; crossorigin"#
+        );
+        assert!(font.stylesheet_tag().is_none());
+
+        let html = asset_set("/index.html", "html", "text/html");
+        assert!(html.preload_tag().is_none());
+        assert!(html.link_header().is_none());
+    }
+
+    static APP_CSS: AssetSet = AssetSet {
+        url_path: "/css/app.abc123=.css",
+        file_path_parts: FilePathParts {
+            folder: Some("css"),
+            name: "app",
+            hash: Some("abc123="),
+            ext: "css",
+        },
+        available_encodings: &IDENTITY,
+        available_languages: None,
+        available_formats: None,
+        available_themes: None,
+        mime: "text/css",
+        provider: &PROVIDER,
+    };
+    static ASSETS: [&AssetSet; 1] = [&APP_CSS];
+
+    #[test]
+    fn test_catalog_tags() {
+        let catalog = AssetCatalog::from_assets(&ASSETS).with_prefix("/static");
+        let tag = r#"<link rel="stylesheet" href="/static/css/app.abc123=.css">"#;
+        assert_eq!(catalog.stylesheet_tag("/static/css/app.css").unwrap(), tag);
+        assert_eq!(
+            catalog
+                .stylesheet_tag("/static/css/app.abc123=.css")
+                .unwrap(),
+            tag
+        );
+        assert_eq!(
+            catalog.link_header("/static/css/app.css").unwrap(),
+            "</static/css/app.abc123=.css>; rel=preload; as=style"
+        );
+        assert!(catalog.script_tag("/static/css/app.css").is_none());
+        assert!(catalog.preload_tag("/css/app.css").is_none());
+    }
+}
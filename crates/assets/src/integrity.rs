@@ -0,0 +1,277 @@
+use crate::{asset_set::AssetSet, encoding::Encoding, provider::AssetProvider};
+use base64::{
+    Engine,
+    engine::general_purpose::{STANDARD, URL_SAFE},
+};
+use seahash::SeaHasher;
+use sha2::{Digest, Sha384};
+use std::{fmt, hash::Hasher, io};
+
+/// Why the files of an asset don't match the hash recorded at build time
+#[derive(Debug)]
+pub enum IntegrityError {
+    /// The hash is computed from the uncompressed files, which were not written
+    NoIdentityVariant,
+    /// A file the hash was computed from could not be loaded
+    Load { path: String, source: io::Error },
+    /// The files were modified or only partially deployed
+    Mismatch { expected: String, actual: String },
+}
+
+impl fmt::Display for IntegrityError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            IntegrityError::NoIdentityVariant => {
+                write!(f, "No uncompressed variant to verify the hash with")
+            }
+            IntegrityError::Load { path, source } => {
+                write!(f, "Could not load {path} to verify its hash: {source}")
+            }
+            IntegrityError::Mismatch { expected, actual } => {
+                write!(f, "Expected hash {expected} but the files hash to {actual}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for IntegrityError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            IntegrityError::Load { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
+
+/// Computes the hash builder puts in the file names of hashed assets, from the
+/// content of all files of the asset in order
+pub fn checksum<'a>(contents: impl IntoIterator<Item = &'a [u8]>) -> String {
+    let mut hasher = SeaHasher::new();
+    for content in contents {
+        hasher.write(content);
+    }
+    URL_SAFE.encode(hasher.finish().to_be_bytes())
+}
+
+/// Computes the `integrity` attribute value for Subresource Integrity, e.g. `sha384-...`
+pub fn subresource_integrity(content: &[u8]) -> String {
+    format!("sha384-{}", STANDARD.encode(Sha384::digest(content)))
+}
+
+impl AssetSet<'_> {
+    /// The `integrity` attribute for the HTML tags of this asset, computed from its
+    /// uncompressed content (browsers check the decoded response).
+    ///
+    /// None for assets with languages, image formats or themes, as the content served at the
+    /// url depends on the request, and for assets without an uncompressed variant.
+    pub fn subresource_integrity(&self, provider: &dyn AssetProvider) -> Option<String> {
+        if self.available_languages.is_some()
+            || self.available_formats.is_some()
+            || self.available_themes.is_some()
+            || !self.available_encodings.contains(&Encoding::Identity)
+        {
+            return None;
+        }
+        let path = self
+            .file_path_parts
+            .construct_path(Encoding::Identity, None);
+        let content = provider.try_load(&path).ok()?;
+        Some(subresource_integrity(&content))
+    }
+
+    /// Checks that the files loaded from the provider match the hash recorded at build time.
+    ///
+    /// The hash covers the uncompressed files: the file itself, all languages of a
+    /// translated file, all themes of a stylesheet, or the original and all alternative
+    /// formats of an image.
+    /// Compressed variants are written from the same content and are not checked.
+    /// Assets without a hash always pass.
+    pub fn verify(&self, provider: &dyn AssetProvider) -> Result<(), IntegrityError> {
+        let Some(expected) = self.file_path_parts.hash else {
+            return Ok(());
+        };
+        if !self.available_encodings.contains(&Encoding::Identity) {
+            return Err(IntegrityError::NoIdentityVariant);
+        }
+
+        let mut paths = Vec::new();
+        match (self.available_languages, self.available_themes) {
+            (Some(languages), _) => paths.extend(languages.iter().map(|lang| {
+                self.file_path_parts
+                    .construct_path(Encoding::Identity, Some(lang))
+            })),
+            (None, Some(themes)) => paths.extend(themes.iter().map(|theme| {
+                self.file_path_parts
+                    .construct_theme_path(Encoding::Identity, theme)
+            })),
+            (None, None) => {
+                paths.push(
+                    self.file_path_parts
+                        .construct_path(Encoding::Identity, None),
+                );
+                paths.extend(
+                    self.available_formats
+                        .unwrap_or_default()
+                        .iter()
+                        .map(|format| {
+                            self.file_path_parts.construct_variant_path(
+                                Encoding::Identity,
+                                None,
+                                Some(*format),
+                            )
+                        }),
+                );
+            }
+        }
+
+        let mut contents = Vec::with_capacity(paths.len());
+        for path in paths {
+            match provider.try_load(&path) {
+                Ok(content) => contents.push(content),
+                Err(source) => return Err(IntegrityError::Load { path, source }),
+            }
+        }
+        let actual = checksum(contents.iter().map(AsRef::as_ref));
+        if actual == expected {
+            Ok(())
+        } else {
+            Err(IntegrityError::Mismatch {
+                expected: expected.to_string(),
+                actual,
+            })
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{AssetCatalog, FilePathParts, RequestHeaders};
+    use icu_locid::{LanguageIdentifier, langid};
+
+    static PROVIDER: fn(&str) -> Option<Vec<u8>> = provider;
+    fn provider(path: &str) -> Option<Vec<u8>> {
+        match path {
+            "app.UoLMHvAokYQ=.js" => Some(b"console.log(1)".to_vec()),
+            "app.UoLMHvAokYQ=.js.br" => Some(b"compressed".to_vec()),
+            // Modified after the build
+            "lib.UoLMHvAokYQ=.js" => Some(b"console.log(2)".to_vec()),
+            "msg.2MM0Qv8f1Xk=.txt/en.txt" => Some(b"Hello".to_vec()),
+            _ => None,
+        }
+    }
+
+    static ENCODINGS: [Encoding; 2] = [Encoding::Identity, Encoding::Brotli];
+    static APP: AssetSet = AssetSet {
+        url_path: "/app.UoLMHvAokYQ=.js",
+        file_path_parts: FilePathParts {
+            folder: None,
+            name: "app",
+            hash: Some("UoLMHvAokYQ="),
+            ext: "js",
+        },
+        available_encodings: &ENCODINGS,
+        available_languages: None,
+        available_formats: None,
+        available_themes: None,
+        mime: "application/javascript",
+        provider: &PROVIDER,
+    };
+    static LIB: AssetSet = AssetSet {
+        url_path: "/lib.UoLMHvAokYQ=.js",
+        file_path_parts: FilePathParts {
+            folder: None,
+            name: "lib",
+            hash: Some("UoLMHvAokYQ="),
+            ext: "js",
+        },
+        available_encodings: &ENCODINGS,
+        available_languages: None,
+        available_formats: None,
+        available_themes: None,
+        mime: "application/javascript",
+        provider: &PROVIDER,
+    };
+    static LANGUAGES: [LanguageIdentifier; 2] = [langid!("en"), langid!("fr")];
+    static MSG: AssetSet = AssetSet {
+        url_path: "/msg.2MM0Qv8f1Xk=.txt",
+        file_path_parts: FilePathParts {
+            folder: None,
+            name: "msg",
+            hash: Some("2MM0Qv8f1Xk="),
+            ext: "txt",
+        },
+        available_encodings: &ENCODINGS,
+        available_languages: Some(&LANGUAGES),
+        available_formats: None,
+        available_themes: None,
+        mime: "text/plain",
+        provider: &PROVIDER,
+    };
+    static ASSETS: [&AssetSet; 3] = [&APP, &LIB, &MSG];
+
+    #[test]
+    fn test_verify() {
+        assert_eq!(checksum([b"console.log(1)".as_slice()]), "UoLMHvAokYQ=");
+        assert!(APP.verify(&PROVIDER).is_ok());
+        assert!(matches!(
+            LIB.verify(&PROVIDER),
+            Err(IntegrityError::Mismatch { .. })
+        ));
+        // The French translation was not deployed
+        let err = MSG.verify(&PROVIDER).unwrap_err();
+        assert!(
+            matches!(&err, IntegrityError::Load { path, .. } if path == "msg.2MM0Qv8f1Xk=.txt/fr.txt")
+        );
+    }
+
+    #[test]
+    fn test_subresource_integrity() {
+        assert_eq!(
+            subresource_integrity(b"alert('Hello, world.');"),
+            "sha384-H8BRh8j48O9oYatfu5AZzq6A9RINhZO5H16dQZngK7T62em8MUt1FLm52t+eX6xO"
+        );
+        assert!(APP.subresource_integrity(&PROVIDER).is_some());
+        assert!(MSG.subresource_integrity(&PROVIDER).is_none());
+
+        let catalog = AssetCatalog::from_assets(&ASSETS).with_subresource_integrity();
+        let expected = subresource_integrity(b"console.log(1)");
+        assert_eq!(
+            catalog.script_tag("/app.js").unwrap(),
+            format!(
+                r#"<script type="module" src="/app.UoLMHvAokYQ=.js" integrity="{expected}"></script>"#
+            )
+        );
+    }
+
+    #[test]
+    fn test_catalog_integrity_check() {
+        let catalog = AssetCatalog::from_assets(&ASSETS).with_integrity_check();
+        let headers = RequestHeaders::new().accept_encoding("br");
+
+        let mut response = catalog.respond("/app.UoLMHvAokYQ=.js", &headers);
+        assert_eq!(response.status, 200);
+        assert_eq!(response.body().unwrap(), b"compressed");
+
+        assert_eq!(
+            catalog.respond("/lib.UoLMHvAokYQ=.js", &headers).status,
+            500
+        );
+        assert!(
+            catalog
+                .asset_for("/lib.UoLMHvAokYQ=.js", Some("br"), None)
+                .is_none()
+        );
+
+        let failures = catalog.verify_all();
+        let urls: Vec<_> = failures.iter().map(|(url, _)| url.as_str()).collect();
+        assert_eq!(urls, vec!["/lib.UoLMHvAokYQ=.js", "/msg.2MM0Qv8f1Xk=.txt"]);
+
+        // Without the check, modified files are served
+        let catalog = AssetCatalog::from_assets(&ASSETS);
+        assert_eq!(
+            catalog.respond("/lib.UoLMHvAokYQ=.js", &headers).status,
+            200
+        );
+    }
+}
@@ -0,0 +1,279 @@
+use crate::{negotiation, response::RequestHeaders};
+use icu_locid::LanguageIdentifier;
+
+/// Why [`LanguageNegotiation`] chose a language
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LanguageReason {
+    /// Set explicitly with [`RequestHeaders::language`], the language cookie or a url path segment
+    Override,
+    /// Requested in the Accept-Language header
+    Accepted,
+    /// Reached through the fallback chain of a requested language
+    Fallback,
+    /// The default language of the catalog
+    Default,
+    /// The first available language, when nothing else matched
+    FirstAvailable,
+}
+
+/// The negotiated language of an asset and why it was chosen
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LanguageChoice {
+    pub lang: LanguageIdentifier,
+    pub reason: LanguageReason,
+}
+
+/// Configures how the language of translated assets is chosen.
+///
+/// In order, the first available language is taken from:
+/// 1. the override: [`RequestHeaders::language`], the cookie set with
+///    [`with_cookie`](Self::with_cookie) or the url path segment with
+///    [`with_path_segment`](Self::with_path_segment)
+/// 2. the Accept-Language header by q-value, where each requested language is followed
+///    by its [fallback chain](Self::with_fallback)
+/// 3. the [default language](Self::with_default_language)
+/// 4. the first language of the asset
+///
+/// ```
+/// use builder_assets::{LanguageNegotiation, LanguageReason, RequestHeaders, langid};
+/// let negotiation = LanguageNegotiation::new()
+///     .with_default_language(langid!("en"))
+///     .with_fallback(langid!("nb"), [langid!("no"), langid!("en")]);
+///
+/// let available = [langid!("en"), langid!("no")];
+/// let headers = RequestHeaders::new().accept_language("nb-NO, de;q=0.5");
+/// let choice = negotiation.negotiate(&headers, &available).unwrap();
+/// assert_eq!(choice.lang, langid!("no"));
+/// assert_eq!(choice.reason, LanguageReason::Fallback);
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct LanguageNegotiation {
+    default_language: Option<LanguageIdentifier>,
+    fallbacks: Vec<(LanguageIdentifier, Vec<LanguageIdentifier>)>,
+    cookie_name: Option<String>,
+    path_segment: bool,
+}
+
+impl LanguageNegotiation {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The language used when none of the requested languages is available
+    pub fn with_default_language(mut self, lang: LanguageIdentifier) -> Self {
+        self.default_language = Some(lang);
+        self
+    }
+
+    /// Languages to try in order when `lang` is requested but not available,
+    /// e.g. `nb` → `no` → `en`. Also applies to regional variants like `nb-NO`.
+    pub fn with_fallback(
+        mut self,
+        lang: LanguageIdentifier,
+        chain: impl IntoIterator<Item = LanguageIdentifier>,
+    ) -> Self {
+        self.fallbacks.push((lang, chain.into_iter().collect()));
+        self
+    }
+
+    /// Reads a language override from the cookie with the given name
+    pub fn with_cookie(mut self, name: impl Into<String>) -> Self {
+        self.cookie_name = Some(name.into());
+        self
+    }
+
+    /// Reads a language override from the first url path segment below the
+    /// catalog prefix, e.g. `/fr/css/style.css` serves `/css/style.css` in French
+    pub fn with_path_segment(mut self) -> Self {
+        self.path_segment = true;
+        self
+    }
+
+    pub fn default_language(&self) -> Option<&LanguageIdentifier> {
+        self.default_language.as_ref()
+    }
+
+    pub fn cookie_name(&self) -> Option<&str> {
+        self.cookie_name.as_deref()
+    }
+
+    pub fn uses_path_segment(&self) -> bool {
+        self.path_segment
+    }
+
+    /// Chooses one of the available languages for the request, or None when
+    /// there are no available languages
+    pub fn negotiate(
+        &self,
+        headers: &RequestHeaders,
+        available_languages: &[LanguageIdentifier],
+    ) -> Option<LanguageChoice> {
+        let choice = |lang, reason| Some(LanguageChoice { lang, reason });
+
+        let cookie = self
+            .cookie_name
+            .as_deref()
+            .zip(headers.cookie)
+            .and_then(|(name, cookie)| cookie_value(cookie, name));
+        for requested in [headers.language, cookie].into_iter().flatten() {
+            if let Some(lang) = requested
+                .parse()
+                .ok()
+                .and_then(|requested| negotiation::match_language(&requested, available_languages))
+            {
+                return choice(lang, LanguageReason::Override);
+            }
+        }
+
+        let accepted = headers
+            .accept_language
+            .map(negotiation::accepted_languages)
+            .unwrap_or_default();
+        for requested in &accepted {
+            if let Some(lang) = negotiation::match_language(requested, available_languages) {
+                return choice(lang, LanguageReason::Accepted);
+            }
+            if let Some(lang) = self
+                .fallback_chain(requested)
+                .iter()
+                .find_map(|fallback| negotiation::match_language(fallback, available_languages))
+            {
+                return choice(lang, LanguageReason::Fallback);
+            }
+        }
+
+        if let Some(lang) = self
+            .default_language
+            .as_ref()
+            .and_then(|default| negotiation::match_language(default, available_languages))
+        {
+            return choice(lang, LanguageReason::Default);
+        }
+
+        available_languages.first().map(|lang| LanguageChoice {
+            lang: lang.clone(),
+            reason: LanguageReason::FirstAvailable,
+        })
+    }
+
+    /// The fallback chain for the requested language or, for regional variants,
+    /// for its language without the region
+    fn fallback_chain(&self, requested: &LanguageIdentifier) -> &[LanguageIdentifier] {
+        let language_only = LanguageIdentifier::from(requested.language);
+        self.fallbacks
+            .iter()
+            .find(|(lang, _)| lang == requested)
+            .or_else(|| {
+                self.fallbacks
+                    .iter()
+                    .find(|(lang, _)| *lang == language_only)
+            })
+            .map(|(_, chain)| chain.as_slice())
+            .unwrap_or_default()
+    }
+}
+
+/// Returns the value of a cookie from a Cookie header like `theme=dark; lang=fr`
+pub fn cookie_value<'a>(cookie_header: &'a str, name: &str) -> Option<&'a str> {
+    cookie_header.split(';').find_map(|pair| {
+        let (key, value) = pair.trim().split_once('=')?;
+        (key == name).then(|| value.trim_matches('"'))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use icu_locid::langid;
+
+    fn negotiate(
+        negotiation: &LanguageNegotiation,
+        headers: RequestHeaders,
+        available: &[LanguageIdentifier],
+    ) -> (LanguageIdentifier, LanguageReason) {
+        let choice = negotiation.negotiate(&headers, available).unwrap();
+        (choice.lang, choice.reason)
+    }
+
+    #[test]
+    fn test_accepted_by_quality() {
+        let available = [langid!("en"), langid!("fr"), langid!("de")];
+        let negotiation = LanguageNegotiation::new();
+        assert_eq!(
+            negotiate(
+                &negotiation,
+                RequestHeaders::new().accept_language("en;q=0.3, de-CH;q=0.9, fr;q=0.5"),
+                &available
+            ),
+            (langid!("de"), LanguageReason::Accepted)
+        );
+        assert!(negotiation.negotiate(&RequestHeaders::new(), &[]).is_none());
+    }
+
+    #[test]
+    fn test_fallback_chain_and_default() {
+        let available = [langid!("de"), langid!("en"), langid!("no")];
+        let negotiation = LanguageNegotiation::new()
+            .with_default_language(langid!("en"))
+            .with_fallback(langid!("nb"), [langid!("no"), langid!("en")]);
+
+        assert_eq!(
+            negotiate(
+                &negotiation,
+                RequestHeaders::new().accept_language("nb-NO, de;q=0.9"),
+                &available
+            ),
+            (langid!("no"), LanguageReason::Fallback)
+        );
+        assert_eq!(
+            negotiate(
+                &negotiation,
+                RequestHeaders::new().accept_language("es"),
+                &available
+            ),
+            (langid!("en"), LanguageReason::Default)
+        );
+        assert_eq!(
+            negotiate(
+                &LanguageNegotiation::new(),
+                RequestHeaders::new(),
+                &available
+            ),
+            (langid!("de"), LanguageReason::FirstAvailable)
+        );
+    }
+
+    #[test]
+    fn test_overrides() {
+        let available = [langid!("en"), langid!("fr")];
+        let negotiation = LanguageNegotiation::new().with_cookie("lang");
+
+        let headers = RequestHeaders::new()
+            .accept_language("en")
+            .cookie("theme=dark; lang=fr");
+        assert_eq!(
+            negotiate(&negotiation, headers, &available),
+            (langid!("fr"), LanguageReason::Override)
+        );
+
+        // The explicit language wins over the cookie, unavailable overrides are ignored
+        assert_eq!(
+            negotiate(&negotiation, headers.language("en-GB"), &available),
+            (langid!("en"), LanguageReason::Override)
+        );
+        let headers = RequestHeaders::new()
+            .accept_language("fr")
+            .cookie("lang=es");
+        assert_eq!(
+            negotiate(&negotiation, headers, &available),
+            (langid!("fr"), LanguageReason::Accepted)
+        );
+    }
+
+    #[test]
+    fn test_cookie_value() {
+        assert_eq!(cookie_value("a=1; lang=fr", "lang"), Some("fr"));
+        assert_eq!(cookie_value("lang=\"nb\"", "lang"), Some("nb"));
+        assert_eq!(cookie_value("language=fr", "lang"), None);
+    }
+}
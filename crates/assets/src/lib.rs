@@ -42,6 +42,8 @@
 //!     file_path_parts: PARTS,
 //!     available_encodings: &ENCODINGS,
 //!     available_languages: None,
+//!     available_formats: None,
+//!     available_themes: None,
 //!     mime: "text/css",
 //!     provider: &MOCK_PROVIDER,
 //! };
@@ -69,30 +71,66 @@
 //! ## Content Negotiation
 //!
 //! The system performs HTTP-style content negotiation:
-//! - **Language negotiation**: Uses `fluent-langneg` for proper locale matching
+//! - **Language negotiation**: Uses `fluent-langneg` for locale matching, with q-values,
+//!   fallback chains and a default language configured with `LanguageNegotiation`
 //! - **Encoding negotiation**: Respects client preferences with quality values
 //! - **Fallbacks**: Always returns a valid asset, preferring better encodings
 
+pub mod archive;
 pub mod asset;
 pub mod asset_set;
+pub mod cache;
 pub mod catalog;
 pub mod encoding;
 pub mod file_path;
+pub mod format;
+pub mod html;
+#[cfg(feature = "integrity")]
+pub mod integrity;
+pub mod language;
+#[cfg(feature = "manifest")]
+pub mod manifest;
 pub mod negotiation;
+pub mod provider;
+pub mod range;
+pub mod reader;
+pub mod response;
+pub mod router;
 pub mod runtime_config;
+#[cfg(feature = "tower")]
+pub mod service;
+pub mod shared;
 
 // Re-export the main public API
+pub use archive::{Archive, ArchiveProvider, ArchiveWriter};
 pub use asset::Asset;
 pub use asset_set::AssetSet;
-pub use catalog::AssetCatalog;
+pub use cache::CachedProvider;
+pub use catalog::{AssetCatalog, JoinConflict, LogicalUrls};
 pub use encoding::Encoding;
 pub use file_path::FilePathParts;
+pub use format::ImageFormat;
+#[cfg(feature = "integrity")]
+pub use integrity::IntegrityError;
+pub use language::{LanguageChoice, LanguageNegotiation, LanguageReason};
+#[cfg(feature = "manifest")]
+pub use manifest::{AssetManifest, ManifestAsset};
+pub use provider::{AssetProvider, FileSystemProvider, LoadFn, LoadStaticFn};
+pub use reader::AssetReader;
+pub use response::{AssetResponse, RequestHeaders};
+pub use router::AssetRouter;
 pub use runtime_config::{get_asset_base_path, get_asset_base_path_or_panic, set_asset_base_path};
+#[cfg(feature = "tower")]
+pub use service::{AssetLayer, AssetService, AssetServiceBody};
+pub use shared::SharedCatalog;
+#[cfg(feature = "tokio")]
+pub use {provider::BoxFuture, reader::AsyncAssetReader};
 
 // Re-export icu_locid for convenience since it's part of the public API
 pub use icu_locid::{LanguageIdentifier, langid};
 
 // Re-export rust_embed for generated code
+#[cfg(feature = "embed")]
 pub use rust_embed::Embed;
 
 // Re-export std::path for filesystem operations
@@ -146,6 +184,8 @@ mod integration_tests {
         file_path_parts: STYLE_PARTS,
         available_encodings: &STYLE_ENCODINGS,
         available_languages: None,
+        available_formats: None,
+        available_themes: None,
         mime: "text/css",
         provider: &TEST_PROVIDER,
     };
@@ -155,6 +195,8 @@ mod integration_tests {
         file_path_parts: BUTTON_PARTS,
         available_encodings: &BUTTON_ENCODINGS,
         available_languages: Some(&BUTTON_LANGUAGES),
+        available_formats: None,
+        available_themes: None,
         mime: "text/css",
         provider: &TEST_PROVIDER,
     };
@@ -210,6 +252,8 @@ mod integration_tests {
         file_path_parts: FONT_PARTS,
         available_encodings: &FONT_ENCODINGS,
         available_languages: None,
+        available_formats: None,
+        available_themes: None,
         mime: "font/woff2",
         provider: &TEST_PROVIDER,
     };
@@ -274,6 +318,8 @@ mod integration_tests {
         file_path_parts: TEST_FILE_PARTS,
         available_encodings: &TEST_FILE_ENCODINGS,
         available_languages: None,
+        available_formats: None,
+        available_themes: None,
         mime: "text/plain",
         provider: &TEST_PROVIDER,
     };
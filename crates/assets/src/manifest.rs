@@ -0,0 +1,224 @@
+use crate::{
+    asset_set::AssetSet, catalog::AssetCatalog, encoding::Encoding, file_path::FilePathParts,
+    format::ImageFormat, provider::AssetProvider,
+};
+use icu_locid::LanguageIdentifier;
+use serde::{Deserialize, Serialize};
+use std::{borrow::Cow, io, path::Path, sync::Arc};
+
+/// The JSON manifest written by builder with `Output::asset_manifest`, describing
+/// the assets of an output directory without generating code for them
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct AssetManifest {
+    pub assets: Vec<ManifestAsset>,
+}
+
+/// An asset in the [`AssetManifest`], with the same fields as the generated AssetSets
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct ManifestAsset {
+    pub url_path: String,
+    pub folder: Option<String>,
+    pub name: String,
+    pub hash: Option<String>,
+    pub ext: String,
+    pub available_encodings: Vec<Encoding>,
+    pub available_languages: Option<Vec<LanguageIdentifier>>,
+    #[serde(default)]
+    pub available_formats: Option<Vec<ImageFormat>>,
+    #[serde(default)]
+    pub available_themes: Option<Vec<Cow<'static, str>>>,
+    pub mime: String,
+}
+
+impl AssetManifest {
+    /// Parses a manifest from its JSON content
+    pub fn from_json(json: &str) -> io::Result<Self> {
+        serde_json::from_str(json).map_err(|err| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("Invalid asset manifest: {err}"),
+            )
+        })
+    }
+
+    /// Serializes the manifest to pretty-printed JSON
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("Asset manifests serialize to JSON")
+    }
+
+    /// Reads and parses a manifest file
+    pub fn open<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        Self::from_json(&std::fs::read_to_string(path)?)
+    }
+}
+
+/// The AssetSets of a manifest have no provider of their own, a catalog created
+/// with [`AssetCatalog::from_manifest`] loads their data from its provider
+static NO_PROVIDER: fn(&str) -> Option<Vec<u8>> = no_data;
+fn no_data(_path: &str) -> Option<Vec<u8>> {
+    None
+}
+
+impl ManifestAsset {
+    /// Returns the AssetSet for this asset, borrowing its fields
+    pub fn asset_set(&self) -> AssetSet<'_> {
+        AssetSet {
+            url_path: &self.url_path,
+            file_path_parts: FilePathParts {
+                folder: self.folder.as_deref(),
+                name: &self.name,
+                hash: self.hash.as_deref(),
+                ext: &self.ext,
+            },
+            available_encodings: &self.available_encodings,
+            available_languages: self.available_languages.as_deref(),
+            available_formats: self.available_formats.as_deref(),
+            available_themes: self.available_themes.as_deref(),
+            mime: &self.mime,
+            provider: &NO_PROVIDER,
+        }
+    }
+}
+
+impl AssetCatalog {
+    /// Creates a catalog for the assets of a manifest, loading their data from the
+    /// provider, e.g. a [`FileSystemProvider`](crate::FileSystemProvider) for the
+    /// output directory the manifest was written for.
+    ///
+    /// The catalog owns a copy of the manifest assets, which is freed with the catalog.
+    pub fn from_manifest<P: AssetProvider + 'static>(
+        manifest: &AssetManifest,
+        provider: P,
+    ) -> Self {
+        let mut catalog = Self::new();
+        for asset in &manifest.assets {
+            catalog.add_manifest_asset(Arc::new(asset.clone()));
+        }
+        catalog.with_provider(provider)
+    }
+
+    /// Reads a manifest file and creates a catalog for it, see
+    /// [`from_manifest`](Self::from_manifest)
+    pub fn load_manifest<M: AsRef<Path>, P: AssetProvider + 'static>(
+        manifest_path: M,
+        provider: P,
+    ) -> io::Result<Self> {
+        Ok(Self::from_manifest(
+            &AssetManifest::open(manifest_path)?,
+            provider,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{RequestHeaders, SharedCatalog};
+    use icu_locid::langid;
+
+    static MANIFEST: &str = r#"{
+  "assets": [
+    {
+      "url_path": "/css/style.abc123=.css",
+      "folder": "css",
+      "name": "style",
+      "hash": "abc123=",
+      "ext": "css",
+      "available_encodings": ["Identity", "Brotli"],
+      "available_languages": ["en", "fr"],
+      "mime": "text/css"
+    },
+    {
+      "url_path": "/img/logo.png",
+      "folder": "img",
+      "name": "logo",
+      "hash": null,
+      "ext": "png",
+      "available_encodings": ["Identity"],
+      "available_languages": null,
+      "available_formats": ["Avif"],
+      "mime": "image/png"
+    }
+  ]
+}"#;
+
+    #[derive(Debug)]
+    struct PathProvider;
+
+    impl AssetProvider for PathProvider {
+        fn load(&self, path: &str) -> Option<std::borrow::Cow<'_, [u8]>> {
+            Some(path.as_bytes().to_vec().into())
+        }
+    }
+
+    #[test]
+    fn test_catalog_from_manifest() {
+        let manifest = AssetManifest::from_json(MANIFEST).unwrap();
+        assert_eq!(manifest.assets[0].available_formats, None);
+        let catalog = AssetCatalog::from_manifest(&manifest, PathProvider);
+        assert_eq!(catalog.len(), 2);
+        assert_eq!(
+            catalog.url_for_logical("/css/style.css"),
+            Some("/css/style.abc123=.css")
+        );
+
+        let asset = catalog
+            .asset_for("/css/style.abc123=.css", Some("br"), Some("fr"))
+            .unwrap();
+        assert_eq!(asset.lang, Some(langid!("fr")));
+        assert_eq!(asset.data_for(), b"css/style.abc123=.css/fr.css.br");
+
+        // The AssetSets of the catalog load through its provider
+        let asset = catalog
+            .get_asset_set("/img/logo.png")
+            .unwrap()
+            .asset_for(None, None)
+            .unwrap();
+        assert_eq!(asset.try_data_for().unwrap(), b"img/logo.png");
+        let asset_set = catalog.asset_sets().next().unwrap();
+        assert!(
+            asset_set
+                .asset_for(None, None)
+                .unwrap()
+                .try_data_for()
+                .is_ok()
+        );
+
+        let headers = RequestHeaders::new().accept("image/avif,image/*");
+        let mut response = catalog.respond("/img/logo.png", &headers);
+        assert_eq!(response.status, 200);
+        assert_eq!(response.body().unwrap(), b"img/logo.png.avif");
+    }
+
+    #[test]
+    fn test_reload_manifest() {
+        let manifest = AssetManifest::from_json(MANIFEST).unwrap();
+        let shared = SharedCatalog::new(AssetCatalog::from_manifest(&manifest, PathProvider));
+        let before = shared.load();
+
+        let mut updated = manifest.clone();
+        updated.assets[0].hash = Some("def456=".to_string());
+        updated.assets[0].url_path = "/css/style.def456=.css".to_string();
+        shared.store(AssetCatalog::from_manifest(&updated, PathProvider));
+
+        // Catalogs already in use keep serving the previous assets
+        assert!(before.contains_url("/css/style.abc123=.css"));
+        let current = shared.load();
+        assert!(!current.contains_url("/css/style.abc123=.css"));
+        assert_eq!(
+            current.url_for_logical("/css/style.css"),
+            Some("/css/style.def456=.css")
+        );
+
+        // The previous catalog and its assets are freed once no longer in use
+        let previous = Arc::downgrade(&before);
+        drop(before);
+        assert!(previous.upgrade().is_none());
+    }
+
+    #[test]
+    fn test_invalid_manifest() {
+        let err = AssetManifest::from_json("{\"assets\": [{}]}").unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+}
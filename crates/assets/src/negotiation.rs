@@ -1,4 +1,4 @@
-use crate::encoding::Encoding;
+use crate::{encoding::Encoding, format::ImageFormat};
 use fluent_langneg::{NegotiationStrategy, negotiate_languages};
 use icu_locid::LanguageIdentifier;
 
@@ -56,8 +56,105 @@ pub fn negotiate_encoding(accept_encoding: &str, available_encodings: &[Encoding
         .unwrap_or(Encoding::Identity)
 }
 
-/// Negotiates the best language from the Accept-Language header
-/// or falls back to "en" if present otherwise the first available language
+/// Returns the best available encoding that the Accept-Encoding header allows,
+/// or None when the client refuses all of them.
+///
+/// Unlike [`negotiate_encoding`] this doesn't fall back: an encoding is acceptable when
+/// it is listed (or matched by `*`) with a non-zero quality, and identity is acceptable
+/// unless it is refused with `identity;q=0` or `*;q=0`.
+pub fn acceptable_encoding(
+    accept_encoding: &str,
+    available_encodings: &[Encoding],
+) -> Option<Encoding> {
+    let mut preferences = Vec::new();
+    for part in accept_encoding.split(',') {
+        let part = part.trim();
+        if part.is_empty() {
+            continue;
+        }
+        let (name, quality) = match part.split_once(';') {
+            Some((name, q_part)) => (name.trim(), parse_quality(q_part.trim()).unwrap_or(1.0)),
+            None => (part, 1.0),
+        };
+        preferences.push((name, quality));
+    }
+
+    let quality_of = |encoding: Encoding| {
+        let explicit = preferences
+            .iter()
+            .filter(|(name, _)| *name != "*" && matches_encoding(name, encoding))
+            .map(|(_, q)| *q)
+            .reduce(f32::max);
+        let wildcard = preferences
+            .iter()
+            .find(|(name, _)| *name == "*")
+            .map(|(_, q)| *q);
+        match (explicit, wildcard) {
+            (Some(q), _) => q,
+            (None, Some(q)) => q,
+            (None, None) if encoding == Encoding::Identity => 1.0,
+            (None, None) => 0.0,
+        }
+    };
+
+    available_encodings
+        .iter()
+        .map(|&encoding| (encoding, quality_of(encoding)))
+        .filter(|(_, q)| *q > 0.0)
+        .min_by(|(a, qa), (b, qb)| {
+            qb.partial_cmp(qa)
+                .unwrap_or(std::cmp::Ordering::Equal)
+                .then(a.preference_order().cmp(&b.preference_order()))
+        })
+        .map(|(encoding, _)| encoding)
+}
+
+/// Returns the preferred alternative image format that the Accept header lists,
+/// or None to serve the original format.
+///
+/// Only explicit media types count: `image/*` and `*/*` don't imply AVIF or WebP support.
+pub fn negotiate_image_format(
+    accept: &str,
+    available_formats: &[ImageFormat],
+) -> Option<ImageFormat> {
+    let quality_of = |format: ImageFormat| {
+        accept
+            .split(',')
+            .filter_map(|part| {
+                let (media_type, quality) = match part.split_once(';') {
+                    Some((media_type, params)) => {
+                        let quality = params
+                            .split(';')
+                            .find_map(|param| parse_quality(param.trim()))
+                            .unwrap_or(1.0);
+                        (media_type, quality)
+                    }
+                    None => (part, 1.0),
+                };
+                media_type
+                    .trim()
+                    .eq_ignore_ascii_case(format.mime())
+                    .then_some(quality)
+            })
+            .reduce(f32::max)
+            .unwrap_or(0.0)
+    };
+
+    available_formats
+        .iter()
+        .map(|&format| (format, quality_of(format)))
+        .filter(|(_, q)| *q > 0.0)
+        .min_by(|(a, qa), (b, qb)| {
+            qb.partial_cmp(qa)
+                .unwrap_or(std::cmp::Ordering::Equal)
+                .then(a.preference_order().cmp(&b.preference_order()))
+        })
+        .map(|(format, _)| format)
+}
+
+/// Negotiates the best language from the Accept-Language header, in the order of
+/// the q-values. Returns None when no requested language is available, see
+/// [`LanguageNegotiation`](crate::LanguageNegotiation) for defaults and fallbacks.
 pub fn negotiate_language(
     accept_language: &str,
     available_languages: &[LanguageIdentifier],
@@ -66,15 +163,7 @@ pub fn negotiate_language(
         return None;
     }
 
-    // Parse the Accept-Language header into LanguageIdentifiers
-    let requested: Vec<LanguageIdentifier> = accept_language
-        .split(',')
-        .filter_map(|lang_part| {
-            let lang_tag = lang_part.split(';').next()?.trim();
-            lang_tag.parse().ok()
-        })
-        .collect();
-
+    let requested = accepted_languages(accept_language);
     if requested.is_empty() {
         return None;
     }
@@ -90,6 +179,43 @@ pub fn negotiate_language(
     result.into_iter().next().cloned()
 }
 
+/// Parses the languages of an Accept-Language header, highest quality first.
+///
+/// Languages with the same quality keep the header order; refused (`q=0`),
+/// wildcard and invalid entries are left out.
+pub fn accepted_languages(accept_language: &str) -> Vec<LanguageIdentifier> {
+    let mut preferences: Vec<(LanguageIdentifier, f32)> = accept_language
+        .split(',')
+        .filter_map(|part| {
+            let (tag, quality) = match part.split_once(';') {
+                Some((tag, q_part)) => (tag, parse_quality(q_part.trim()).unwrap_or(1.0)),
+                None => (part, 1.0),
+            };
+            let lang = tag.trim().parse().ok()?;
+            (quality > 0.0).then_some((lang, quality))
+        })
+        .collect();
+    // Stable sort, keeping the header order for equal qualities
+    preferences.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal));
+    preferences.into_iter().map(|(lang, _)| lang).collect()
+}
+
+/// Returns the available language matching a requested one, e.g. `fr` for `fr-CA`
+pub fn match_language(
+    requested: &LanguageIdentifier,
+    available_languages: &[LanguageIdentifier],
+) -> Option<LanguageIdentifier> {
+    negotiate_languages(
+        std::slice::from_ref(requested),
+        available_languages,
+        None,
+        NegotiationStrategy::Filtering,
+    )
+    .into_iter()
+    .next()
+    .cloned()
+}
+
 /// Parses a quality value from a q-parameter (e.g., "q=0.8")
 fn parse_quality(q_part: &str) -> Option<f32> {
     if let Some(q_value) = q_part.strip_prefix("q=") {
@@ -105,7 +231,6 @@ fn matches_encoding(encoding_name: &str, available: Encoding) -> bool {
         "br" => available == Encoding::Brotli,
         "brotli" => available == Encoding::Brotli,
         "gzip" => available == Encoding::Gzip,
-        "deflate" => available == Encoding::Gzip, // Treat deflate as gzip
         "identity" => available == Encoding::Identity,
         "*" => true, // Wildcard matches any encoding
         _ => false,
@@ -169,6 +294,69 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_acceptable_encoding() {
+        let available = [Encoding::Identity, Encoding::Gzip, Encoding::Brotli];
+        assert_eq!(
+            acceptable_encoding("gzip, br", &available),
+            Some(Encoding::Brotli)
+        );
+        assert_eq!(
+            acceptable_encoding("gzip;q=1, br;q=0.5", &available),
+            Some(Encoding::Gzip)
+        );
+        // deflate is a different format than gzip
+        assert_eq!(
+            acceptable_encoding("deflate", &available),
+            Some(Encoding::Identity)
+        );
+        assert_eq!(acceptable_encoding("deflate", &[Encoding::Gzip]), None);
+        // Identity is acceptable unless refused
+        assert_eq!(
+            acceptable_encoding("compress", &available),
+            Some(Encoding::Identity)
+        );
+        assert_eq!(
+            acceptable_encoding("", &available),
+            Some(Encoding::Identity)
+        );
+
+        let compressed = [Encoding::Brotli];
+        assert_eq!(acceptable_encoding("gzip", &compressed), None);
+        assert_eq!(
+            acceptable_encoding("*", &compressed),
+            Some(Encoding::Brotli)
+        );
+        assert_eq!(acceptable_encoding("br;q=0, *", &compressed), None);
+
+        let identity = [Encoding::Identity];
+        assert_eq!(acceptable_encoding("br, identity;q=0", &identity), None);
+        assert_eq!(acceptable_encoding("br, *;q=0", &identity), None);
+    }
+
+    #[test]
+    fn test_negotiate_image_format() {
+        let available = [ImageFormat::Avif, ImageFormat::WebP];
+        let chrome = "image/avif,image/webp,image/apng,image/svg+xml,image/*,*/*;q=0.8";
+        assert_eq!(
+            negotiate_image_format(chrome, &available),
+            Some(ImageFormat::Avif)
+        );
+        assert_eq!(
+            negotiate_image_format("image/webp,*/*", &available),
+            Some(ImageFormat::WebP)
+        );
+        assert_eq!(
+            negotiate_image_format("image/avif;q=0.5, image/webp", &available),
+            Some(ImageFormat::WebP)
+        );
+        assert_eq!(negotiate_image_format("image/*,*/*", &available), None);
+        assert_eq!(
+            negotiate_image_format("image/avif;q=0", &[ImageFormat::Avif]),
+            None
+        );
+    }
+
     #[test]
     fn test_negotiate_language_basic() {
         let available = [langid!("en"), langid!("fr"), langid!("de")];
@@ -200,6 +388,24 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_negotiate_language_quality() {
+        let available = [langid!("en"), langid!("fr"), langid!("de")];
+
+        assert_eq!(
+            negotiate_language("en;q=0.5, fr, de;q=0.8", &available),
+            Some(langid!("fr"))
+        );
+        assert_eq!(
+            negotiate_language("fr;q=0, en;q=0.1", &available),
+            Some(langid!("en"))
+        );
+        assert_eq!(
+            accepted_languages("de;q=0.8, *;q=0.1, nb, no;q=0.8, invalid tag"),
+            vec![langid!("nb"), langid!("de"), langid!("no")]
+        );
+    }
+
     #[test]
     fn test_negotiate_language_empty_available() {
         let available: [LanguageIdentifier; 0] = [];
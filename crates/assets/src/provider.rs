@@ -0,0 +1,244 @@
+use crate::reader::AssetReader;
+use std::{borrow::Cow, fmt::Debug, io, path::PathBuf};
+
+#[cfg(feature = "tokio")]
+use crate::reader::AsyncAssetReader;
+#[cfg(feature = "tokio")]
+use std::{future::Future, pin::Pin};
+
+/// Future returned by the async provider methods
+#[cfg(feature = "tokio")]
+pub type BoxFuture<'a, T> = Pin<Box<dyn Future<Output = T> + Send + 'a>>;
+
+/// Loads the data of an asset variant given its relative file path
+/// (as constructed by [`FilePathParts::construct_path`](crate::FilePathParts::construct_path)).
+///
+/// The generated code uses plain functions as providers, which implement this trait:
+/// - [`LoadFn`] returns an owned copy of the data, e.g. from rust-embed or the filesystem
+/// - [`LoadStaticFn`] borrows data that lives for the whole program, e.g. from `include_bytes!`
+///
+/// Providers with runtime state, like [`FileSystemProvider`] or an
+/// [`Archive`](crate::Archive), can be bound to an
+/// [`AssetCatalog`](crate::AssetCatalog) with `with_provider` or `replace_provider`.
+pub trait AssetProvider: Debug + Send + Sync {
+    /// Returns the data for the path, borrowed when the provider already holds it in memory
+    fn load(&self, path: &str) -> Option<Cow<'_, [u8]>>;
+
+    /// Like [`load`](Self::load), but reports why the data could not be loaded
+    fn try_load(&self, path: &str) -> io::Result<Cow<'_, [u8]>> {
+        self.load(path).ok_or_else(|| not_found(path))
+    }
+
+    /// Opens a streaming reader for the path.
+    ///
+    /// The default reads from the loaded data; providers backed by files stream them instead.
+    fn open(&self, path: &str) -> io::Result<AssetReader<'_>> {
+        self.try_load(path).map(AssetReader::from_data)
+    }
+
+    /// Loads the data for the path without blocking the tokio runtime.
+    ///
+    /// The default calls [`try_load`](Self::try_load), which is fine for data in memory.
+    #[cfg(feature = "tokio")]
+    fn load_async<'a: 'p, 'p>(&'a self, path: &'p str) -> BoxFuture<'p, io::Result<Cow<'a, [u8]>>> {
+        Box::pin(std::future::ready(self.try_load(path)))
+    }
+
+    /// Opens an async streaming reader for the path.
+    ///
+    /// The default reads from the loaded data; providers backed by files stream them instead.
+    #[cfg(feature = "tokio")]
+    fn open_async<'a: 'p, 'p>(
+        &'a self,
+        path: &'p str,
+    ) -> BoxFuture<'p, io::Result<AsyncAssetReader<'a>>> {
+        Box::pin(async move { self.load_async(path).await.map(AsyncAssetReader::from_data) })
+    }
+}
+
+fn not_found(path: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::NotFound, format!("Asset not found: {path}"))
+}
+
+/// Provider function returning an owned copy of the asset data
+pub type LoadFn = fn(&str) -> Option<Vec<u8>>;
+
+/// Provider function returning asset data compiled into the binary
+pub type LoadStaticFn = fn(&str) -> Option<&'static [u8]>;
+
+impl AssetProvider for LoadFn {
+    fn load(&self, path: &str) -> Option<Cow<'_, [u8]>> {
+        self(path).map(Cow::Owned)
+    }
+}
+
+impl AssetProvider for LoadStaticFn {
+    fn load(&self, path: &str) -> Option<Cow<'_, [u8]>> {
+        self(path).map(Cow::Borrowed)
+    }
+}
+
+/// Loads asset data from files in a base directory
+#[derive(Debug, Clone)]
+pub struct FileSystemProvider {
+    /// None for the process-global path set with [`set_asset_base_path`](crate::set_asset_base_path)
+    base_path: Option<PathBuf>,
+}
+
+impl FileSystemProvider {
+    /// Creates a provider loading files relative to `base_path`
+    pub fn new<P: Into<PathBuf>>(base_path: P) -> Self {
+        Self {
+            base_path: Some(base_path.into()),
+        }
+    }
+
+    /// Creates a provider loading files relative to the process-global base path.
+    ///
+    /// This is what the generated code for `DataProvider::FileSystem` uses by default.
+    ///
+    /// # Panics
+    /// Loading panics if the asset base path has not been configured using set_asset_base_path().
+    pub const fn global() -> Self {
+        Self { base_path: None }
+    }
+
+    fn full_path(&self, path: &str) -> PathBuf {
+        let base_path = match &self.base_path {
+            Some(base_path) => base_path,
+            None => crate::get_asset_base_path_or_panic(),
+        };
+        base_path.join(path.trim_start_matches('/'))
+    }
+}
+
+impl AssetProvider for FileSystemProvider {
+    fn load(&self, path: &str) -> Option<Cow<'_, [u8]>> {
+        self.try_load(path).ok()
+    }
+
+    fn try_load(&self, path: &str) -> io::Result<Cow<'_, [u8]>> {
+        std::fs::read(self.full_path(path)).map(Cow::Owned)
+    }
+
+    fn open(&self, path: &str) -> io::Result<AssetReader<'_>> {
+        let file = std::fs::File::open(self.full_path(path))?;
+        let len = file.metadata()?.len();
+        Ok(AssetReader::new(len, file))
+    }
+
+    #[cfg(feature = "tokio")]
+    fn load_async<'a: 'p, 'p>(&'a self, path: &'p str) -> BoxFuture<'p, io::Result<Cow<'a, [u8]>>> {
+        Box::pin(async move { tokio::fs::read(self.full_path(path)).await.map(Cow::Owned) })
+    }
+
+    #[cfg(feature = "tokio")]
+    fn open_async<'a: 'p, 'p>(
+        &'a self,
+        path: &'p str,
+    ) -> BoxFuture<'p, io::Result<AsyncAssetReader<'a>>> {
+        Box::pin(async move {
+            let file = tokio::fs::File::open(self.full_path(path)).await?;
+            let len = file.metadata().await?.len();
+            Ok(AsyncAssetReader::new(len, file))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    static OWNED: LoadFn = owned;
+    fn owned(path: &str) -> Option<Vec<u8>> {
+        (path == "a.txt").then(|| b"owned".to_vec())
+    }
+
+    static BORROWED: LoadStaticFn = borrowed;
+    fn borrowed(path: &str) -> Option<&'static [u8]> {
+        (path == "a.txt").then_some(b"borrowed")
+    }
+
+    #[test]
+    fn test_owned_provider() {
+        let provider: &dyn AssetProvider = &OWNED;
+        assert!(matches!(provider.load("a.txt"), Some(Cow::Owned(_))));
+        assert!(provider.load("b.txt").is_none());
+    }
+
+    #[test]
+    fn test_filesystem_provider() {
+        let dir = std::env::temp_dir().join(format!("builder-assets-fs-{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("css")).unwrap();
+        std::fs::write(dir.join("css/style.css"), "body {}").unwrap();
+
+        let provider = FileSystemProvider::new(&dir);
+        assert_eq!(provider.load("css/style.css").unwrap().as_ref(), b"body {}");
+        assert_eq!(
+            provider.load("/css/style.css").unwrap().as_ref(),
+            b"body {}"
+        );
+        assert!(provider.load("css/missing.css").is_none());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_filesystem_provider_errors_and_streams() {
+        use std::io::Read;
+
+        let dir =
+            std::env::temp_dir().join(format!("builder-assets-stream-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("app.wasm"), b"wasm bytes").unwrap();
+
+        let provider = FileSystemProvider::new(&dir);
+        let err = provider.try_load("missing.wasm").unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::NotFound);
+
+        let mut reader = provider.open("app.wasm").unwrap();
+        assert_eq!(reader.len(), 10);
+        let mut data = Vec::new();
+        reader.read_to_end(&mut data).unwrap();
+        assert_eq!(data, b"wasm bytes");
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[cfg(feature = "tokio")]
+    #[tokio::test]
+    async fn test_filesystem_provider_async() {
+        use tokio::io::AsyncReadExt;
+
+        let dir = std::env::temp_dir().join(format!("builder-assets-async-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("app.wasm"), b"wasm bytes").unwrap();
+
+        let provider = FileSystemProvider::new(&dir);
+        let data = provider.load_async("app.wasm").await.unwrap();
+        assert_eq!(data.as_ref(), b"wasm bytes");
+        assert!(provider.load_async("missing.wasm").await.is_err());
+
+        let mut reader = provider.open_async("app.wasm").await.unwrap();
+        assert_eq!(reader.len(), 10);
+        let mut data = Vec::new();
+        reader.read_to_end(&mut data).await.unwrap();
+        assert_eq!(data, b"wasm bytes");
+
+        // Providers with data in memory use the default implementations
+        let mut reader = BORROWED.open_async("a.txt").await.unwrap();
+        let mut data = Vec::new();
+        reader.read_to_end(&mut data).await.unwrap();
+        assert_eq!(data, b"borrowed");
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_static_provider_borrows() {
+        let provider: &dyn AssetProvider = &BORROWED;
+        let data = provider.load("a.txt").unwrap();
+        assert!(matches!(data, Cow::Borrowed(b"borrowed")));
+        assert!(provider.load("b.txt").is_none());
+    }
+}
@@ -0,0 +1,189 @@
+use std::ops::Range;
+
+/// The most ranges accepted in a Range header, more are answered with the full content
+pub const MAX_RANGES: usize = 16;
+
+/// The outcome of parsing a Range header against the length of the selected variant
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ByteRanges {
+    /// The header is not a valid `bytes` range and the full content should be sent
+    Ignored,
+    /// The byte ranges to send, sorted and with overlapping or adjacent ranges merged
+    Satisfiable(Vec<Range<u64>>),
+    /// None of the ranges overlap the content, answered with 416
+    Unsatisfiable,
+}
+
+/// Parses a Range header like `bytes=0-99`, `bytes=100-`, `bytes=-500` or
+/// `bytes=0-99,200-299` for content of the given length.
+///
+/// Ranges that start after the end of the content are dropped, and the end of the
+/// other ranges is clamped to the length. Headers with more than [`MAX_RANGES`]
+/// ranges are ignored.
+pub fn parse_byte_ranges(header: &str, len: u64) -> ByteRanges {
+    let Some(specs) = header.trim().strip_prefix("bytes=") else {
+        return ByteRanges::Ignored;
+    };
+    if specs.split(',').count() > MAX_RANGES {
+        return ByteRanges::Ignored;
+    }
+
+    let mut ranges = Vec::new();
+    for spec in specs.split(',') {
+        let Some((start, end)) = spec.trim().split_once('-') else {
+            return ByteRanges::Ignored;
+        };
+        let (start, end) = (start.trim(), end.trim());
+
+        let range = if start.is_empty() {
+            // Suffix range: the last `end` bytes
+            let Ok(suffix) = end.parse::<u64>() else {
+                return ByteRanges::Ignored;
+            };
+            // Empty content has no last bytes to send
+            if suffix == 0 || len == 0 {
+                continue;
+            }
+            len.saturating_sub(suffix)..len
+        } else {
+            let Ok(start) = start.parse::<u64>() else {
+                return ByteRanges::Ignored;
+            };
+            let end = if end.is_empty() {
+                len
+            } else {
+                match end.parse::<u64>() {
+                    Ok(end) if end >= start => end.saturating_add(1).min(len),
+                    _ => return ByteRanges::Ignored,
+                }
+            };
+            if start >= len {
+                continue;
+            }
+            start..end
+        };
+        ranges.push(range);
+    }
+
+    if ranges.is_empty() {
+        ByteRanges::Unsatisfiable
+    } else {
+        ByteRanges::Satisfiable(merge_ranges(ranges))
+    }
+}
+
+/// Sorts the ranges and merges the ones that overlap or are adjacent
+fn merge_ranges(mut ranges: Vec<Range<u64>>) -> Vec<Range<u64>> {
+    ranges.sort_by_key(|range| range.start);
+    let mut merged: Vec<Range<u64>> = Vec::with_capacity(ranges.len());
+    for range in ranges {
+        match merged.last_mut() {
+            Some(last) if range.start <= last.end => last.end = last.end.max(range.end),
+            _ => merged.push(range),
+        }
+    }
+    merged
+}
+
+/// Formats the Content-Range header value for a range, e.g. `bytes 0-99/1000`
+pub fn content_range(range: &Range<u64>, len: u64) -> String {
+    format!("bytes {}-{}/{len}", range.start, range.end - 1)
+}
+
+#[cfg(test)]
+#[allow(clippy::single_range_in_vec_init)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_single_ranges() {
+        assert_eq!(
+            parse_byte_ranges("bytes=0-99", 1000),
+            ByteRanges::Satisfiable(vec![0..100])
+        );
+        assert_eq!(
+            parse_byte_ranges("bytes=900-", 1000),
+            ByteRanges::Satisfiable(vec![900..1000])
+        );
+        assert_eq!(
+            parse_byte_ranges("bytes=-100", 1000),
+            ByteRanges::Satisfiable(vec![900..1000])
+        );
+        // Clamped to the length
+        assert_eq!(
+            parse_byte_ranges("bytes=990-2000", 1000),
+            ByteRanges::Satisfiable(vec![990..1000])
+        );
+        assert_eq!(
+            parse_byte_ranges("bytes=-5000", 1000),
+            ByteRanges::Satisfiable(vec![0..1000])
+        );
+    }
+
+    #[test]
+    fn test_empty_content() {
+        assert_eq!(parse_byte_ranges("bytes=-5", 0), ByteRanges::Unsatisfiable);
+        assert_eq!(parse_byte_ranges("bytes=0-", 0), ByteRanges::Unsatisfiable);
+        assert_eq!(
+            parse_byte_ranges("bytes=0-99,-5", 0),
+            ByteRanges::Unsatisfiable
+        );
+    }
+
+    #[test]
+    fn test_multiple_ranges() {
+        assert_eq!(
+            parse_byte_ranges("bytes=0-9, 20-29,-5", 100),
+            ByteRanges::Satisfiable(vec![0..10, 20..30, 95..100])
+        );
+        // Unsatisfiable parts are dropped
+        assert_eq!(
+            parse_byte_ranges("bytes=0-9,200-299", 100),
+            ByteRanges::Satisfiable(vec![0..10])
+        );
+    }
+
+    #[test]
+    fn test_merged_ranges() {
+        // Sorted, with overlapping and adjacent ranges merged
+        assert_eq!(
+            parse_byte_ranges("bytes=50-59,0-9,5-14,15-19", 100),
+            ByteRanges::Satisfiable(vec![0..20, 50..60])
+        );
+        assert_eq!(
+            parse_byte_ranges("bytes=0-,-10", 100),
+            ByteRanges::Satisfiable(vec![0..100])
+        );
+    }
+
+    #[test]
+    fn test_too_many_ranges() {
+        let header = format!("bytes={}", vec!["0-0"; MAX_RANGES].join(","));
+        assert_eq!(
+            parse_byte_ranges(&header, 100),
+            ByteRanges::Satisfiable(vec![0..1])
+        );
+        let header = format!("bytes={}", vec!["0-0"; MAX_RANGES + 1].join(","));
+        assert_eq!(parse_byte_ranges(&header, 100), ByteRanges::Ignored);
+    }
+
+    #[test]
+    fn test_unsatisfiable_and_invalid() {
+        assert_eq!(
+            parse_byte_ranges("bytes=1000-", 1000),
+            ByteRanges::Unsatisfiable
+        );
+        assert_eq!(
+            parse_byte_ranges("bytes=-0", 1000),
+            ByteRanges::Unsatisfiable
+        );
+        assert_eq!(parse_byte_ranges("items=0-9", 1000), ByteRanges::Ignored);
+        assert_eq!(parse_byte_ranges("bytes=9-0", 1000), ByteRanges::Ignored);
+        assert_eq!(parse_byte_ranges("bytes=a-b", 1000), ByteRanges::Ignored);
+    }
+
+    #[test]
+    fn test_content_range() {
+        assert_eq!(content_range(&(0..100), 1000), "bytes 0-99/1000");
+    }
+}
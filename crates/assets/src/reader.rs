@@ -0,0 +1,133 @@
+use std::{
+    borrow::Cow,
+    io::{self, Cursor, Read, Seek, SeekFrom},
+};
+
+/// Object safe combination of [`Read`] and [`Seek`]
+pub trait ReadSeek: Read + Seek {}
+impl<T: Read + Seek> ReadSeek for T {}
+
+/// Streams the data of an asset variant, with its total length known up front
+/// so that it can be used for a Content-Length header.
+pub struct AssetReader<'a> {
+    len: u64,
+    inner: Box<dyn ReadSeek + Send + 'a>,
+}
+
+impl<'a> AssetReader<'a> {
+    pub fn new<R: Read + Seek + Send + 'a>(len: u64, reader: R) -> Self {
+        Self {
+            len,
+            inner: Box::new(reader),
+        }
+    }
+
+    /// Reads from data that has already been loaded
+    pub fn from_data(data: Cow<'a, [u8]>) -> Self {
+        Self::new(data.len() as u64, Cursor::new(data))
+    }
+
+    /// The total length of the asset data in bytes
+    pub fn len(&self) -> u64 {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+}
+
+impl Read for AssetReader<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.inner.read(buf)
+    }
+}
+
+impl Seek for AssetReader<'_> {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        self.inner.seek(pos)
+    }
+}
+
+impl std::fmt::Debug for AssetReader<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("AssetReader")
+            .field("len", &self.len)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(feature = "tokio")]
+pub use async_reader::*;
+
+#[cfg(feature = "tokio")]
+mod async_reader {
+    use std::{
+        borrow::Cow,
+        io::{self, Cursor, SeekFrom},
+        pin::Pin,
+        task::{Context, Poll},
+    };
+    use tokio::io::{AsyncRead, AsyncSeek, ReadBuf};
+
+    /// Object safe combination of [`AsyncRead`] and [`AsyncSeek`]
+    pub trait AsyncReadSeek: AsyncRead + AsyncSeek {}
+    impl<T: AsyncRead + AsyncSeek> AsyncReadSeek for T {}
+
+    /// Streams the data of an asset variant without blocking the tokio runtime
+    pub struct AsyncAssetReader<'a> {
+        len: u64,
+        inner: Pin<Box<dyn AsyncReadSeek + Send + 'a>>,
+    }
+
+    impl<'a> AsyncAssetReader<'a> {
+        pub fn new<R: AsyncRead + AsyncSeek + Send + 'a>(len: u64, reader: R) -> Self {
+            Self {
+                len,
+                inner: Box::pin(reader),
+            }
+        }
+
+        /// Reads from data that has already been loaded
+        pub fn from_data(data: Cow<'a, [u8]>) -> Self {
+            Self::new(data.len() as u64, Cursor::new(data))
+        }
+
+        /// The total length of the asset data in bytes
+        pub fn len(&self) -> u64 {
+            self.len
+        }
+
+        pub fn is_empty(&self) -> bool {
+            self.len == 0
+        }
+    }
+
+    impl AsyncRead for AsyncAssetReader<'_> {
+        fn poll_read(
+            mut self: Pin<&mut Self>,
+            cx: &mut Context<'_>,
+            buf: &mut ReadBuf<'_>,
+        ) -> Poll<io::Result<()>> {
+            self.inner.as_mut().poll_read(cx, buf)
+        }
+    }
+
+    impl AsyncSeek for AsyncAssetReader<'_> {
+        fn start_seek(mut self: Pin<&mut Self>, position: SeekFrom) -> io::Result<()> {
+            self.inner.as_mut().start_seek(position)
+        }
+
+        fn poll_complete(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<u64>> {
+            self.inner.as_mut().poll_complete(cx)
+        }
+    }
+
+    impl std::fmt::Debug for AsyncAssetReader<'_> {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            f.debug_struct("AsyncAssetReader")
+                .field("len", &self.len)
+                .finish_non_exhaustive()
+        }
+    }
+}
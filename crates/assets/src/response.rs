@@ -0,0 +1,395 @@
+use crate::{
+    asset::Asset,
+    encoding::Encoding,
+    format::ImageFormat,
+    language::LanguageChoice,
+    range::{self, ByteRanges},
+    reader::AssetReader,
+};
+use icu_locid::LanguageIdentifier;
+use std::{
+    io::{self, Read, Seek, SeekFrom, Write},
+    ops::Range,
+};
+
+/// Cache-Control for assets with a content hash in the url, which never change
+pub const CACHE_CONTROL_IMMUTABLE: &str = "public, max-age=31536000, immutable";
+
+/// Cache-Control for assets without a hash, which browsers need to revalidate
+pub const CACHE_CONTROL_REVALIDATE: &str = "no-cache";
+
+/// Separates the parts of multi-range responses
+const MULTIPART_BOUNDARY: &str = "BUILDER_ASSETS_BYTERANGES";
+
+/// The request headers used to select and describe the response for an asset
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct RequestHeaders<'a> {
+    pub accept: Option<&'a str>,
+    pub accept_encoding: Option<&'a str>,
+    pub accept_language: Option<&'a str>,
+    pub if_none_match: Option<&'a str>,
+    pub range: Option<&'a str>,
+    /// The Cookie header, read for the language cookie of a [`LanguageNegotiation`](crate::LanguageNegotiation)
+    /// and the [`THEME_COOKIE`](crate::asset_set::THEME_COOKIE)
+    pub cookie: Option<&'a str>,
+    /// Overrides the negotiated language, e.g. with the language of the user's account
+    pub language: Option<&'a str>,
+    /// Overrides the theme of the theme cookie, e.g. with the theme of the user's account
+    pub theme: Option<&'a str>,
+}
+
+impl<'a> RequestHeaders<'a> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn accept(mut self, value: &'a str) -> Self {
+        self.accept = Some(value);
+        self
+    }
+
+    pub fn accept_encoding(mut self, value: &'a str) -> Self {
+        self.accept_encoding = Some(value);
+        self
+    }
+
+    pub fn accept_language(mut self, value: &'a str) -> Self {
+        self.accept_language = Some(value);
+        self
+    }
+
+    pub fn if_none_match(mut self, value: &'a str) -> Self {
+        self.if_none_match = Some(value);
+        self
+    }
+
+    pub fn range(mut self, value: &'a str) -> Self {
+        self.range = Some(value);
+        self
+    }
+
+    pub fn cookie(mut self, value: &'a str) -> Self {
+        self.cookie = Some(value);
+        self
+    }
+
+    pub fn language(mut self, value: &'a str) -> Self {
+        self.language = Some(value);
+        self
+    }
+
+    pub fn theme(mut self, value: &'a str) -> Self {
+        self.theme = Some(value);
+        self
+    }
+}
+
+/// Describes the HTTP response for an asset request.
+///
+/// The body is not loaded: 200 and 206 responses carry the selected [`Asset`] variant,
+/// to be sent with [`write_body`](Self::write_body) or [`Asset::reader`].
+#[derive(Debug)]
+pub struct AssetResponse<'a> {
+    pub status: u16,
+    pub headers: Vec<(&'static str, String)>,
+    /// The asset variant to send as body
+    pub asset: Option<Asset<'a>>,
+    /// The byte ranges of the asset to send for 206 responses, empty for the full content
+    pub ranges: Vec<Range<u64>>,
+    /// The reader opened to get the length for a Range header, reused for the body
+    pub reader: Option<AssetReader<'a>>,
+    /// The negotiated language of translated assets and why it was chosen
+    pub language: Option<LanguageChoice>,
+}
+
+impl<'a> AssetResponse<'a> {
+    pub fn new(status: u16) -> Self {
+        Self {
+            status,
+            headers: Vec::new(),
+            asset: None,
+            ranges: Vec::new(),
+            reader: None,
+            language: None,
+        }
+    }
+
+    /// 404 Not Found, for urls that are not in the catalog
+    pub fn not_found() -> Self {
+        Self::new(404)
+    }
+
+    /// 302 Found, redirecting to the given url without caching the redirect
+    pub fn redirect(location: &str) -> Self {
+        Self::new(302)
+            .header("Location", location)
+            .header("Cache-Control", CACHE_CONTROL_REVALIDATE)
+    }
+
+    pub fn header(mut self, name: &'static str, value: impl Into<String>) -> Self {
+        self.headers.push((name, value.into()));
+        self
+    }
+
+    /// Replaces the value of an existing header, matching the name case-insensitively
+    pub fn replace_header(mut self, name: &str, value: impl Into<String>) -> Self {
+        let value = value.into();
+        for (header, header_value) in &mut self.headers {
+            if header.eq_ignore_ascii_case(name) {
+                *header_value = value.clone();
+            }
+        }
+        self
+    }
+
+    /// Returns the value of a header, matching the name case-insensitively
+    pub fn header_value(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(header, _)| header.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// Applies a Range header to a 200 response for content of the given length,
+    /// turning it into a 206 or 416 response
+    pub fn with_range(mut self, range_header: &str, len: u64) -> Self {
+        if self.status != 200 {
+            return self;
+        }
+        let ranges = match range::parse_byte_ranges(range_header, len) {
+            ByteRanges::Ignored => return self,
+            ByteRanges::Satisfiable(ranges) => ranges,
+            ByteRanges::Unsatisfiable => {
+                return AssetResponse::new(416)
+                    .header("Accept-Ranges", "bytes")
+                    .header("Content-Range", format!("bytes */{len}"));
+            }
+        };
+
+        self.status = 206;
+        if let [range] = ranges.as_slice() {
+            self = self
+                .header("Content-Range", range::content_range(range, len))
+                .header("Content-Length", (range.end - range.start).to_string());
+        } else {
+            let mime = self
+                .header_value("Content-Type")
+                .unwrap_or_default()
+                .to_string();
+            let content_length = ranges
+                .iter()
+                .map(|range| {
+                    multipart_header(&mime, range, len).len() as u64 + range.end - range.start + 2
+                })
+                .sum::<u64>()
+                + multipart_end().len() as u64;
+            self.headers.retain(|(name, _)| *name != "Content-Type");
+            self = self
+                .header(
+                    "Content-Type",
+                    format!("multipart/byteranges; boundary={MULTIPART_BOUNDARY}"),
+                )
+                .header("Content-Length", content_length.to_string());
+        }
+        self.ranges = ranges;
+        self
+    }
+
+    /// Writes the body of a 200 or 206 response, reading only the requested ranges.
+    ///
+    /// Multiple ranges are written as `multipart/byteranges`.
+    pub fn write_body<W: Write>(&mut self, mut writer: W) -> io::Result<()> {
+        let Some(asset) = &self.asset else {
+            return Ok(());
+        };
+        let mut reader = match self.reader.take() {
+            Some(reader) => reader,
+            None => asset.reader()?,
+        };
+
+        match self.ranges.as_slice() {
+            [] => {
+                io::copy(&mut reader, &mut writer)?;
+            }
+            [range] => copy_range(&mut reader, range, &mut writer)?,
+            ranges => {
+                let len = reader.len();
+                for range in ranges {
+                    writer.write_all(multipart_header(asset.mime, range, len).as_bytes())?;
+                    copy_range(&mut reader, range, &mut writer)?;
+                    writer.write_all(b"\r\n")?;
+                }
+                writer.write_all(multipart_end().as_bytes())?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Loads the body of a 200 or 206 response, see [`write_body`](Self::write_body)
+    pub fn body(&mut self) -> io::Result<Vec<u8>> {
+        let mut body = Vec::new();
+        self.write_body(&mut body)?;
+        Ok(body)
+    }
+}
+
+fn copy_range<R: Read + Seek, W: Write>(
+    reader: &mut R,
+    range: &Range<u64>,
+    writer: &mut W,
+) -> io::Result<()> {
+    reader.seek(SeekFrom::Start(range.start))?;
+    let copied = io::copy(&mut reader.take(range.end - range.start), writer)?;
+    if copied != range.end - range.start {
+        return Err(io::Error::new(
+            io::ErrorKind::UnexpectedEof,
+            "Asset is shorter than the requested range",
+        ));
+    }
+    Ok(())
+}
+
+fn multipart_header(mime: &str, range: &Range<u64>, len: u64) -> String {
+    format!(
+        "--{MULTIPART_BOUNDARY}\r\nContent-Type: {mime}\r\nContent-Range: {}\r\n\r\n",
+        range::content_range(range, len)
+    )
+}
+
+fn multipart_end() -> String {
+    format!("--{MULTIPART_BOUNDARY}--\r\n")
+}
+
+/// Builds the quoted ETag of an asset variant from the content hash.
+///
+/// Each encoding, language and image format gets its own ETag, e.g. `"jLsQ8S_Iyso=-fr-br"`.
+pub fn etag(
+    hash: &str,
+    encoding: Encoding,
+    lang: Option<&LanguageIdentifier>,
+    format: Option<ImageFormat>,
+) -> String {
+    let mut etag = format!("\"{hash}");
+    if let Some(lang) = lang {
+        etag.push('-');
+        etag.push_str(&lang.to_string());
+    }
+    if let Some(format) = format {
+        etag.push('-');
+        etag.push_str(format.file_ending());
+    }
+    if let Some(ending) = encoding.file_ending() {
+        etag.push('-');
+        etag.push_str(ending);
+    }
+    etag.push('"');
+    etag
+}
+
+/// Checks an If-None-Match header against an ETag, using weak comparison
+pub fn etag_matches(if_none_match: &str, etag: &str) -> bool {
+    if_none_match.split(',').map(str::trim).any(|candidate| {
+        candidate == "*" || candidate.strip_prefix("W/").unwrap_or(candidate) == etag
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use icu_locid::langid;
+
+    #[test]
+    fn test_etag() {
+        assert_eq!(etag("abc=", Encoding::Identity, None, None), "\"abc=\"");
+        assert_eq!(etag("abc=", Encoding::Brotli, None, None), "\"abc=-br\"");
+        assert_eq!(
+            etag("abc=", Encoding::Gzip, Some(&langid!("fr")), None),
+            "\"abc=-fr-gzip\""
+        );
+        assert_eq!(
+            etag("abc=", Encoding::Identity, None, Some(ImageFormat::WebP)),
+            "\"abc=-webp\""
+        );
+    }
+
+    static DATA_PROVIDER: fn(&str) -> Option<Vec<u8>> = data_provider;
+    fn data_provider(_path: &str) -> Option<Vec<u8>> {
+        Some(b"0123456789".to_vec())
+    }
+
+    fn ok_response() -> AssetResponse<'static> {
+        let parts = crate::FilePathParts {
+            folder: None,
+            name: "app",
+            hash: None,
+            ext: "wasm",
+        };
+        let mut response = AssetResponse::new(200).header("Content-Type", "application/wasm");
+        response.asset = Some(Asset::new(
+            Encoding::Identity,
+            "application/wasm",
+            None,
+            parts,
+            &DATA_PROVIDER,
+        ));
+        response
+    }
+
+    #[test]
+    fn test_single_range_body() {
+        let mut response = ok_response().with_range("bytes=2-5", 10);
+        assert_eq!(response.status, 206);
+        assert_eq!(response.header_value("Content-Range"), Some("bytes 2-5/10"));
+        assert_eq!(response.header_value("Content-Length"), Some("4"));
+        assert_eq!(response.body().unwrap(), b"2345");
+    }
+
+    #[test]
+    fn test_multi_range_body() {
+        let mut response = ok_response().with_range("bytes=0-1,-2", 10);
+        assert_eq!(response.status, 206);
+        assert_eq!(
+            response.header_value("Content-Type"),
+            Some("multipart/byteranges; boundary=BUILDER_ASSETS_BYTERANGES")
+        );
+        let body = response.body().unwrap();
+        assert_eq!(
+            String::from_utf8(body.clone()).unwrap(),
+            "--BUILDER_ASSETS_BYTERANGES\r\n\
+             Content-Type: application/wasm\r\n\
+             Content-Range: bytes 0-1/10\r\n\r\n\
+             01\r\n\
+             --BUILDER_ASSETS_BYTERANGES\r\n\
+             Content-Type: application/wasm\r\n\
+             Content-Range: bytes 8-9/10\r\n\r\n\
+             89\r\n\
+             --BUILDER_ASSETS_BYTERANGES--\r\n"
+        );
+        assert_eq!(
+            response.header_value("Content-Length"),
+            Some(body.len().to_string().as_str())
+        );
+    }
+
+    #[test]
+    fn test_unsatisfiable_and_ignored_range() {
+        let response = ok_response().with_range("bytes=20-", 10);
+        assert_eq!(response.status, 416);
+        assert_eq!(response.header_value("Content-Range"), Some("bytes */10"));
+        assert!(response.asset.is_none());
+
+        let mut response = ok_response().with_range("lines=1-2", 10);
+        assert_eq!(response.status, 200);
+        assert_eq!(response.body().unwrap(), b"0123456789");
+    }
+
+    #[test]
+    fn test_etag_matches() {
+        let etag = "\"abc=-br\"";
+        assert!(etag_matches("\"abc=-br\"", etag));
+        assert!(etag_matches("W/\"abc=-br\"", etag));
+        assert!(etag_matches("\"other\", \"abc=-br\"", etag));
+        assert!(etag_matches("*", etag));
+        assert!(!etag_matches("\"abc=\"", etag));
+    }
+}
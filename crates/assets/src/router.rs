@@ -0,0 +1,148 @@
+use crate::{
+    asset::Asset,
+    catalog::AssetCatalog,
+    response::{AssetResponse, RequestHeaders},
+};
+
+/// Serves several catalogs mounted under different url prefixes, picking the
+/// catalog with the longest prefix that matches the request url.
+///
+/// ```
+/// use builder_assets::{AssetCatalog, AssetRouter};
+/// let router = AssetRouter::new()
+///     .mount(AssetCatalog::new().with_prefix("/static"))
+///     .mount(AssetCatalog::new().with_prefix("/admin/static"));
+/// assert_eq!(router.catalog_for("/admin/static/app.js").unwrap().prefix(), "/admin/static");
+/// assert!(router.catalog_for("/api/users").is_none());
+/// ```
+#[derive(Debug, Default)]
+pub struct AssetRouter {
+    /// Sorted by descending prefix length
+    catalogs: Vec<AssetCatalog>,
+}
+
+impl AssetRouter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a catalog, serving the urls under its [`prefix`](AssetCatalog::prefix).
+    ///
+    /// Catalogs with the same prefix should be combined with [`AssetCatalog::join`],
+    /// as only the first one mounted is used.
+    pub fn mount(mut self, catalog: AssetCatalog) -> Self {
+        let position = self
+            .catalogs
+            .iter()
+            .position(|mounted| mounted.prefix().len() < catalog.prefix().len())
+            .unwrap_or(self.catalogs.len());
+        self.catalogs.insert(position, catalog);
+        self
+    }
+
+    /// Returns the catalog with the longest prefix matching the url
+    pub fn catalog_for(&self, url_path: &str) -> Option<&AssetCatalog> {
+        self.catalogs
+            .iter()
+            .find(|catalog| catalog.is_under_prefix(url_path))
+    }
+
+    /// Returns all mounted catalogs, longest prefix first
+    pub fn catalogs(&self) -> impl Iterator<Item = &AssetCatalog> {
+        self.catalogs.iter()
+    }
+
+    /// Looks up the asset in the catalog for the url, see [`AssetCatalog::asset_for`]
+    pub fn asset_for(
+        &self,
+        url_path: &str,
+        accept_encodings: Option<&str>,
+        accept_languages: Option<&str>,
+    ) -> Option<Asset<'_>> {
+        self.catalog_for(url_path)?
+            .asset_for(url_path, accept_encodings, accept_languages)
+    }
+
+    /// Describes the HTTP response from the catalog for the url, see
+    /// [`AssetCatalog::respond`]. Returns 404 for urls outside all prefixes.
+    pub fn respond(&self, url_path: &str, headers: &RequestHeaders) -> AssetResponse<'_> {
+        match self.catalog_for(url_path) {
+            Some(catalog) => catalog.respond(url_path, headers),
+            None => AssetResponse::not_found(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{AssetSet, Encoding, FilePathParts};
+
+    static MOCK_PROVIDER: fn(&str) -> Option<Vec<u8>> = mock_provider;
+    fn mock_provider(path: &str) -> Option<Vec<u8>> {
+        Some(path.as_bytes().to_vec())
+    }
+
+    static STYLE_PARTS: FilePathParts = FilePathParts {
+        folder: None,
+        name: "style",
+        hash: None,
+        ext: "css",
+    };
+    static IDENTITY: [Encoding; 1] = [Encoding::Identity];
+    static STYLE_ASSET: AssetSet = AssetSet {
+        url_path: "/style.css",
+        file_path_parts: STYLE_PARTS,
+        available_encodings: &IDENTITY,
+        available_languages: None,
+        available_formats: None,
+        available_themes: None,
+        mime: "text/css",
+        provider: &MOCK_PROVIDER,
+    };
+    static ASSETS: [&AssetSet; 1] = [&STYLE_ASSET];
+
+    #[test]
+    fn test_longest_prefix_wins() {
+        let router = AssetRouter::new()
+            .mount(AssetCatalog::from_assets(&ASSETS))
+            .mount(AssetCatalog::from_assets(&ASSETS).with_prefix("/admin/static"))
+            .mount(AssetCatalog::from_assets(&ASSETS).with_prefix("/static"));
+
+        let prefixes: Vec<_> = router.catalogs().map(AssetCatalog::prefix).collect();
+        assert_eq!(prefixes, vec!["/admin/static", "/static", ""]);
+
+        assert_eq!(
+            router
+                .catalog_for("/admin/static/style.css")
+                .unwrap()
+                .prefix(),
+            "/admin/static"
+        );
+        assert_eq!(
+            router.catalog_for("/static/style.css").unwrap().prefix(),
+            "/static"
+        );
+        assert_eq!(router.catalog_for("/style.css").unwrap().prefix(), "");
+
+        let response = router.respond("/static/style.css", &RequestHeaders::new());
+        assert_eq!(response.status, 200);
+        assert_eq!(response.asset.unwrap().data_for(), b"style.css");
+
+        // Urls under a prefix are only looked up in the catalog mounted there
+        let response = router.respond("/admin/static/missing.css", &RequestHeaders::new());
+        assert_eq!(response.status, 404);
+    }
+
+    #[test]
+    fn test_no_matching_prefix() {
+        let router =
+            AssetRouter::new().mount(AssetCatalog::from_assets(&ASSETS).with_prefix("/static"));
+        assert!(router.catalog_for("/style.css").is_none());
+        assert!(router.asset_for("/style.css", None, None).is_none());
+        assert_eq!(
+            router.respond("/style.css", &RequestHeaders::new()).status,
+            404
+        );
+    }
+}
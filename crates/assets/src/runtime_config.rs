@@ -5,7 +5,9 @@ use std::sync::OnceLock;
 static ASSET_BASE_PATH: OnceLock<PathBuf> = OnceLock::new();
 
 /// Sets the base path for filesystem asset loading.
-/// This must be called before any asset operations when using DataProvider::FileSystem.
+/// This must be called before any asset operations when using DataProvider::FileSystem
+/// or DataProvider::Archive, unless the catalog is given its own provider with
+/// [`AssetCatalog::with_provider`](crate::AssetCatalog::with_provider).
 ///
 /// # Panics
 /// Panics if the base path has already been set.
@@ -55,6 +57,10 @@ For different deployment scenarios:
 - System package: set_asset_base_path("/usr/share/myapp/assets")
 - Relative to binary: set_asset_base_path(exe_dir.join("assets"))
 
+To serve assets from a different path per catalog, bind a provider instead:
+
+    let catalog = get_asset_catalog().with_provider(FileSystemProvider::new("/path/to/assets"));
+
 Alternatively, consider using DataProvider::Embed to avoid filesystem dependencies.
 "#
         )
@@ -0,0 +1,323 @@
+use crate::{catalog::AssetCatalog, response::RequestHeaders, shared::SharedCatalog};
+use bytes::Bytes;
+use http::{HeaderMap, HeaderName, HeaderValue, Method, Request, Response, StatusCode, header};
+use http_body_util::{Either, Full};
+use std::{
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll},
+};
+use tower::{Layer, Service};
+
+/// The body of responses from an [`AssetService`]: the asset data, or the body of the
+/// inner service for urls that are not assets
+pub type AssetServiceBody<B> = Either<Full<Bytes>, B>;
+
+/// Serves the assets of a catalog, falling through to the inner service for other urls.
+///
+/// GET and HEAD requests for assets are answered with [`AssetCatalog::respond`], which
+/// negotiates the encoding and language and handles conditional and range requests.
+/// The asset data is loaded on the tokio blocking thread pool when the returned future
+/// is polled, so slow providers don't block the runtime.
+///
+/// Created with a [`SharedCatalog`], the service serves the catalog stored in it at the
+/// time of each request.
+#[derive(Debug, Clone)]
+pub struct AssetService<S> {
+    catalog: SharedCatalog,
+    inner: S,
+}
+
+impl<S> AssetService<S> {
+    pub fn new(catalog: impl Into<SharedCatalog>, inner: S) -> Self {
+        Self {
+            catalog: catalog.into(),
+            inner,
+        }
+    }
+}
+
+impl<S, B, ResBody> Service<Request<B>> for AssetService<S>
+where
+    S: Service<Request<B>, Response = Response<ResBody>> + Clone + Send + 'static,
+    S::Future: Send + 'static,
+    S::Error: Send + 'static,
+    B: Send + 'static,
+    ResBody: Send + 'static,
+{
+    type Response = Response<AssetServiceBody<ResBody>>;
+    type Error = S::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: Request<B>) -> Self::Future {
+        let catalog = self.catalog.load();
+        let is_asset_request = matches!(*request.method(), Method::GET | Method::HEAD)
+            && catalog.handles_url(request.uri().path());
+
+        if !is_asset_request {
+            // Use the inner service that was polled ready, leaving a clone in its place
+            let clone = self.inner.clone();
+            let mut inner = std::mem::replace(&mut self.inner, clone);
+            return Box::pin(async move {
+                let response = inner.call(request).await?;
+                Ok(response.map(Either::Right))
+            });
+        }
+
+        Box::pin(async move {
+            let response = tokio::task::spawn_blocking(move || asset_response(&catalog, &request))
+                .await
+                .unwrap_or_else(|_| status_response(StatusCode::INTERNAL_SERVER_ERROR));
+            Ok(response.map(Either::Left))
+        })
+    }
+}
+
+/// Wraps services in an [`AssetService`] for the catalog
+#[derive(Debug, Clone)]
+pub struct AssetLayer {
+    catalog: SharedCatalog,
+}
+
+impl AssetLayer {
+    pub fn new(catalog: impl Into<SharedCatalog>) -> Self {
+        Self {
+            catalog: catalog.into(),
+        }
+    }
+}
+
+impl<S> Layer<S> for AssetLayer {
+    type Service = AssetService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        AssetService {
+            catalog: self.catalog.clone(),
+            inner,
+        }
+    }
+}
+
+fn header_str(headers: &HeaderMap, name: HeaderName) -> Option<&str> {
+    headers.get(name).and_then(|value| value.to_str().ok())
+}
+
+/// Builds the HTTP response for an asset request to the catalog, loading the asset data.
+///
+/// Responds with 500 when a header value, e.g. a mime type from a manifest, is not
+/// a valid HTTP header value.
+pub fn asset_response<B>(catalog: &AssetCatalog, request: &Request<B>) -> Response<Full<Bytes>> {
+    let headers = request.headers();
+    let request_headers = RequestHeaders {
+        accept: header_str(headers, header::ACCEPT),
+        accept_encoding: header_str(headers, header::ACCEPT_ENCODING),
+        accept_language: header_str(headers, header::ACCEPT_LANGUAGE),
+        if_none_match: header_str(headers, header::IF_NONE_MATCH),
+        range: header_str(headers, header::RANGE),
+        cookie: header_str(headers, header::COOKIE),
+        language: None,
+        theme: None,
+    };
+    let mut asset_response = catalog.respond(request.uri().path(), &request_headers);
+
+    let is_head = request.method() == Method::HEAD;
+    let body = match &asset_response.asset {
+        Some(_) if is_head => Bytes::new(),
+        Some(_) => match asset_response.body() {
+            Ok(body) => Bytes::from(body),
+            Err(_) => return status_response(StatusCode::NOT_FOUND),
+        },
+        None => Bytes::new(),
+    };
+
+    let mut builder = Response::builder().status(asset_response.status);
+    let mut has_content_length = false;
+    for (name, value) in &asset_response.headers {
+        has_content_length |= name.eq_ignore_ascii_case("Content-Length");
+        let Ok(value) = HeaderValue::from_str(value) else {
+            return status_response(StatusCode::INTERNAL_SERVER_ERROR);
+        };
+        builder = builder.header(*name, value);
+    }
+    if !has_content_length && let Some(asset) = &asset_response.asset {
+        let len = if is_head {
+            asset
+                .reader()
+                .map(|reader| reader.len())
+                .unwrap_or_default()
+        } else {
+            body.len() as u64
+        };
+        builder = builder.header(header::CONTENT_LENGTH, len);
+    }
+
+    builder
+        .body(Full::new(body))
+        .unwrap_or_else(|_| status_response(StatusCode::INTERNAL_SERVER_ERROR))
+}
+
+fn status_response(status: StatusCode) -> Response<Full<Bytes>> {
+    let mut response = Response::new(Full::new(Bytes::new()));
+    *response.status_mut() = status;
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{AssetSet, Encoding, FilePathParts, LanguageNegotiation};
+    use http_body_util::BodyExt;
+    use std::convert::Infallible;
+    use tower::{ServiceExt, service_fn};
+
+    static MOCK_PROVIDER: fn(&str) -> Option<Vec<u8>> = mock_provider;
+    fn mock_provider(path: &str) -> Option<Vec<u8>> {
+        match path {
+            "js/app.hash123=.js" => Some(b"console.log('app')".to_vec()),
+            "js/app.hash123=.js.br" => Some(b"brotli app".to_vec()),
+            _ => None,
+        }
+    }
+
+    static APP_PARTS: FilePathParts = FilePathParts {
+        folder: Some("js"),
+        name: "app",
+        hash: Some("hash123="),
+        ext: "js",
+    };
+    static APP_ENCODINGS: [Encoding; 2] = [Encoding::Identity, Encoding::Brotli];
+    static APP_ASSET: AssetSet = AssetSet {
+        url_path: "/js/app.hash123=.js",
+        file_path_parts: APP_PARTS,
+        available_encodings: &APP_ENCODINGS,
+        available_languages: None,
+        available_formats: None,
+        available_themes: None,
+        mime: "application/javascript",
+        provider: &MOCK_PROVIDER,
+    };
+    static INVALID_ASSET: AssetSet = AssetSet {
+        url_path: "/js/invalid.hash123=.js",
+        file_path_parts: APP_PARTS,
+        available_encodings: &APP_ENCODINGS,
+        available_languages: None,
+        available_formats: None,
+        available_themes: None,
+        mime: "application/javascript\n",
+        provider: &MOCK_PROVIDER,
+    };
+    static ASSETS: [&AssetSet; 2] = [&APP_ASSET, &INVALID_ASSET];
+
+    fn service()
+    -> impl Service<Request<String>, Response = Response<AssetServiceBody<String>>, Error = Infallible>
+    {
+        service_for(AssetCatalog::from_assets(&ASSETS))
+    }
+
+    fn service_for(
+        catalog: AssetCatalog,
+    ) -> impl Service<Request<String>, Response = Response<AssetServiceBody<String>>, Error = Infallible>
+    {
+        let fallback = service_fn(|request: Request<String>| async move {
+            Ok::<_, Infallible>(Response::new(format!("fallback {}", request.uri().path())))
+        });
+        AssetLayer::new(catalog).layer(fallback)
+    }
+
+    fn build(method: Method, uri: &str) -> http::request::Builder {
+        Request::builder().method(method).uri(uri)
+    }
+
+    async fn body_bytes<B>(response: Response<AssetServiceBody<B>>) -> Vec<u8> {
+        match response.into_body() {
+            Either::Left(body) => body.collect().await.unwrap().to_bytes().to_vec(),
+            Either::Right(_) => panic!("Expected an asset body"),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_serves_asset_with_negotiation() {
+        let request = build(Method::GET, "/js/app.hash123=.js")
+            .header("Accept-Encoding", "br, gzip")
+            .body(String::new())
+            .unwrap();
+        let response = service().oneshot(request).await.unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()["content-encoding"], "br");
+        assert_eq!(response.headers()["content-length"], "10");
+        assert_eq!(response.headers()["etag"], "\"hash123=-br\"");
+        assert_eq!(body_bytes(response).await, b"brotli app");
+    }
+
+    #[tokio::test]
+    async fn test_head_request() {
+        let request = build(Method::HEAD, "/js/app.hash123=.js")
+            .body(String::new())
+            .unwrap();
+        let response = service().oneshot(request).await.unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()["content-length"], "18");
+        assert!(body_bytes(response).await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_conditional_and_range_requests() {
+        let request = build(Method::GET, "/js/app.hash123=.js")
+            .header("If-None-Match", "\"hash123=\"")
+            .body(String::new())
+            .unwrap();
+        let response = service().oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_MODIFIED);
+        assert!(body_bytes(response).await.is_empty());
+
+        let request = build(Method::GET, "/js/app.hash123=.js")
+            .header("Range", "bytes=0-6")
+            .body(String::new())
+            .unwrap();
+        let response = service().oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::PARTIAL_CONTENT);
+        assert_eq!(response.headers()["content-range"], "bytes 0-6/18");
+        assert_eq!(body_bytes(response).await, b"console");
+    }
+
+    #[tokio::test]
+    async fn test_invalid_header_value() {
+        let request = build(Method::GET, "/js/invalid.hash123=.js")
+            .body(String::new())
+            .unwrap();
+        let response = service().oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::INTERNAL_SERVER_ERROR);
+    }
+
+    #[tokio::test]
+    async fn test_falls_through_to_inner_service() {
+        for (method, uri) in [
+            (Method::GET, "/api/users"),
+            (Method::POST, "/js/app.hash123=.js"),
+        ] {
+            let request = build(method, uri).body(String::new()).unwrap();
+            let response = service().oneshot(request).await.unwrap();
+            match response.into_body() {
+                Either::Right(body) => assert_eq!(body, format!("fallback {uri}")),
+                Either::Left(_) => panic!("Expected the inner service to respond"),
+            }
+        }
+
+        // `api` parses as a language, but is not one of the asset's languages
+        let catalog = AssetCatalog::from_assets(&ASSETS)
+            .with_language_negotiation(LanguageNegotiation::new().with_path_segment());
+        let uri = "/api/js/app.hash123=.js";
+        let request = build(Method::GET, uri).body(String::new()).unwrap();
+        let response = service_for(catalog).oneshot(request).await.unwrap();
+        match response.into_body() {
+            Either::Right(body) => assert_eq!(body, format!("fallback {uri}")),
+            Either::Left(_) => panic!("Expected the inner service to respond"),
+        }
+    }
+}
@@ -0,0 +1,57 @@
+use crate::catalog::AssetCatalog;
+use std::sync::{Arc, RwLock};
+
+/// A handle to a catalog that can be replaced while it is served, e.g. with a catalog
+/// for a new manifest when the output directory is redeployed.
+///
+/// Clones share the catalog. Requests hold on to the catalog they started with,
+/// so replacing it never interrupts a response in progress.
+///
+/// ```
+/// use builder_assets::{AssetCatalog, SharedCatalog};
+/// let shared = SharedCatalog::new(AssetCatalog::new());
+/// let handle = shared.clone();
+/// handle.store(AssetCatalog::new().with_prefix("/static"));
+/// assert_eq!(shared.load().prefix(), "/static");
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct SharedCatalog {
+    current: Arc<RwLock<Arc<AssetCatalog>>>,
+}
+
+impl SharedCatalog {
+    pub fn new(catalog: impl Into<Arc<AssetCatalog>>) -> Self {
+        Self {
+            current: Arc::new(RwLock::new(catalog.into())),
+        }
+    }
+
+    /// Returns the current catalog
+    pub fn load(&self) -> Arc<AssetCatalog> {
+        self.current
+            .read()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .clone()
+    }
+
+    /// Replaces the catalog, returning the previous one
+    pub fn store(&self, catalog: impl Into<Arc<AssetCatalog>>) -> Arc<AssetCatalog> {
+        let mut current = self
+            .current
+            .write()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        std::mem::replace(&mut current, catalog.into())
+    }
+}
+
+impl From<AssetCatalog> for SharedCatalog {
+    fn from(catalog: AssetCatalog) -> Self {
+        Self::new(catalog)
+    }
+}
+
+impl From<Arc<AssetCatalog>> for SharedCatalog {
+    fn from(catalog: Arc<AssetCatalog>) -> Self {
+        Self::new(catalog)
+    }
+}
@@ -4,7 +4,7 @@ use std::env;
 use builder_command::{BuilderCmd, Cmd};
 use camino_fs::*;
 use common::{LOG_LEVEL, RELEASE, setup_logging};
-use common::{asset_code_generation, site_fs};
+use common::{asset_code_generation, asset_manifest, mobile_asset_code, site_fs};
 
 fn main() {
     let args = std::env::args().collect::<Vec<_>>();
@@ -67,4 +67,14 @@ pub fn run(mut builder: BuilderCmd) {
     if let Err(e) = asset_code_generation::finalize_asset_code_outputs() {
         eprintln!("Failed to write asset code files: {}", e);
     }
+
+    // Finalize Swift and Kotlin asset constants after all commands have completed
+    if let Err(e) = mobile_asset_code::finalize_mobile_asset_outputs() {
+        eprintln!("Failed to write mobile asset code files: {}", e);
+    }
+
+    // Finalize JSON asset manifests after all commands have completed
+    if let Err(e) = asset_manifest::finalize_asset_manifests() {
+        eprintln!("Failed to write asset manifest files: {}", e);
+    }
 }
@@ -12,6 +12,11 @@ pub struct CopyCmd {
     /// File extensions that should be processed when searching for files in the input directory
     pub file_extensions: Vec<String>,
 
+    /// Copy `<file>.avif` and `<file>.webp` found next to a file as alternative image
+    /// formats of it, served to browsers that accept them
+    #[serde(default)]
+    pub image_formats: bool,
+
     pub output: Vec<Output>,
 }
 
@@ -21,6 +26,7 @@ impl CopyCmd {
             src_dir: input_dir.into(),
             recursive: false,
             file_extensions: Default::default(),
+            image_formats: false,
             output: Vec::new(),
         }
     }
@@ -38,6 +44,11 @@ impl CopyCmd {
         self
     }
 
+    pub fn image_formats(mut self, val: bool) -> Self {
+        self.image_formats = val;
+        self
+    }
+
     pub fn add_output(mut self, output: Output) -> Self {
         self.output.push(output);
         self
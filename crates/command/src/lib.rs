@@ -11,14 +11,17 @@ mod wasm;
 use std::{env, path::Path, process::Command};
 
 pub use assemble::AssembleCmd;
+pub use builder_assets::AssetManifest;
 use camino_fs::Utf8PathBuf;
 pub use copy::CopyCmd;
 pub use fontforge::FontForgeCmd;
 use fs_err as fs;
 pub use localized::LocalizedCmd;
 use log::LevelFilter;
-pub use out::{AssetMetadata, DataProvider, Encoding, Output};
-pub use sass::SassCmd;
+pub use out::{AssetMetadata, DataProvider, Encoding, ImageFormat, Output};
+pub use sass::{
+    BrowserTargets, CssModules, Purge, SassCmd, SassValue, SourceMapMode, Theme, ThemeLayout,
+};
 use serde::{Deserialize, Serialize};
 pub use swift_package::SwiftPackageCmd;
 pub use uniffi::UniffiCmd;
@@ -222,6 +225,8 @@ impl BuilderCmd {
     }
 }
 
+// The commands are only built once per build, so their size doesn't matter
+#[allow(clippy::large_enum_variant)]
 #[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub enum Cmd {
     Uniffi(UniffiCmd),
@@ -257,6 +262,14 @@ fn roundtrip() {
     assert_eq!(cmd, cmd2);
 }
 
+#[test]
+fn deserialize_sass_without_new_fields() {
+    // Written before the sass options were added
+    let json = r#"{"in_scss":"style.scss","optimize":true,"output":[],"replacements":[]}"#;
+    let cmd = serde_json::from_str::<SassCmd>(json).unwrap();
+    assert_eq!(cmd, SassCmd::new("style.scss").optimize(true));
+}
+
 #[test]
 fn roundtrip_log_destinations() {
     // Test all log destination variants
@@ -1,3 +1,4 @@
+use builder_assets::FilePathParts;
 use camino_fs::*;
 use icu_locid::LanguageIdentifier;
 use serde::{Deserialize, Serialize};
@@ -10,12 +11,36 @@ pub enum Encoding {
     Identity,
 }
 
+/// Alternative image formats written next to the original image, served to
+/// browsers that list them in the Accept header
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum ImageFormat {
+    Avif,
+    WebP,
+}
+
+impl ImageFormat {
+    /// The ending appended to the original file name, e.g. `logo.png.avif`
+    pub fn file_ending(&self) -> &'static str {
+        match self {
+            ImageFormat::Avif => "avif",
+            ImageFormat::WebP => "webp",
+        }
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum DataProvider {
     /// Assets are embedded in the binary using rust-embed
     Embed,
     /// Assets are loaded from the filesystem at runtime
     FileSystem,
+    /// Assets are compiled into the binary using `include_bytes!` and served
+    /// without copying
+    IncludeBytes,
+    /// Assets are packed into a single archive file in the output dir, which is
+    /// opened (or memory-mapped) at runtime. The packed files are kept.
+    Archive,
 }
 
 impl Display for Encoding {
@@ -70,9 +95,107 @@ pub struct AssetMetadata {
     pub ext: String,
     pub available_encodings: Vec<Encoding>,
     pub available_languages: Option<Vec<LanguageIdentifier>>,
+    #[serde(default)]
+    pub available_formats: Option<Vec<ImageFormat>>,
+    /// The themes of a stylesheet compiled with theme variants, stored like languages
+    #[serde(default)]
+    pub available_themes: Option<Vec<String>>,
     pub mime: String,
 }
 
+impl AssetMetadata {
+    /// The paths of all encoding, language and image format variants, relative to the output dir.
+    ///
+    /// Regular files: `folder/name[.hash].ext[.format_ext][.encoding_ext]`
+    /// Translated files: `folder/name[.hash].ext/lang.ext[.format_ext][.encoding_ext]`
+    /// Themed files: `folder/name[.hash].ext/theme.ext[.encoding_ext]`
+    pub fn variant_paths(&self) -> Vec<String> {
+        let parts = FilePathParts {
+            folder: self.folder.as_deref(),
+            name: &self.name,
+            hash: self.hash.as_deref(),
+            ext: &self.ext,
+        };
+        let encodings = || {
+            self.available_encodings
+                .iter()
+                .map(builder_assets::Encoding::from)
+        };
+        // the original format first, then the alternative image formats
+        let formats = self.available_formats.as_deref().unwrap_or_default();
+        let formats = std::iter::once(None).chain(formats.iter().map(|format| Some(format.into())));
+
+        let mut paths = Vec::new();
+        for format in formats {
+            match (&self.available_languages, &self.available_themes) {
+                (Some(langs), _) => {
+                    for lang in langs {
+                        paths.extend(encodings().map(|encoding| {
+                            parts.construct_variant_path(encoding, Some(lang), format)
+                        }));
+                    }
+                }
+                // theme variants are only written for stylesheets, which have no image formats
+                (None, Some(themes)) if format.is_none() => {
+                    for theme in themes {
+                        paths.extend(
+                            encodings().map(|encoding| parts.construct_theme_path(encoding, theme)),
+                        );
+                    }
+                }
+                (None, Some(_)) => {}
+                (None, None) => paths.extend(
+                    encodings()
+                        .map(|encoding| parts.construct_variant_path(encoding, None, format)),
+                ),
+            }
+        }
+        paths
+    }
+}
+
+impl From<&Encoding> for builder_assets::Encoding {
+    fn from(encoding: &Encoding) -> Self {
+        match encoding {
+            Encoding::Brotli => Self::Brotli,
+            Encoding::Gzip => Self::Gzip,
+            Encoding::Identity => Self::Identity,
+        }
+    }
+}
+
+impl From<&AssetMetadata> for builder_assets::ManifestAsset {
+    fn from(metadata: &AssetMetadata) -> Self {
+        Self {
+            url_path: metadata.url_path.clone(),
+            folder: metadata.folder.clone(),
+            name: metadata.name.clone(),
+            hash: metadata.hash.clone(),
+            ext: metadata.ext.clone(),
+            available_encodings: metadata
+                .available_encodings
+                .iter()
+                .map(Into::into)
+                .collect(),
+            available_languages: metadata.available_languages.clone(),
+            available_formats: (metadata.available_formats.as_ref())
+                .map(|formats| formats.iter().map(Into::into).collect()),
+            available_themes: (metadata.available_themes.as_ref())
+                .map(|themes| themes.iter().cloned().map(Into::into).collect()),
+            mime: metadata.mime.clone(),
+        }
+    }
+}
+
+impl From<&ImageFormat> for builder_assets::ImageFormat {
+    fn from(format: &ImageFormat) -> Self {
+        match format {
+            ImageFormat::Avif => Self::Avif,
+            ImageFormat::WebP => Self::WebP,
+        }
+    }
+}
+
 #[derive(Debug, PartialEq, Eq, Clone, Default, Serialize, Deserialize)]
 pub struct Output {
     /// Folder where the output files should be written
@@ -97,6 +220,16 @@ pub struct Output {
     /// Asset code generation configuration (path and provider type)
     pub asset_code_generation: Option<(Utf8PathBuf, DataProvider)>,
 
+    /// Optional path to write asset constants as a Swift file
+    pub swift_asset_code_path: Option<Utf8PathBuf>,
+
+    /// Optional path and package name to write asset constants as a Kotlin file
+    pub kotlin_asset_code_path: Option<(Utf8PathBuf, String)>,
+
+    /// Optional path to write the asset metadata as a JSON manifest, loaded at
+    /// runtime without code generation
+    pub asset_manifest_path: Option<Utf8PathBuf>,
+
     /// Collected asset metadata during file operations
     pub asset_metadata: Vec<AssetMetadata>,
 }
@@ -113,6 +246,9 @@ impl Output {
             checksum: false,
             hash_output_path: None,
             asset_code_generation: None,
+            swift_asset_code_path: None,
+            kotlin_asset_code_path: None,
+            asset_manifest_path: None,
             asset_metadata: Vec::new(),
         }
     }
@@ -128,6 +264,9 @@ impl Output {
             checksum: true,
             hash_output_path: None,
             asset_code_generation: None,
+            swift_asset_code_path: None,
+            kotlin_asset_code_path: None,
+            asset_manifest_path: None,
             asset_metadata: Vec::new(),
         }
     }
@@ -143,6 +282,9 @@ impl Output {
             checksum: false,
             hash_output_path: None,
             asset_code_generation: None,
+            swift_asset_code_path: None,
+            kotlin_asset_code_path: None,
+            asset_manifest_path: None,
             asset_metadata: Vec::new(),
         }
     }
@@ -166,6 +308,32 @@ impl Output {
         self
     }
 
+    /// Writes a Swift `enum Assets` with the url, hash, mime type and languages
+    /// of every asset written to this output.
+    pub fn swift_asset_code_gen<P: Into<Utf8PathBuf>>(mut self, path: P) -> Self {
+        self.swift_asset_code_path = Some(path.into());
+        self
+    }
+
+    /// Writes a Kotlin `object Assets` in the given package with the url, hash,
+    /// mime type and languages of every asset written to this output.
+    pub fn kotlin_asset_code_gen<P: Into<Utf8PathBuf>, S: AsRef<str>>(
+        mut self,
+        path: P,
+        package: S,
+    ) -> Self {
+        self.kotlin_asset_code_path = Some((path.into(), package.as_ref().to_string()));
+        self
+    }
+
+    /// Writes the url, file path parts, encodings, languages, formats and mime type of
+    /// every asset written to this output as JSON, which `builder-assets` loads at runtime
+    /// with `AssetCatalog::from_manifest`.
+    pub fn asset_manifest<P: Into<Utf8PathBuf>>(mut self, path: P) -> Self {
+        self.asset_manifest_path = Some(path.into());
+        self
+    }
+
     pub fn uncompressed(&self) -> bool {
         // if none are set, then default to uncompressed
         let default_uncompressed = !self.uncompressed && !self.brotli && !self.gzip;
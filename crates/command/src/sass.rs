@@ -1,39 +1,396 @@
 use camino_fs::Utf8PathBuf;
 use serde::{Deserialize, Serialize};
+use std::fmt::Display;
 
 use crate::Output;
 
+/// A value passed from Rust to Sass, written with the Sass syntax of its type
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SassValue {
+    /// A quoted string like `"https://cdn.example.com"`
+    String(String),
+    /// A number with an optional unit like `4px`, `1.5` or `50%`
+    Number(String),
+    /// A color like `#0af`, `rebeccapurple` or `rgb(0 170 255)`
+    Color(String),
+    Bool(bool),
+    Null,
+    /// A comma separated list
+    List(Vec<SassValue>),
+    /// A map like `("primary": #0af, "secondary": #333)`
+    Map(Vec<(String, SassValue)>),
+}
+
+impl SassValue {
+    pub fn number<V: Display>(value: V, unit: &str) -> Self {
+        Self::Number(format!("{value}{unit}"))
+    }
+
+    pub fn color<S: Into<String>>(color: S) -> Self {
+        Self::Color(color.into())
+    }
+}
+
+impl From<&str> for SassValue {
+    fn from(value: &str) -> Self {
+        Self::String(value.to_string())
+    }
+}
+
+impl From<String> for SassValue {
+    fn from(value: String) -> Self {
+        Self::String(value)
+    }
+}
+
+impl From<bool> for SassValue {
+    fn from(value: bool) -> Self {
+        Self::Bool(value)
+    }
+}
+
+macro_rules! number_from {
+    ($($ty:ty),*) => {
+        $(impl From<$ty> for SassValue {
+            fn from(value: $ty) -> Self {
+                Self::Number(value.to_string())
+            }
+        })*
+    };
+}
+number_from!(i32, i64, u32, u64, f32, f64);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum SourceMapMode {
+    /// No source map, the sources are not shipped with the CSS
+    #[default]
+    None,
+    /// Embed the source map with the sources in the CSS file
+    Inline,
+    /// Write the source map next to the CSS file as `<name>[.<hash>].css.map`
+    External,
+}
+
+/// The browsers the CSS is optimized for
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum BrowserTargets {
+    /// A `browserslist` or `.browserslistrc` file in the crate directory or a parent,
+    /// else `package.metadata.builder.browserslist` in the Cargo.toml of the crate,
+    /// else `>0.3%, defaults, supports es6-module, maintained node versions`
+    #[default]
+    Project,
+    /// A browserslist query like `safari >= 15.4, chrome >= 110`
+    Query(String),
+    /// Minimum versions by browser, like `("safari", "15.4")`. The browsers are the ones
+    /// Lightning CSS knows: android, chrome, edge, firefox, ie, ios_saf, opera, safari
+    /// and samsung.
+    Versions(Vec<(String, String)>),
+}
+
+impl BrowserTargets {
+    pub fn query<S: Into<String>>(query: S) -> Self {
+        Self::Query(query.into())
+    }
+
+    pub fn versions<B, V>(it: impl IntoIterator<Item = (B, V)>) -> Self
+    where
+        B: Into<String>,
+        V: Into<String>,
+    {
+        Self::Versions(
+            it.into_iter()
+                .map(|(browser, version)| (browser.into(), version.into()))
+                .collect(),
+        )
+    }
+}
+
+/// Scopes the class names, keyframes and optionally custom properties of the CSS,
+/// like CSS Modules, and generates constants with the scoped names
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CssModules {
+    /// The pattern of the scoped names, `[hash]_[local]` when not set.
+    /// The placeholders are `[name]`, `[local]`, `[hash]` and `[content-hash]`.
+    /// With [themes](SassCmd::add_theme), the names must be the same for all themes,
+    /// so `[content-hash]` fails the build when the themes compile to different CSS.
+    pub pattern: Option<String>,
+
+    /// Also scope custom properties like `--brand-color`
+    pub dashed_idents: bool,
+
+    /// The Rust file with a module of constants for each stylesheet
+    pub rust_module: Option<Utf8PathBuf>,
+
+    /// The TypeScript file with an object of the scoped names for each stylesheet
+    pub typescript_module: Option<Utf8PathBuf>,
+}
+
+impl CssModules {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn pattern<S: Into<String>>(mut self, pattern: S) -> Self {
+        self.pattern = Some(pattern.into());
+        self
+    }
+
+    pub fn dashed_idents(mut self, dashed_idents: bool) -> Self {
+        self.dashed_idents = dashed_idents;
+        self
+    }
+
+    pub fn rust_module<P: Into<Utf8PathBuf>>(mut self, path: P) -> Self {
+        self.rust_module = Some(path.into());
+        self
+    }
+
+    pub fn typescript_module<P: Into<Utf8PathBuf>>(mut self, path: P) -> Self {
+        self.typescript_module = Some(path.into());
+        self
+    }
+}
+
+/// Removes the rules whose selectors use class names or ids that are not found in
+/// the content files. Names are matched as written in the content, so purging
+/// doesn't work with the generated constants of CSS Modules.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Purge {
+    /// Glob patterns of the files scanned for names, like `src/**/*.rs` or `web/**/*.html`
+    pub content: Vec<String>,
+
+    /// Names that are kept without being found, as glob patterns like `is-*`
+    pub safelist: Vec<String>,
+}
+
+impl Purge {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add_content<S: Into<String>>(mut self, pattern: S) -> Self {
+        self.content.push(pattern.into());
+        self
+    }
+
+    pub fn safelist<S: Into<String>>(mut self, names: impl IntoIterator<Item = S>) -> Self {
+        self.safelist.extend(names.into_iter().map(Into::into));
+        self
+    }
+}
+
+/// A named set of variables that the stylesheets are compiled with, in addition to the
+/// variables of the [`SassCmd`]. The name is the `$theme` variable of the `builder` module.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Theme {
+    pub name: String,
+    pub variables: Vec<(String, SassValue)>,
+}
+
+impl Theme {
+    pub fn new<S: Into<String>>(name: S) -> Self {
+        Self {
+            name: name.into(),
+            variables: Vec::new(),
+        }
+    }
+
+    pub fn add_variable<S: Into<String>, V: Into<SassValue>>(mut self, name: S, value: V) -> Self {
+        self.variables.push((name.into(), value.into()));
+        self
+    }
+}
+
+/// How the stylesheets of the themes are written
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum ThemeLayout {
+    /// A stylesheet per theme, `<name>.<theme>[.<hash>].css`. Each is an asset of
+    /// its own that doesn't list the themes, the page links the one of its theme.
+    #[default]
+    Files,
+    /// A folder like the one of translations, `<name>[.<hash>].css/<theme>.css`. The
+    /// AssetSet lists the themes and serves the one of the `theme` cookie.
+    /// External source maps are inlined.
+    Folder,
+}
+
 #[derive(Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
 pub struct SassCmd {
+    /// The entry stylesheet, or a glob pattern like `styles/pages/**/*.scss`.
+    /// Plain `.css` entries are bundled with their `@import`s by Lightning CSS
+    /// instead of compiled with Sass. Relative `url()`s stay relative to the entry,
+    /// so the assets must be laid out next to the written CSS like they are next
+    /// to the entry.
     pub in_scss: Utf8PathBuf,
 
+    /// More entry stylesheets or glob patterns compiled with the same settings
+    #[serde(default)]
+    pub entries: Vec<Utf8PathBuf>,
+
+    /// Directories searched for `@use`, `@forward` and `@import` urls that are not
+    /// relative to the importing file
+    #[serde(default)]
+    pub load_paths: Vec<Utf8PathBuf>,
+
     pub optimize: bool,
 
+    /// The browsers the optimized CSS must support
+    #[serde(default)]
+    pub browser_targets: BrowserTargets,
+
+    #[serde(default)]
+    pub source_map: SourceMapMode,
+
+    #[serde(default)]
+    pub css_modules: Option<CssModules>,
+
+    #[serde(default)]
+    pub purge: Option<Purge>,
+
     pub output: Vec<Output>,
     pub replacements: Vec<(String, String)>,
+
+    /// Fail when a replacement doesn't match anything in the CSS of an entry
+    #[serde(default)]
+    pub strict_replacements: bool,
+
+    /// Variables of the generated `builder` module, used with `@use "builder"`
+    #[serde(default)]
+    pub variables: Vec<(String, SassValue)>,
+
+    /// Modules configured with `@use <url> with (...)` before the entry is compiled
+    #[serde(default)]
+    pub module_config: Vec<(String, Vec<(String, SassValue)>)>,
+
+    /// Each entry is compiled once per theme, plain `.css` entries only once
+    #[serde(default)]
+    pub themes: Vec<Theme>,
+
+    #[serde(default)]
+    pub theme_layout: ThemeLayout,
 }
 
 impl SassCmd {
     pub fn new<P: Into<Utf8PathBuf>>(in_scss: P) -> Self {
         Self {
             in_scss: in_scss.into(),
+            entries: Vec::new(),
+            load_paths: Vec::new(),
             optimize: false,
+            browser_targets: BrowserTargets::Project,
+            source_map: SourceMapMode::None,
+            css_modules: None,
+            purge: None,
             output: Vec::new(),
             replacements: Vec::new(),
+            strict_replacements: false,
+            variables: Vec::new(),
+            module_config: Vec::new(),
+            themes: Vec::new(),
+            theme_layout: ThemeLayout::Files,
         }
     }
 
+    /// Adds an entry stylesheet or a glob pattern of entry stylesheets.
+    ///
+    /// Files matched by a glob pattern keep their directory below the part of the
+    /// pattern without wildcards, so `styles/**/*.scss` writes `styles/admin/users.scss`
+    /// to `admin/users.css`. Partials, whose names start with `_`, are skipped.
+    pub fn add_entry<P: Into<Utf8PathBuf>>(mut self, path: P) -> Self {
+        self.entries.push(path.into());
+        self
+    }
+
     pub fn add_css_replacement<S1: AsRef<str>, S2: AsRef<str>>(mut self, from: S1, to: S2) -> Self {
         self.replacements
             .push((from.as_ref().to_string(), to.as_ref().to_string()));
         self
     }
 
+    pub fn strict_replacements(mut self, strict: bool) -> Self {
+        self.strict_replacements = strict;
+        self
+    }
+
+    /// Adds a variable to the generated `builder` module, so that stylesheets can
+    /// use values from Rust, like `builder.$cdn-url` after `@use "builder"`
+    pub fn add_variable<S: Into<String>, V: Into<SassValue>>(mut self, name: S, value: V) -> Self {
+        self.variables.push((name.into(), value.into()));
+        self
+    }
+
+    /// Configures the `!default` variables of a module, like `@use <url> with (...)`.
+    /// The url is resolved relative to the entry or in the load paths, and the
+    /// stylesheets that use the module get the configured values.
+    pub fn configure_module<U, It, S, V>(mut self, url: U, variables: It) -> Self
+    where
+        U: Into<String>,
+        It: IntoIterator<Item = (S, V)>,
+        S: Into<String>,
+        V: Into<SassValue>,
+    {
+        let variables = variables
+            .into_iter()
+            .map(|(name, value)| (name.into(), value.into()))
+            .collect();
+        self.module_config.push((url.into(), variables));
+        self
+    }
+
+    /// Adds a theme, see [`Theme`]. The first theme is the default of the
+    /// [`ThemeLayout::Folder`] layout.
+    pub fn add_theme(mut self, theme: Theme) -> Self {
+        self.themes.push(theme);
+        self
+    }
+
+    pub fn theme_layout(mut self, layout: ThemeLayout) -> Self {
+        self.theme_layout = layout;
+        self
+    }
+
+    /// Adds a directory to search for stylesheets, like `--load-path` of dart-sass.
+    ///
+    /// Stylesheets of other crates can also be used with `@use "pkg:<crate>/<path>"`,
+    /// which resolves `<path>` in the directory of the crate.
+    pub fn add_load_path<P: Into<Utf8PathBuf>>(mut self, path: P) -> Self {
+        self.load_paths.push(path.into());
+        self
+    }
+
     pub fn optimize(mut self, optimize: bool) -> Self {
         self.optimize = optimize;
         self
     }
 
+    /// Sets the browsers to optimize for, which decides the vendor prefixes and the
+    /// syntax lowering done by Lightning CSS
+    pub fn browser_targets(mut self, targets: BrowserTargets) -> Self {
+        self.browser_targets = targets;
+        self
+    }
+
+    /// Sets how the source map of the CSS is shipped. Source maps are produced by the
+    /// external sass binary and kept correct through replacements and optimization.
+    /// The built-in grass compiler doesn't produce source maps.
+    pub fn source_map(mut self, mode: SourceMapMode) -> Self {
+        self.source_map = mode;
+        self
+    }
+
+    /// Compiles the stylesheets as CSS Modules, see [`CssModules`]
+    pub fn css_modules(mut self, css_modules: CssModules) -> Self {
+        self.css_modules = Some(css_modules);
+        self
+    }
+
+    /// Removes the unused rules before the CSS is optimized, see [`Purge`]. Skipped
+    /// with a warning for [`css_modules`](Self::css_modules).
+    pub fn purge(mut self, purge: Purge) -> Self {
+        self.purge = Some(purge);
+        self
+    }
+
     pub fn add_output(mut self, output: Output) -> Self {
         self.output.push(output);
         self
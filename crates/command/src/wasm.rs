@@ -5,9 +5,10 @@ use serde::{Deserialize, Serialize};
 
 use crate::Output;
 
-#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
 pub enum DebugSymbolsMode {
     /// Strip debug symbols without preserving them
+    #[default]
     Strip,
     /// Keep debug symbols in the main WASM file
     Keep,
@@ -17,12 +18,6 @@ pub enum DebugSymbolsMode {
     WriteAdjacent,
 }
 
-impl Default for DebugSymbolsMode {
-    fn default() -> Self {
-        Self::Strip
-    }
-}
-
 impl DebugSymbolsMode {
     pub fn write_to(path: impl Into<Utf8PathBuf>) -> Self {
         Self::WriteTo(path.into())
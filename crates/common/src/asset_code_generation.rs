@@ -1,4 +1,5 @@
 use anyhow;
+use builder_assets::ArchiveWriter;
 use builder_command::{AssetMetadata, DataProvider};
 use camino_fs::{Utf8Path, Utf8PathBuf};
 use std::collections::{BTreeMap, HashSet};
@@ -14,9 +15,14 @@ pub struct ProviderConfig {
 #[derive(Debug, Clone)]
 pub struct AssetCodeConfig {
     pub embed_config: Option<ProviderConfig>,
+    pub include_bytes_config: Option<ProviderConfig>,
+    pub archive_config: Option<ProviderConfig>,
     pub filesystem_config: Option<ProviderConfig>,
 }
 
+/// File name of the archive written for `DataProvider::Archive`, in the output dir
+pub const ASSET_ARCHIVE_NAME: &str = "assets.archive";
+
 static ASSET_CODE_CONFIGS: OnceLock<Mutex<BTreeMap<Utf8PathBuf, AssetCodeConfig>>> =
     OnceLock::new();
 
@@ -36,6 +42,8 @@ pub fn register_asset_metadata_for_output(
         .entry(output_path.to_path_buf())
         .or_insert_with(|| AssetCodeConfig {
             embed_config: None,
+            include_bytes_config: None,
+            archive_config: None,
             filesystem_config: None,
         });
 
@@ -47,6 +55,24 @@ pub fn register_asset_metadata_for_output(
             });
             embed_config.metadata.extend(metadata);
         }
+        DataProvider::IncludeBytes => {
+            let include_bytes_config =
+                config
+                    .include_bytes_config
+                    .get_or_insert_with(|| ProviderConfig {
+                        metadata: Vec::new(),
+                        // include_bytes! resolves relative paths from the generated file
+                        base_path: absolute_path(base_path),
+                    });
+            include_bytes_config.metadata.extend(metadata);
+        }
+        DataProvider::Archive => {
+            let archive_config = config.archive_config.get_or_insert_with(|| ProviderConfig {
+                metadata: Vec::new(),
+                base_path: base_path.to_path_buf(),
+            });
+            archive_config.metadata.extend(metadata);
+        }
         DataProvider::FileSystem => {
             let filesystem_config =
                 config
@@ -60,6 +86,16 @@ pub fn register_asset_metadata_for_output(
     }
 }
 
+fn absolute_path(path: &Utf8Path) -> Utf8PathBuf {
+    if path.is_absolute() {
+        return path.to_path_buf();
+    }
+    let current_dir = std::env::current_dir().expect("Failed to get current directory");
+    Utf8PathBuf::from_path_buf(current_dir)
+        .expect("Current directory is not valid UTF-8")
+        .join(path)
+}
+
 /// Finalizes asset code generation and writes all accumulated metadata to their respective output files
 pub fn finalize_asset_code_outputs() -> anyhow::Result<()> {
     let configs = get_asset_code_configs().lock().unwrap();
@@ -69,12 +105,24 @@ pub fn finalize_asset_code_outputs() -> anyhow::Result<()> {
             .embed_config
             .as_ref()
             .is_some_and(|c| !c.metadata.is_empty());
+        let has_include_bytes = config
+            .include_bytes_config
+            .as_ref()
+            .is_some_and(|c| !c.metadata.is_empty());
+        let has_archive = config
+            .archive_config
+            .as_ref()
+            .is_some_and(|c| !c.metadata.is_empty());
         let has_filesystem = config
             .filesystem_config
             .as_ref()
             .is_some_and(|c| !c.metadata.is_empty());
 
-        if has_embed || has_filesystem {
+        if let Some(archive_config) = config.archive_config.as_ref().filter(|_| has_archive) {
+            write_asset_archive(archive_config)?;
+        }
+
+        if has_embed || has_include_bytes || has_archive || has_filesystem {
             let code = generate_multi_provider_asset_code(config);
 
             // Ensure parent directory exists
@@ -93,6 +141,32 @@ pub fn finalize_asset_code_outputs() -> anyhow::Result<()> {
     Ok(())
 }
 
+/// Packs every variant of the assets into `ASSET_ARCHIVE_NAME` in the base path.
+///
+/// The packed files are kept, as the same directory can also be served from the
+/// file system or through an asset manifest.
+pub fn write_asset_archive(config: &ProviderConfig) -> anyhow::Result<Utf8PathBuf> {
+    let mut writer = ArchiveWriter::new();
+    for metadata in &config.metadata {
+        for path in metadata.variant_paths() {
+            let file = config.base_path.join(&path);
+            writer.add(path, fs_err::read(&file)?);
+        }
+    }
+
+    let archive_path = config.base_path.join(ASSET_ARCHIVE_NAME);
+    writer.write(std::io::BufWriter::new(fs_err::File::create(
+        &archive_path,
+    )?))?;
+    crate::log_operation!(
+        "ASSET_CODE",
+        "Packed {} asset files into: {}",
+        writer.len(),
+        archive_path
+    );
+    Ok(archive_path)
+}
+
 /// Generates asset code with multiple provider support
 pub fn generate_multi_provider_asset_code(config: &AssetCodeConfig) -> String {
     let mut parts = Vec::new();
@@ -121,19 +195,34 @@ static LOAD_EMBED_ASSET: fn(&str) -> Option<Vec<u8>> = load_embed_asset;"#
         (None, None)
     };
 
+    let include_bytes_provider = config
+        .include_bytes_config
+        .as_ref()
+        .map(generate_include_bytes_provider);
+
+    let archive_provider = config.archive_config.as_ref().map(|_| {
+        format!(
+            r#"/// Provider for loading asset data from the single-file asset archive
+///
+/// Use `get_asset_catalog().with_provider(ArchiveProvider::new(path))` to load
+/// the assets from an archive at another path.
+///
+/// # Panics
+/// Panics if the asset base path has not been configured using set_asset_base_path().
+static ARCHIVE_PROVIDER: ArchiveProvider = ArchiveProvider::global("{ASSET_ARCHIVE_NAME}");"#
+        )
+    });
+
     let filesystem_provider = if config.filesystem_config.is_some() {
         Some(
-            r#"/// Provider function for loading asset data from filesystem
+            r#"/// Provider for loading asset data from filesystem
+///
+/// Use `get_asset_catalog().with_provider(FileSystemProvider::new(path))` to load
+/// the assets from another path.
 ///
 /// # Panics
 /// Panics if the asset base path has not been configured using set_asset_base_path().
-fn load_filesystem_asset(path: &str) -> Option<Vec<u8>> {
-    let base_path = builder_assets::get_asset_base_path_or_panic();
-    let clean_path = path.trim_start_matches('/');
-    let full_path = base_path.join(clean_path);
-    std::fs::read(full_path).ok()
-}
-static LOAD_FILESYSTEM_ASSET: fn(&str) -> Option<Vec<u8>> = load_filesystem_asset;"#
+static FILESYSTEM_PROVIDER: FileSystemProvider = FileSystemProvider::global();"#
                 .to_string(),
         )
     } else {
@@ -149,6 +238,12 @@ static LOAD_FILESYSTEM_ASSET: fn(&str) -> Option<Vec<u8>> = load_filesystem_asse
     if let Some(embed_prov) = embed_provider {
         parts.push(embed_prov);
     }
+    if let Some(include_prov) = include_bytes_provider {
+        parts.push(include_prov);
+    }
+    if let Some(archive_prov) = archive_provider {
+        parts.push(archive_prov);
+    }
     if let Some(fs_prov) = filesystem_provider {
         parts.push(fs_prov);
     }
@@ -160,6 +255,12 @@ static LOAD_FILESYSTEM_ASSET: fn(&str) -> Option<Vec<u8>> = load_filesystem_asse
     if let Some(embed_config) = &config.embed_config {
         all_metadata.extend(embed_config.metadata.iter());
     }
+    if let Some(include_config) = &config.include_bytes_config {
+        all_metadata.extend(include_config.metadata.iter());
+    }
+    if let Some(archive_config) = &config.archive_config {
+        all_metadata.extend(archive_config.metadata.iter());
+    }
     if let Some(fs_config) = &config.filesystem_config {
         all_metadata.extend(fs_config.metadata.iter());
     }
@@ -175,8 +276,24 @@ static LOAD_FILESYSTEM_ASSET: fn(&str) -> Option<Vec<u8>> = load_filesystem_asse
         }
     }
 
+    if let Some(include_config) = &config.include_bytes_config {
+        let included_assets =
+            generate_provider_asset_sets(&include_config.metadata, "&LOAD_INCLUDED_ASSET");
+        if !included_assets.is_empty() {
+            parts.push(format!("// Included assets\n{}", included_assets));
+        }
+    }
+
+    if let Some(archive_config) = &config.archive_config {
+        let archived_assets =
+            generate_provider_asset_sets(&archive_config.metadata, "&ARCHIVE_PROVIDER");
+        if !archived_assets.is_empty() {
+            parts.push(format!("// Archived assets\n{}", archived_assets));
+        }
+    }
+
     if let Some(fs_config) = &config.filesystem_config {
-        let fs_assets = generate_provider_asset_sets(&fs_config.metadata, "&LOAD_FILESYSTEM_ASSET");
+        let fs_assets = generate_provider_asset_sets(&fs_config.metadata, "&FILESYSTEM_PROVIDER");
         if !fs_assets.is_empty() {
             parts.push(format!("// Filesystem assets\n{}", fs_assets));
         }
@@ -192,6 +309,38 @@ static LOAD_FILESYSTEM_ASSET: fn(&str) -> Option<Vec<u8>> = load_filesystem_asse
     parts.join("\n\n")
 }
 
+/// Generates a provider function with an `include_bytes!` arm for every asset variant
+fn generate_include_bytes_provider(config: &ProviderConfig) -> String {
+    let mut paths: Vec<String> = config
+        .metadata
+        .iter()
+        .flat_map(|metadata| metadata.variant_paths())
+        .collect();
+    paths.sort();
+    paths.dedup();
+
+    let arms = paths
+        .iter()
+        .map(|path| {
+            format!(
+                "        {:?} => Some(&include_bytes!({:?})[..]),\n",
+                path,
+                config.base_path.join(path)
+            )
+        })
+        .collect::<String>();
+
+    format!(
+        r#"/// Provider function for borrowing asset data compiled into the binary
+fn load_included_asset(path: &str) -> Option<&'static [u8]> {{
+    match path {{
+{arms}        _ => None,
+    }}
+}}
+static LOAD_INCLUDED_ASSET: fn(&str) -> Option<&'static [u8]> = load_included_asset;"#
+    )
+}
+
 /// Generates the AssetCatalog
 fn generate_asset_catalog(metadata: &[AssetMetadata]) -> String {
     let mut deduplicated: BTreeMap<String, &AssetMetadata> = BTreeMap::new();
@@ -277,6 +426,28 @@ fn generate_single_asset_set_with_provider(metadata: &AssetMetadata, provider_re
         "None".to_string()
     };
 
+    let formats = if let Some(formats) = &metadata.available_formats {
+        let format_list = formats
+            .iter()
+            .map(|format| format!("ImageFormat::{:?}", format))
+            .collect::<Vec<_>>()
+            .join(", ");
+        format!("Some(&[{}])", format_list)
+    } else {
+        "None".to_string()
+    };
+
+    let themes = if let Some(themes) = &metadata.available_themes {
+        let theme_list = themes
+            .iter()
+            .map(|theme| format!(r#"std::borrow::Cow::Borrowed("{}")"#, theme))
+            .collect::<Vec<_>>()
+            .join(", ");
+        format!("Some(&[{}])", theme_list)
+    } else {
+        "None".to_string()
+    };
+
     let folder = metadata
         .folder
         .as_ref()
@@ -300,6 +471,8 @@ fn generate_single_asset_set_with_provider(metadata: &AssetMetadata, provider_re
     }},
     available_encodings: &[{encodings}],
     available_languages: {languages},
+    available_formats: {formats},
+    available_themes: {themes},
     mime: "{mime}",
     provider: {provider_ref},
 }};"#,
@@ -311,6 +484,8 @@ fn generate_single_asset_set_with_provider(metadata: &AssetMetadata, provider_re
         ext = metadata.ext,
         encodings = encodings,
         languages = languages,
+        formats = formats,
+        themes = themes,
         mime = metadata.mime,
         provider_ref = provider_ref,
     )
@@ -332,7 +507,7 @@ pub fn check_global_naming_conflicts(all_metadata: &[&AssetMetadata]) {
         if !used_names.insert(const_name.clone()) {
             panic!(
                 "Asset constant name conflict across providers: '{}' would be generated by multiple assets.\n\
-                 This conflict exists between assets from different providers (embed vs filesystem).\n\
+                 This conflict exists between assets from different providers (embed, include bytes, archive or filesystem).\n\
                  Consider renaming one of the assets to avoid this conflict.\n\
                  Conflicting asset: {} ({})",
                 const_name, metadata.name, metadata.url_path
@@ -1,7 +1,7 @@
 #[cfg(test)]
 mod tests {
     use crate::asset_code_generation::*;
-    use builder_command::{AssetMetadata, Encoding};
+    use builder_command::{AssetMetadata, Encoding, ImageFormat};
     use camino_fs::Utf8PathBuf;
     use icu_locid::langid;
     use insta::assert_snapshot;
@@ -16,11 +16,15 @@ mod tests {
             ext: "css".to_string(),
             available_encodings: vec![Encoding::Identity, Encoding::Brotli],
             available_languages: None,
+            available_formats: None,
+            available_themes: None,
             mime: "text/css".to_string(),
         }];
 
         let config = AssetCodeConfig {
             embed_config: None,
+            include_bytes_config: None,
+            archive_config: None,
             filesystem_config: Some(ProviderConfig {
                 metadata,
                 base_path: Utf8PathBuf::from(""),
@@ -40,11 +44,43 @@ mod tests {
             ext: "css".to_string(),
             available_encodings: vec![Encoding::Identity, Encoding::Brotli, Encoding::Gzip],
             available_languages: Some(vec![langid!("en"), langid!("fr"), langid!("de")]),
+            available_formats: None,
+            available_themes: None,
             mime: "text/css".to_string(),
         }];
 
         let config = AssetCodeConfig {
             embed_config: None,
+            include_bytes_config: None,
+            archive_config: None,
+            filesystem_config: Some(ProviderConfig {
+                metadata,
+                base_path: Utf8PathBuf::from(""),
+            }),
+        };
+        let generated_code = generate_multi_provider_asset_code(&config);
+        assert_snapshot!(generated_code);
+    }
+
+    #[test]
+    fn test_generate_image_formats_asset_code() {
+        let metadata = vec![AssetMetadata {
+            url_path: "/img/logo.png".to_string(),
+            folder: Some("img".to_string()),
+            name: "logo".to_string(),
+            hash: Some("abc123=".to_string()),
+            ext: "png".to_string(),
+            available_encodings: vec![Encoding::Identity],
+            available_languages: None,
+            available_formats: Some(vec![ImageFormat::Avif, ImageFormat::WebP]),
+            available_themes: None,
+            mime: "image/png".to_string(),
+        }];
+
+        let config = AssetCodeConfig {
+            embed_config: None,
+            include_bytes_config: None,
+            archive_config: None,
             filesystem_config: Some(ProviderConfig {
                 metadata,
                 base_path: Utf8PathBuf::from(""),
@@ -65,6 +101,8 @@ mod tests {
                 ext: "css".to_string(),
                 available_encodings: vec![Encoding::Identity],
                 available_languages: None,
+                available_formats: None,
+                available_themes: None,
                 mime: "text/css".to_string(),
             },
             AssetMetadata {
@@ -75,6 +113,8 @@ mod tests {
                 ext: "js".to_string(),
                 available_encodings: vec![Encoding::Brotli, Encoding::Gzip],
                 available_languages: None,
+                available_formats: None,
+                available_themes: None,
                 mime: "application/javascript".to_string(),
             },
             AssetMetadata {
@@ -85,6 +125,8 @@ mod tests {
                 ext: "ico".to_string(),
                 available_encodings: vec![Encoding::Identity],
                 available_languages: None,
+                available_formats: None,
+                available_themes: None,
                 mime: "image/x-icon".to_string(),
             },
             AssetMetadata {
@@ -95,12 +137,16 @@ mod tests {
                 ext: "json".to_string(),
                 available_encodings: vec![Encoding::Identity, Encoding::Gzip],
                 available_languages: Some(vec![langid!("en"), langid!("fr"), langid!("es-MX")]),
+                available_formats: None,
+                available_themes: None,
                 mime: "application/json".to_string(),
             },
         ];
 
         let config = AssetCodeConfig {
             embed_config: None,
+            include_bytes_config: None,
+            archive_config: None,
             filesystem_config: Some(ProviderConfig {
                 metadata,
                 base_path: Utf8PathBuf::from(""),
@@ -120,11 +166,15 @@ mod tests {
             ext: "woff2".to_string(),
             available_encodings: vec![Encoding::Identity],
             available_languages: None,
+            available_formats: None,
+            available_themes: None,
             mime: "font/woff2".to_string(),
         }];
 
         let config = AssetCodeConfig {
             embed_config: None,
+            include_bytes_config: None,
+            archive_config: None,
             filesystem_config: Some(ProviderConfig {
                 metadata,
                 base_path: Utf8PathBuf::from(""),
@@ -139,6 +189,8 @@ mod tests {
         let metadata: Vec<AssetMetadata> = vec![];
         let config = AssetCodeConfig {
             embed_config: None,
+            include_bytes_config: None,
+            archive_config: None,
             filesystem_config: Some(ProviderConfig {
                 metadata,
                 base_path: Utf8PathBuf::from(""),
@@ -170,11 +222,15 @@ mod tests {
             ext: "css".to_string(),
             available_encodings: vec![Encoding::Identity, Encoding::Brotli],
             available_languages: None,
+            available_formats: None,
+            available_themes: None,
             mime: "text/css".to_string(),
         }];
 
         let config = AssetCodeConfig {
             embed_config: None,
+            include_bytes_config: None,
+            archive_config: None,
             filesystem_config: Some(ProviderConfig {
                 metadata,
                 base_path: Utf8PathBuf::from("/tmp/test"),
@@ -195,6 +251,8 @@ mod tests {
             ext: "js".to_string(),
             available_encodings: vec![Encoding::Identity, Encoding::Brotli],
             available_languages: None,
+            available_formats: None,
+            available_themes: None,
             mime: "application/javascript".to_string(),
         }];
 
@@ -203,6 +261,8 @@ mod tests {
                 metadata,
                 base_path: Utf8PathBuf::from("/tmp/test"),
             }),
+            include_bytes_config: None,
+            archive_config: None,
             filesystem_config: None,
         };
         let generated_code = generate_multi_provider_asset_code(&config);
@@ -220,6 +280,8 @@ mod tests {
             ext: "json".to_string(),
             available_encodings: vec![Encoding::Identity, Encoding::Gzip],
             available_languages: Some(vec![langid!("en"), langid!("fr"), langid!("de")]),
+            available_formats: None,
+            available_themes: None,
             mime: "application/json".to_string(),
         }];
 
@@ -228,6 +290,8 @@ mod tests {
                 metadata,
                 base_path: Utf8PathBuf::from("/assets"),
             }),
+            include_bytes_config: None,
+            archive_config: None,
             filesystem_config: None,
         };
         let generated_code = generate_multi_provider_asset_code(&config);
@@ -235,6 +299,134 @@ mod tests {
         assert_snapshot!(generated_code);
     }
 
+    #[test]
+    fn test_generate_include_bytes_provider() {
+        let metadata = vec![
+            AssetMetadata {
+                url_path: "/style.abc123=.css".to_string(),
+                folder: None,
+                name: "style".to_string(),
+                hash: Some("abc123=".to_string()),
+                ext: "css".to_string(),
+                available_encodings: vec![Encoding::Identity, Encoding::Brotli],
+                available_languages: None,
+                available_formats: None,
+                available_themes: None,
+                mime: "text/css".to_string(),
+            },
+            AssetMetadata {
+                url_path: "/lang/messages.json".to_string(),
+                folder: Some("lang".to_string()),
+                name: "messages".to_string(),
+                hash: None,
+                ext: "json".to_string(),
+                available_encodings: vec![Encoding::Identity],
+                available_languages: Some(vec![langid!("en"), langid!("fr")]),
+                available_formats: None,
+                available_themes: None,
+                mime: "application/json".to_string(),
+            },
+        ];
+
+        let config = AssetCodeConfig {
+            embed_config: None,
+            include_bytes_config: Some(ProviderConfig {
+                metadata,
+                base_path: Utf8PathBuf::from("/dist"),
+            }),
+            archive_config: None,
+            filesystem_config: None,
+        };
+        let generated_code = generate_multi_provider_asset_code(&config);
+
+        assert_snapshot!(generated_code);
+    }
+
+    #[test]
+    fn test_generate_archive_provider() {
+        let metadata = vec![AssetMetadata {
+            url_path: "/style.abc123=.css".to_string(),
+            folder: None,
+            name: "style".to_string(),
+            hash: Some("abc123=".to_string()),
+            ext: "css".to_string(),
+            available_encodings: vec![Encoding::Identity, Encoding::Brotli],
+            available_languages: None,
+            available_formats: None,
+            available_themes: None,
+            mime: "text/css".to_string(),
+        }];
+
+        let config = AssetCodeConfig {
+            embed_config: None,
+            include_bytes_config: None,
+            archive_config: Some(ProviderConfig {
+                metadata,
+                base_path: Utf8PathBuf::from("/dist"),
+            }),
+            filesystem_config: None,
+        };
+        let generated_code = generate_multi_provider_asset_code(&config);
+
+        assert_snapshot!(generated_code);
+    }
+
+    #[test]
+    fn test_write_asset_archive() {
+        let dir = tempfile::tempdir().unwrap();
+        let base_path = Utf8PathBuf::from_path_buf(dir.path().to_path_buf()).unwrap();
+        std::fs::create_dir_all(base_path.join("lang/messages.json")).unwrap();
+        std::fs::write(base_path.join("style.css"), "body {}").unwrap();
+        std::fs::write(base_path.join("style.css.br"), "brotli").unwrap();
+        std::fs::write(base_path.join("lang/messages.json/en.json"), "{}").unwrap();
+
+        let config = ProviderConfig {
+            metadata: vec![
+                AssetMetadata {
+                    url_path: "/style.css".to_string(),
+                    folder: None,
+                    name: "style".to_string(),
+                    hash: None,
+                    ext: "css".to_string(),
+                    available_encodings: vec![Encoding::Identity, Encoding::Brotli],
+                    available_languages: None,
+                    available_formats: None,
+                    available_themes: None,
+                    mime: "text/css".to_string(),
+                },
+                AssetMetadata {
+                    url_path: "/lang/messages.json".to_string(),
+                    folder: Some("lang".to_string()),
+                    name: "messages".to_string(),
+                    hash: None,
+                    ext: "json".to_string(),
+                    available_encodings: vec![Encoding::Identity],
+                    available_languages: Some(vec![langid!("en")]),
+                    available_formats: None,
+                    available_themes: None,
+                    mime: "application/json".to_string(),
+                },
+            ],
+            base_path,
+        };
+
+        let archive_path = write_asset_archive(&config).unwrap();
+        let archive = builder_assets::Archive::open(&archive_path).unwrap();
+        assert_eq!(archive.len(), 3);
+        assert_eq!(archive.get("style.css"), Some(&b"body {}"[..]));
+        assert_eq!(archive.get("style.css.br"), Some(&b"brotli"[..]));
+        assert_eq!(archive.get("lang/messages.json/en.json"), Some(&b"{}"[..]));
+
+        // The packed files are kept for other outputs of the same directory
+        assert!(config.base_path.join("style.css").is_file());
+        assert!(
+            config
+                .base_path
+                .join("lang/messages.json/en.json")
+                .is_file()
+        );
+    }
+
     #[test]
     fn test_multi_provider_mixed_same_file() {
         // Create a mock config with both providers
@@ -248,10 +440,14 @@ mod tests {
                     ext: "json".to_string(),
                     available_encodings: vec![Encoding::Identity],
                     available_languages: None,
+                    available_formats: None,
+                    available_themes: None,
                     mime: "application/json".to_string(),
                 }],
                 base_path: Utf8PathBuf::from("/assets"),
             }),
+            include_bytes_config: None,
+            archive_config: None,
             filesystem_config: Some(ProviderConfig {
                 metadata: vec![AssetMetadata {
                     url_path: "/style.css".to_string(),
@@ -261,6 +457,8 @@ mod tests {
                     ext: "css".to_string(),
                     available_encodings: vec![Encoding::Identity, Encoding::Brotli],
                     available_languages: None,
+                    available_formats: None,
+                    available_themes: None,
                     mime: "text/css".to_string(),
                 }],
                 base_path: Utf8PathBuf::from("/dist"),
@@ -273,8 +471,8 @@ mod tests {
         assert!(generated_code.contains("EmbedAssetFiles"));
         assert!(generated_code.contains("load_embed_asset"));
         assert!(generated_code.contains("LOAD_EMBED_ASSET"));
-        assert!(generated_code.contains("load_filesystem_asset"));
-        assert!(generated_code.contains("LOAD_FILESYSTEM_ASSET"));
+        assert!(generated_code.contains("FileSystemProvider::global()"));
+        assert!(generated_code.contains("FILESYSTEM_PROVIDER"));
 
         // Check that both asset constants are present
         assert!(generated_code.contains("pub static CONFIG_JSON"));
@@ -282,7 +480,7 @@ mod tests {
 
         // Check provider assignments
         assert!(generated_code.contains("provider: &LOAD_EMBED_ASSET"));
-        assert!(generated_code.contains("provider: &LOAD_FILESYSTEM_ASSET"));
+        assert!(generated_code.contains("provider: &FILESYSTEM_PROVIDER"));
 
         // Check unified catalog
         assert!(generated_code.contains("pub static ASSETS: [&AssetSet; 2]"));
@@ -300,10 +498,14 @@ mod tests {
                     ext: "woff2".to_string(),
                     available_encodings: vec![Encoding::Identity],
                     available_languages: None,
+                    available_formats: None,
+                    available_themes: None,
                     mime: "font/woff2".to_string(),
                 }],
                 base_path: Utf8PathBuf::from("/fonts"),
             }),
+            include_bytes_config: None,
+            archive_config: None,
             filesystem_config: None,
         };
 
@@ -312,7 +514,7 @@ mod tests {
         // Should only have embed provider
         assert!(generated_code.contains("EmbedAssetFiles"));
         assert!(generated_code.contains("load_embed_asset"));
-        assert!(!generated_code.contains("load_filesystem_asset"));
+        assert!(!generated_code.contains("FileSystemProvider::global()"));
 
         assert!(generated_code.contains("pub static FONT_WOFF2"));
         assert!(generated_code.contains("provider: &LOAD_EMBED_ASSET"));
@@ -322,6 +524,8 @@ mod tests {
     fn test_multi_provider_filesystem_only() {
         let config = AssetCodeConfig {
             embed_config: None,
+            include_bytes_config: None,
+            archive_config: None,
             filesystem_config: Some(ProviderConfig {
                 metadata: vec![AssetMetadata {
                     url_path: "/image.png".to_string(),
@@ -331,6 +535,8 @@ mod tests {
                     ext: "png".to_string(),
                     available_encodings: vec![Encoding::Identity],
                     available_languages: None,
+                    available_formats: None,
+                    available_themes: None,
                     mime: "image/png".to_string(),
                 }],
                 base_path: Utf8PathBuf::from("/static"),
@@ -340,12 +546,12 @@ mod tests {
         let generated_code = generate_multi_provider_asset_code(&config);
 
         // Should only have filesystem provider
-        assert!(generated_code.contains("load_filesystem_asset"));
+        assert!(generated_code.contains("FileSystemProvider::global()"));
         assert!(!generated_code.contains("EmbedAssetFiles"));
         assert!(!generated_code.contains("load_embed_asset"));
 
         assert!(generated_code.contains("pub static IMAGE_PNG"));
-        assert!(generated_code.contains("provider: &LOAD_FILESYSTEM_ASSET"));
+        assert!(generated_code.contains("provider: &FILESYSTEM_PROVIDER"));
     }
 
     #[test]
@@ -359,6 +565,8 @@ mod tests {
             ext: "css".to_string(),
             available_encodings: vec![Encoding::Identity],
             available_languages: None,
+            available_formats: None,
+            available_themes: None,
             mime: "text/css".to_string(),
         };
 
@@ -370,6 +578,8 @@ mod tests {
             ext: "css".to_string(), // Same ext -> conflict
             available_encodings: vec![Encoding::Identity],
             available_languages: None,
+            available_formats: None,
+            available_themes: None,
             mime: "text/css".to_string(),
         };
 
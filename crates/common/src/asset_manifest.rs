@@ -0,0 +1,105 @@
+use anyhow::Result;
+use builder_command::{AssetManifest, AssetMetadata, Output};
+use camino_fs::{Utf8Path, Utf8PathBuf, Utf8PathExt};
+use std::collections::BTreeMap;
+use std::sync::{Mutex, OnceLock};
+
+/// Collects asset metadata for a JSON manifest, keyed by url path as
+/// translations are registered once per output
+#[derive(Debug, Default)]
+pub struct AssetManifestCollector {
+    entries: BTreeMap<String, AssetMetadata>,
+}
+
+// Global collectors for the manifest files, keyed by output path
+static ASSET_MANIFEST_COLLECTORS: OnceLock<Mutex<BTreeMap<Utf8PathBuf, AssetManifestCollector>>> =
+    OnceLock::new();
+
+fn get_asset_manifest_collectors() -> &'static Mutex<BTreeMap<Utf8PathBuf, AssetManifestCollector>>
+{
+    ASSET_MANIFEST_COLLECTORS.get_or_init(|| Mutex::new(BTreeMap::new()))
+}
+
+/// Registers asset metadata for the manifest configured on the output
+pub fn register_asset_manifest_metadata(out: &Output, metadata: &AssetMetadata) {
+    if let Some(path) = &out.asset_manifest_path {
+        let mut collectors = get_asset_manifest_collectors().lock().unwrap();
+        collectors
+            .entry(path.clone())
+            .or_default()
+            .add_metadata(metadata.clone());
+    }
+}
+
+/// Writes all accumulated metadata to their manifest files
+pub fn finalize_asset_manifests() -> Result<()> {
+    let collectors = get_asset_manifest_collectors().lock().unwrap();
+    for (output_path, collector) in collectors.iter() {
+        collector.write_to_file(output_path)?;
+        crate::log_trace!("ASSET_CODE", "Wrote asset manifest to: {}", output_path);
+    }
+    Ok(())
+}
+
+impl AssetManifestCollector {
+    pub fn add_metadata(&mut self, metadata: AssetMetadata) {
+        self.entries.insert(metadata.url_path.clone(), metadata);
+    }
+
+    pub fn manifest(&self) -> AssetManifest {
+        AssetManifest {
+            assets: self.entries.values().map(Into::into).collect(),
+        }
+    }
+
+    pub fn write_to_file(&self, output_path: &Utf8Path) -> Result<()> {
+        let content = self.manifest().to_json();
+
+        if let Some(parent) = output_path.parent() {
+            parent.mkdirs()?;
+        }
+
+        output_path.write(&content)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use builder_command::{Encoding, ImageFormat};
+    use icu_locid::langid;
+    use insta::assert_snapshot;
+
+    #[test]
+    fn test_manifest_json() {
+        let mut collector = AssetManifestCollector::default();
+        collector.add_metadata(AssetMetadata {
+            url_path: "/style.abc123=.css".to_string(),
+            folder: None,
+            name: "style".to_string(),
+            hash: Some("abc123=".to_string()),
+            ext: "css".to_string(),
+            available_encodings: vec![Encoding::Identity, Encoding::Brotli],
+            available_languages: Some(vec![langid!("en"), langid!("fr")]),
+            available_formats: None,
+            available_themes: None,
+            mime: "text/css".to_string(),
+        });
+        collector.add_metadata(AssetMetadata {
+            url_path: "/img/logo.png".to_string(),
+            folder: Some("img".to_string()),
+            name: "logo".to_string(),
+            hash: None,
+            ext: "png".to_string(),
+            available_encodings: vec![Encoding::Identity],
+            available_languages: None,
+            available_formats: Some(vec![ImageFormat::Avif]),
+            available_themes: None,
+            mime: "image/png".to_string(),
+        });
+
+        let json = serde_json::to_string_pretty(&collector.manifest()).unwrap();
+        assert_snapshot!(json);
+    }
+}
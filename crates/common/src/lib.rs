@@ -1,10 +1,12 @@
 pub mod asset_code_generation;
 mod asset_code_generation_test;
+pub mod asset_manifest;
 mod envargs;
 mod ext;
 pub mod hash_output;
 mod hash_output_integration_test;
 pub mod mime;
+pub mod mobile_asset_code;
 pub mod out;
 pub mod site_fs;
 
@@ -135,6 +137,25 @@ macro_rules! warn_cargo {
     };
 }
 
+/// Log an error that should use cargo::error when running under cargo
+#[macro_export]
+macro_rules! error_cargo {
+    ($fmt:expr, $($arg:tt)*) => {
+        if std::env::var("CARGO").is_ok() {
+            println!("cargo::error={}", format!($fmt, $($arg)*));
+        } else {
+            log::error!($fmt, $($arg)*);
+        }
+    };
+    ($msg:expr) => {
+        if std::env::var("CARGO").is_ok() {
+            println!("cargo::error={}", $msg);
+        } else {
+            log::error!("{}", $msg);
+        }
+    };
+}
+
 /// Custom logger that routes log messages through cargo's warning system
 struct CargoLogger {
     level: log::LevelFilter,
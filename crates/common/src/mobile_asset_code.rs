@@ -0,0 +1,330 @@
+use crate::asset_code_generation::generate_const_name;
+use anyhow::Result;
+use builder_command::{AssetMetadata, Output};
+use camino_fs::{Utf8Path, Utf8PathBuf, Utf8PathExt};
+use std::collections::{BTreeMap, HashSet};
+use std::sync::{Mutex, OnceLock};
+
+/// The language of a generated mobile asset constants file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MobileLanguage {
+    Swift,
+    /// Kotlin with the package name for the generated file
+    Kotlin(String),
+}
+
+/// Collects asset metadata and writes it as Swift or Kotlin constants
+#[derive(Debug)]
+pub struct MobileAssetCollector {
+    language: MobileLanguage,
+    /// Keyed by url path, as translations are registered once per output
+    entries: BTreeMap<String, AssetMetadata>,
+}
+
+// Global collectors for the Swift and Kotlin files, keyed by output path
+static MOBILE_ASSET_COLLECTORS: OnceLock<Mutex<BTreeMap<Utf8PathBuf, MobileAssetCollector>>> =
+    OnceLock::new();
+
+fn get_mobile_asset_collectors() -> &'static Mutex<BTreeMap<Utf8PathBuf, MobileAssetCollector>> {
+    MOBILE_ASSET_COLLECTORS.get_or_init(|| Mutex::new(BTreeMap::new()))
+}
+
+/// Registers asset metadata for the Swift and Kotlin files configured on the output
+pub fn register_mobile_asset_metadata(out: &Output, metadata: &AssetMetadata) {
+    let mut collectors = get_mobile_asset_collectors().lock().unwrap();
+
+    if let Some(path) = &out.swift_asset_code_path {
+        collectors
+            .entry(path.clone())
+            .or_insert_with(|| MobileAssetCollector::new(MobileLanguage::Swift))
+            .add_metadata(metadata.clone());
+    }
+    if let Some((path, package)) = &out.kotlin_asset_code_path {
+        collectors
+            .entry(path.clone())
+            .or_insert_with(|| MobileAssetCollector::new(MobileLanguage::Kotlin(package.clone())))
+            .add_metadata(metadata.clone());
+    }
+}
+
+/// Finalizes mobile asset code generation and writes all accumulated metadata to their respective output files
+pub fn finalize_mobile_asset_outputs() -> Result<()> {
+    let collectors = get_mobile_asset_collectors().lock().unwrap();
+    for (output_path, collector) in collectors.iter() {
+        collector.write_to_file(output_path)?;
+        crate::log_trace!("ASSET_CODE", "Wrote mobile asset code to: {}", output_path);
+    }
+    Ok(())
+}
+
+impl MobileAssetCollector {
+    pub fn new(language: MobileLanguage) -> Self {
+        Self {
+            language,
+            entries: BTreeMap::new(),
+        }
+    }
+
+    pub fn add_metadata(&mut self, metadata: AssetMetadata) {
+        self.entries.insert(metadata.url_path.clone(), metadata);
+    }
+
+    pub fn write_to_file(&self, output_path: &Utf8Path) -> Result<()> {
+        let content = self.generate_code();
+
+        // Ensure parent directory exists
+        if let Some(parent) = output_path.parent() {
+            parent.mkdirs()?;
+        }
+
+        output_path.write(&content)?;
+        Ok(())
+    }
+
+    pub fn generate_code(&self) -> String {
+        self.check_naming_conflicts();
+        match &self.language {
+            MobileLanguage::Swift => self.generate_swift_code(),
+            MobileLanguage::Kotlin(package) => self.generate_kotlin_code(package),
+        }
+    }
+
+    fn generate_swift_code(&self) -> String {
+        let mut content = String::new();
+
+        content.push_str("// This file is auto-generated by the builder tool.\n");
+        content.push_str("// Do not edit manually - it will be overwritten.\n\n");
+        content.push_str("public enum Assets {\n");
+
+        let mut decls = Vec::new();
+        for metadata in self.entries.values() {
+            let name = self.identifier(metadata);
+            let mut decl = format!(
+                "    public static let {name}Url = \"{}\"\n",
+                metadata.url_path
+            );
+            if let Some(hash) = &metadata.hash {
+                decl.push_str(&format!("    public static let {name}Hash = \"{hash}\"\n"));
+            }
+            decl.push_str(&format!(
+                "    public static let {name}Mime = \"{}\"\n",
+                metadata.mime
+            ));
+            if let Some(langs) = &metadata.available_languages {
+                decl.push_str(&format!(
+                    "    public static let {name}Languages: [String] = [{}]\n",
+                    quoted_languages(langs)
+                ));
+            }
+            decls.push(decl);
+        }
+        content.push_str(&decls.join("\n"));
+        content.push_str("}\n");
+
+        content
+    }
+
+    fn generate_kotlin_code(&self, package: &str) -> String {
+        let mut content = String::new();
+
+        content.push_str("// This file is auto-generated by the builder tool.\n");
+        content.push_str("// Do not edit manually - it will be overwritten.\n\n");
+        content.push_str(&format!("package {package}\n\n"));
+        content.push_str("object Assets {\n");
+
+        let mut decls = Vec::new();
+        for metadata in self.entries.values() {
+            let name = self.identifier(metadata);
+            let mut decl = format!("    const val {name}_URL = \"{}\"\n", metadata.url_path);
+            if let Some(hash) = &metadata.hash {
+                decl.push_str(&format!("    const val {name}_HASH = \"{hash}\"\n"));
+            }
+            decl.push_str(&format!(
+                "    const val {name}_MIME = \"{}\"\n",
+                metadata.mime
+            ));
+            if let Some(langs) = &metadata.available_languages {
+                decl.push_str(&format!(
+                    "    val {name}_LANGUAGES: List<String> = listOf({})\n",
+                    quoted_languages(langs)
+                ));
+            }
+            decls.push(decl);
+        }
+        content.push_str(&decls.join("\n"));
+        content.push_str("}\n");
+
+        content
+    }
+
+    /// The name the constants of the asset start with, in the style of the language
+    fn identifier(&self, metadata: &AssetMetadata) -> String {
+        let const_name = generate_const_name(&metadata.name, &metadata.ext);
+        let name = match self.language {
+            MobileLanguage::Swift => swift_name(&const_name),
+            MobileLanguage::Kotlin(_) => const_name,
+        };
+        // Identifiers can't start with a digit in Swift and Kotlin
+        if name.starts_with(|c: char| c.is_ascii_digit()) {
+            format!("_{name}")
+        } else {
+            name
+        }
+    }
+
+    fn check_naming_conflicts(&self) {
+        let mut used_names = HashSet::new();
+        for metadata in self.entries.values() {
+            let const_name = self.identifier(metadata);
+            if !used_names.insert(const_name.clone()) {
+                panic!(
+                    "Mobile asset constant name conflict: '{}' would be generated by multiple assets.\n\
+                     Consider renaming one of the assets to avoid this conflict.\n\
+                     Conflicting asset: {} ({})",
+                    const_name, metadata.name, metadata.url_path
+                );
+            }
+        }
+    }
+}
+
+/// Converts a constant name like `STYLE_CSS` to a Swift property name like `styleCss`
+fn swift_name(const_name: &str) -> String {
+    let mut name = String::with_capacity(const_name.len());
+    for (i, part) in const_name.split('_').filter(|p| !p.is_empty()).enumerate() {
+        let lower = part.to_lowercase();
+        if i == 0 {
+            name.push_str(&lower);
+        } else {
+            let mut chars = lower.chars();
+            if let Some(first) = chars.next() {
+                name.push(first.to_ascii_uppercase());
+                name.push_str(chars.as_str());
+            }
+        }
+    }
+    name
+}
+
+fn quoted_languages(langs: &[icu_locid::LanguageIdentifier]) -> String {
+    langs
+        .iter()
+        .map(|lang| format!("\"{lang}\""))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use builder_command::Encoding;
+    use icu_locid::langid;
+    use insta::assert_snapshot;
+
+    fn collector(language: MobileLanguage) -> MobileAssetCollector {
+        let mut collector = MobileAssetCollector::new(language);
+        collector.add_metadata(AssetMetadata {
+            url_path: "/style.abc123=.css".to_string(),
+            folder: None,
+            name: "style".to_string(),
+            hash: Some("abc123=".to_string()),
+            ext: "css".to_string(),
+            available_encodings: vec![Encoding::Identity, Encoding::Brotli],
+            available_languages: None,
+            available_formats: None,
+            available_themes: None,
+            mime: "text/css".to_string(),
+        });
+        collector.add_metadata(AssetMetadata {
+            url_path: "/images/welcome.svg".to_string(),
+            folder: Some("images".to_string()),
+            name: "welcome".to_string(),
+            hash: None,
+            ext: "svg".to_string(),
+            available_encodings: vec![Encoding::Identity],
+            available_languages: Some(vec![langid!("en"), langid!("fr")]),
+            available_formats: None,
+            available_themes: None,
+            mime: "image/svg+xml".to_string(),
+        });
+        collector
+    }
+
+    #[test]
+    fn test_swift_name() {
+        assert_eq!(swift_name("STYLE_CSS"), "styleCss");
+        assert_eq!(swift_name("ROBOTO_BOLD_2X_WOFF2"), "robotoBold2xWoff2");
+        assert_eq!(swift_name("APP__JS"), "appJs");
+    }
+
+    fn metadata(name: &str, ext: &str) -> AssetMetadata {
+        AssetMetadata {
+            url_path: format!("/{name}.{ext}"),
+            folder: None,
+            name: name.to_string(),
+            hash: None,
+            ext: ext.to_string(),
+            available_encodings: vec![Encoding::Identity],
+            available_languages: None,
+            available_formats: None,
+            available_themes: None,
+            mime: "image/png".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_identifiers_starting_with_digit() {
+        let mut swift = MobileAssetCollector::new(MobileLanguage::Swift);
+        swift.add_metadata(metadata("2x", "png"));
+        assert!(
+            swift
+                .generate_code()
+                .contains("public static let _2xPngUrl")
+        );
+
+        let mut kotlin = MobileAssetCollector::new(MobileLanguage::Kotlin("app".to_string()));
+        kotlin.add_metadata(metadata("2x", "png"));
+        assert!(kotlin.generate_code().contains("const val _2X_PNG_URL"));
+    }
+
+    #[test]
+    #[should_panic(expected = "Mobile asset constant name conflict")]
+    fn test_swift_naming_conflict() {
+        // Different constant names, but both are `appJs` in Swift
+        let mut collector = MobileAssetCollector::new(MobileLanguage::Swift);
+        collector.add_metadata(metadata("app_", "js"));
+        collector.add_metadata(metadata("app", "js"));
+        collector.generate_code();
+    }
+
+    #[test]
+    fn test_generate_swift_code() {
+        let code = collector(MobileLanguage::Swift).generate_code();
+        assert_snapshot!(code);
+    }
+
+    #[test]
+    fn test_generate_kotlin_code() {
+        let code = collector(MobileLanguage::Kotlin("com.example.app".to_string())).generate_code();
+        assert_snapshot!(code);
+    }
+
+    #[test]
+    #[should_panic(expected = "Mobile asset constant name conflict")]
+    fn test_naming_conflict() {
+        let mut collector = collector(MobileLanguage::Swift);
+        collector.add_metadata(AssetMetadata {
+            url_path: "/themes/style.css".to_string(),
+            folder: Some("themes".to_string()),
+            name: "style".to_string(),
+            hash: None,
+            ext: "css".to_string(),
+            available_encodings: vec![Encoding::Identity],
+            available_languages: None,
+            available_formats: None,
+            available_themes: None,
+            mime: "text/css".to_string(),
+        });
+        collector.generate_code();
+    }
+}
@@ -1,7 +1,10 @@
 #[cfg(test)]
 mod tests {
-    use crate::site_fs::{SiteFile, write_file_to_site, write_translations};
-    use builder_command::{Encoding, Output};
+    use crate::site_fs::{
+        SiteFile, copy_files_to_site, write_file_to_site, write_image_formats_to_site,
+        write_themes_to_site, write_translations,
+    };
+    use builder_command::{Encoding, ImageFormat, Output};
     use camino_fs::{Utf8PathBuf, Utf8PathBufExt, Utf8PathExt};
     use icu_locid::langid;
     use tempfile::TempDir;
@@ -90,6 +93,8 @@ mod tests {
         // Test direct asset code generation from collected metadata using new multi-provider system
         let config = crate::asset_code_generation::AssetCodeConfig {
             embed_config: None,
+            include_bytes_config: None,
+            archive_config: None,
             filesystem_config: Some(crate::asset_code_generation::ProviderConfig {
                 metadata: collected_metadata.to_vec(),
                 base_path: camino_fs::Utf8PathBuf::from(""),
@@ -100,7 +105,7 @@ mod tests {
 
         // Verify it contains all expected elements for multi-provider system
         assert!(generated_content.contains("use builder_assets::*"));
-        assert!(generated_content.contains("fn load_filesystem_asset"));
+        assert!(generated_content.contains("static FILESYSTEM_PROVIDER: FileSystemProvider"));
         assert!(generated_content.contains("pub static STYLE_CSS"));
         assert!(generated_content.contains("pub static APP_JS"));
         assert!(generated_content.contains("pub static MESSAGES_JSON"));
@@ -113,6 +118,101 @@ mod tests {
         assert!(generated_content.contains(r#"langid!("de")"#));
     }
 
+    #[test]
+    fn test_image_format_variants() {
+        let temp_dir = TempDir::new().unwrap();
+        let site_dir = Utf8PathBuf::from_path(temp_dir.path())
+            .unwrap()
+            .join("site");
+        site_dir.mkdirs().unwrap();
+
+        let mut output_configs = [Output::new_compress_and_sum(&site_dir)];
+        let formats = vec![
+            (ImageFormat::Avif, b"avif".to_vec()),
+            (ImageFormat::WebP, b"webp".to_vec()),
+        ];
+        let logo = SiteFile::new("logo", "png").with_dir("img");
+        write_image_formats_to_site(&logo, b"png", &formats, &mut output_configs);
+
+        let metadata = &output_configs[0].asset_metadata[0];
+        assert_eq!(
+            metadata.available_formats,
+            Some(vec![ImageFormat::Avif, ImageFormat::WebP])
+        );
+        for path in metadata.variant_paths() {
+            assert!(site_dir.join(&path).exists(), "missing {path}");
+        }
+        // The original and both formats, each uncompressed, brotli and gzip
+        assert_eq!(metadata.variant_paths().len(), 9);
+    }
+
+    #[test]
+    fn test_copy_image_formats() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = Utf8PathBuf::from_path(temp_dir.path()).unwrap();
+        let src_dir = root.join("src");
+        let site_dir = root.join("site");
+        src_dir.mkdirs().unwrap();
+        site_dir.mkdirs().unwrap();
+        fs_err::write(src_dir.join("logo.png"), b"png").unwrap();
+        fs_err::write(src_dir.join("logo.png.webp"), b"webp").unwrap();
+        fs_err::write(src_dir.join("icon.png"), b"icon").unwrap();
+
+        let mut output_configs = [Output::new(&site_dir)];
+        copy_files_to_site(&src_dir, false, true, |_| true, &mut output_configs);
+
+        let metadata = &output_configs[0].asset_metadata;
+        assert_eq!(metadata.len(), 2);
+        let logo = metadata.iter().find(|m| m.name == "logo").unwrap();
+        assert_eq!(logo.available_formats, Some(vec![ImageFormat::WebP]));
+        assert_eq!(logo.variant_paths(), ["logo.png", "logo.png.webp"]);
+        let icon = metadata.iter().find(|m| m.name == "icon").unwrap();
+        assert_eq!(icon.available_formats, None);
+    }
+
+    #[test]
+    fn test_theme_variants() {
+        let temp_dir = TempDir::new().unwrap();
+        let site_dir = Utf8PathBuf::from_path(temp_dir.path())
+            .unwrap()
+            .join("site");
+        site_dir.mkdirs().unwrap();
+
+        let mut output_configs = [Output::new_compress_and_sum(&site_dir)];
+        let themes = vec![
+            ("light".to_string(), b".a{color:#000}".to_vec()),
+            ("dark".to_string(), b".a{color:#fff}".to_vec()),
+        ];
+        let main = SiteFile::new("main", "css").with_dir("css");
+        write_themes_to_site(&main, &themes, &mut output_configs);
+
+        let metadata = &output_configs[0].asset_metadata[0];
+        assert_eq!(metadata.folder.as_deref(), Some("css"));
+        assert_eq!(
+            metadata.available_themes,
+            Some(vec!["light".to_string(), "dark".to_string()])
+        );
+        assert_eq!(metadata.available_languages, None);
+        for path in metadata.variant_paths() {
+            assert!(site_dir.join(&path).exists(), "missing {path}");
+        }
+        assert_eq!(metadata.variant_paths().len(), 6);
+    }
+
+    #[test]
+    fn test_checksums_match_runtime_integrity_check() {
+        use builder_assets::integrity::checksum;
+
+        let bytes = b"body { color: blue; margin: 0; }";
+        assert_eq!(crate::site_fs::checksum_from(bytes), checksum([&bytes[..]]));
+
+        let translations = [b"Hello".as_slice(), b"Bonjour".as_slice()];
+        assert_eq!(
+            crate::site_fs::checksum_for_all(translations.into_iter()),
+            checksum(translations)
+        );
+    }
+
     // Note: Code generation format details are covered by snapshot tests in out_snapshot_test.rs
     // This test focuses on the end-to-end workflow and metadata collection accuracy
 }
@@ -11,7 +11,7 @@ pub use anyhow::Result;
 pub use asset::Asset;
 pub use asset_path::{AssetPath, SiteFile, TranslatedAssetPath};
 use base64::{Engine, engine::general_purpose::URL_SAFE};
-use builder_command::{AssetMetadata, Encoding as CmdEncoding, Output};
+use builder_command::{AssetMetadata, Encoding as CmdEncoding, ImageFormat, Output};
 use camino_fs::*;
 pub use encoding::AssetEncodings;
 use icu_locid::LanguageIdentifier;
@@ -73,20 +73,33 @@ pub fn parse_site(root: &Utf8Path) -> Result<Vec<Asset>> {
 
 /// Copies all files recursively maintaining the relative
 /// folder structure.
+///
+/// With `image_formats`, `<file>.avif` and `<file>.webp` next to a copied file are
+/// written as its alternative image formats instead of as separate files.
 pub fn copy_files_to_site<F: Fn(&Utf8PathBuf) -> bool>(
     folder: &Utf8Path,
     recursive: bool,
+    image_formats: bool,
     predicate: F,
     output: &mut [Output],
 ) {
     let mut copied_count = 0;
     let mut total_size = 0u64;
 
-    for file in folder.ls().recurse_if(move |_| recursive).filter(predicate) {
+    let files: Vec<_> = folder
+        .ls()
+        .recurse_if(move |_| recursive)
+        .filter(predicate)
+        .collect();
+    for file in &files {
         if !file.is_file() {
             log_trace!("SITE_FS", "Skipping non-file: {}", file);
             continue;
         }
+        if image_formats && image_format_original(file).is_some_and(|orig| files.contains(&orig)) {
+            log_trace!("SITE_FS", "Copying {} with its original format", file);
+            continue;
+        }
         let bytes = file.read_bytes().unwrap();
         total_size += bytes.len() as u64;
         let rel_path = file.relative_to(folder).unwrap();
@@ -98,7 +111,11 @@ pub fn copy_files_to_site<F: Fn(&Utf8PathBuf) -> bool>(
             bytes.len(),
             site_file
         );
-        write_file_to_site(&site_file, &bytes, output);
+        let formats = match image_formats {
+            true => image_format_variants(file),
+            false => Vec::new(),
+        };
+        write_image_formats_to_site(&site_file, &bytes, &formats, output);
         copied_count += 1;
     }
 
@@ -110,7 +127,61 @@ pub fn copy_files_to_site<F: Fn(&Utf8PathBuf) -> bool>(
     }
 }
 
+const IMAGE_FORMATS: [ImageFormat; 2] = [ImageFormat::Avif, ImageFormat::WebP];
+
+/// The original image of a format variant like `logo.png.avif`
+fn image_format_original(file: &Utf8Path) -> Option<Utf8PathBuf> {
+    let ext = file.extension()?;
+    IMAGE_FORMATS
+        .iter()
+        .any(|format| format.file_ending() == ext)
+        .then(|| file.with_extension(""))
+        .filter(|original| original.extension().is_some())
+}
+
+/// Reads the format variants next to an image, e.g. `logo.png.avif` for `logo.png`
+fn image_format_variants(file: &Utf8Path) -> Vec<(ImageFormat, Vec<u8>)> {
+    IMAGE_FORMATS
+        .into_iter()
+        .filter_map(|format| {
+            let variant = Utf8PathBuf::from(format!("{file}.{}", format.file_ending()));
+            variant
+                .is_file()
+                .then(|| (format, variant.read_bytes().unwrap()))
+        })
+        .collect()
+}
+
 pub fn write_file_to_site(site_file: &SiteFile, bytes: &[u8], output: &mut [Output]) {
+    write_image_formats_to_site(site_file, bytes, &[], output);
+}
+
+/// The file name [`write_file_to_site`] gives the file in the output, with the hash
+/// when the output is hashed. Used to reference one written file from another.
+pub fn site_file_name(site_file: &SiteFile, bytes: &[u8], out: &Output) -> String {
+    let asset = AssetPath {
+        subdir: Utf8PathBuf::new(),
+        name_ext: site_file.clone(),
+        checksum: out.checksum.then(|| checksum_from(bytes)),
+    };
+    asset
+        .absolute_path(Utf8Path::new(""))
+        .file_name()
+        .unwrap()
+        .to_string()
+}
+
+/// Writes an image together with alternative formats of it (e.g. AVIF and WebP
+/// produced by an image encoder), which are served to browsers that accept them.
+///
+/// The variants are written as `<name>[.<hash>].<ext>.<format>` and share the
+/// hash, which covers the original and all variants.
+pub fn write_image_formats_to_site(
+    site_file: &SiteFile,
+    bytes: &[u8],
+    formats: &[(ImageFormat, Vec<u8>)],
+    output: &mut [Output],
+) {
     for out in output {
         let mut subdir = Utf8PathBuf::new();
         if let Some(dir) = &out.site_dir {
@@ -120,10 +191,12 @@ pub fn write_file_to_site(site_file: &SiteFile, bytes: &[u8], output: &mut [Outp
             subdir.push(dir);
         }
 
-        let checksum = if out.checksum {
-            Some(checksum_from(bytes))
-        } else {
-            None
+        let checksum = match (out.checksum, formats.is_empty()) {
+            (false, _) => None,
+            (true, true) => Some(checksum_from(bytes)),
+            (true, false) => Some(checksum_for_all(
+                std::iter::once(bytes).chain(formats.iter().map(|(_, b)| b.as_slice())),
+            )),
         };
 
         let asset = AssetPath {
@@ -187,6 +260,12 @@ pub fn write_file_to_site(site_file: &SiteFile, bytes: &[u8], output: &mut [Outp
             encodings
         );
         encodings.write(&path, bytes).unwrap();
+        for (format, bytes) in formats {
+            let ext = path.extension().unwrap_or_default();
+            let format_path = path.with_extension(format!("{ext}.{}", format.file_ending()));
+            log_trace!("SITE_FS", "Writing {format:?} variant: {}", format_path);
+            encodings.write(&format_path, bytes).unwrap();
+        }
 
         // Collect asset metadata for code generation
         let url_path = if asset.subdir.as_str().is_empty() {
@@ -214,9 +293,14 @@ pub fn write_file_to_site(site_file: &SiteFile, bytes: &[u8], output: &mut [Outp
                 .map(encoding_to_cmd_encoding)
                 .collect(),
             available_languages: None,
+            available_formats: (!formats.is_empty())
+                .then(|| formats.iter().map(|(format, _)| *format).collect()),
+            available_themes: None,
             mime: crate::mime::mime_from_ext(&asset.name_ext.ext).to_string(),
         };
         out.asset_metadata.push(metadata.clone());
+        crate::mobile_asset_code::register_mobile_asset_metadata(out, &metadata);
+        crate::asset_manifest::register_asset_manifest_metadata(out, &metadata);
 
         // Register metadata for asset code generation if configured
         if let Some((asset_code_path, data_provider)) = &out.asset_code_generation {
@@ -240,6 +324,46 @@ pub fn write_translations<P: Into<Utf8PathBuf>>(
     let rel_path = rel_path.into();
     debug!("Writing translations for {rel_path}");
 
+    let variants: Vec<(String, &[u8])> = lang_and_bytes
+        .iter()
+        .map(|(lang, bytes)| (lang.to_string(), bytes.as_slice()))
+        .collect();
+    let languages = lang_and_bytes
+        .iter()
+        .map(|(lang, _)| lang.clone())
+        .collect();
+    write_variant_folder(&rel_path, &variants, Some(languages), None, output);
+}
+
+/// Writes the themes of a stylesheet like translations, as `<name>[.<hash>].<ext>/<theme>.<ext>`,
+/// with one AssetSet that lists the themes. The hash covers all themes.
+pub fn write_themes_to_site(
+    site_file: &SiteFile,
+    theme_and_bytes: &[(String, Vec<u8>)],
+    output: &mut [Output],
+) {
+    let rel_path = Utf8PathBuf::from(site_file.to_string());
+    debug!("Writing themes for {rel_path}");
+
+    let variants: Vec<(String, &[u8])> = theme_and_bytes
+        .iter()
+        .map(|(theme, bytes)| (theme.clone(), bytes.as_slice()))
+        .collect();
+    let themes = theme_and_bytes
+        .iter()
+        .map(|(theme, _)| theme.clone())
+        .collect();
+    write_variant_folder(&rel_path, &variants, None, Some(themes), output);
+}
+
+/// Writes the variants of a file into a folder named like the file, for languages or themes
+fn write_variant_folder(
+    rel_path: &Utf8Path,
+    variants: &[(String, &[u8])],
+    available_languages: Option<Vec<LanguageIdentifier>>,
+    available_themes: Option<Vec<String>>,
+    output: &mut [Output],
+) {
     for out in output {
         let mut site_dir = Utf8PathBuf::new();
         // add any dir that is defined in out
@@ -252,21 +376,18 @@ pub fn write_translations<P: Into<Utf8PathBuf>>(
             site_dir.push(dir)
         }
 
-        let site_file = SiteFile::from_file(&rel_path).with_dir(&site_dir);
+        let site_file = SiteFile::from_file(rel_path).with_dir(&site_dir);
 
         out.dir
             .join(site_dir)
             .rm_matching(|p| {
                 p.file_name()
-                    .is_some_and(|f| f.starts_with(&site_file.name))
-                    && p.extension().is_some_and(|e| e == site_file.ext)
+                    .is_some_and(|f| is_previous_output(f, &site_file))
             })
             .unwrap();
 
         let checksum = if out.checksum {
-            Some(checksum_for_all(
-                lang_and_bytes.iter().map(|(_, b)| b.as_slice()),
-            ))
+            Some(checksum_for_all(variants.iter().map(|(_, b)| *b)))
         } else {
             None
         };
@@ -278,15 +399,11 @@ pub fn write_translations<P: Into<Utf8PathBuf>>(
             let mut collectors = get_hash_collectors().lock().unwrap();
             let collector = collectors.entry(hash_output_path.clone()).or_default();
 
-            for (lang, _) in lang_and_bytes {
-                let file_path = if site_file.site_dir.is_some() {
+            for (lang, _) in variants {
+                let file_path = if let Some(site_dir) = &site_file.site_dir {
                     format!(
                         "{}/{}.{}/{}.{}",
-                        site_file.site_dir.as_ref().unwrap(),
-                        site_file.name,
-                        site_file.ext,
-                        lang,
-                        site_file.ext
+                        site_dir, site_file.name, site_file.ext, lang, site_file.ext
                     )
                 } else {
                     format!(
@@ -297,7 +414,7 @@ pub fn write_translations<P: Into<Utf8PathBuf>>(
                 collector.add_entry(file_path, hash);
                 log_trace!(
                     "SITE_FS",
-                    "Added translation hash entry for: {} ({}) -> {}",
+                    "Added variant hash entry for: {} ({}) -> {}",
                     site_file,
                     lang,
                     hash
@@ -310,20 +427,15 @@ pub fn write_translations<P: Into<Utf8PathBuf>>(
             checksum: checksum.clone(),
             lang: "".to_string(),
         };
-        for (lang, bytes) in lang_and_bytes {
-            asset.lang = lang.to_string();
+        for (lang, bytes) in variants {
+            asset.lang = lang.clone();
             let path = asset.absolute_path(&out.dir);
             debug!("Writing to {path}");
             let encodings = AssetEncodings::from_output(out);
             encodings.write(&path, bytes).unwrap()
         }
 
-        // Collect translation metadata (one AssetSet for all languages)
-        let languages: Vec<LanguageIdentifier> = lang_and_bytes
-            .iter()
-            .map(|(lang, _)| lang.clone())
-            .collect();
-
+        // Collect variant metadata (one AssetSet for all languages or themes)
         let url_path = if let Some(site_dir) = &site_file.site_dir {
             if site_dir.is_empty() {
                 format!("/{}.{}", site_file.name, site_file.ext)
@@ -339,7 +451,8 @@ pub fn write_translations<P: Into<Utf8PathBuf>>(
             folder: site_file
                 .site_dir
                 .as_ref()
-                .map(|s| s.trim_end_matches('/').to_string()),
+                .map(|s| s.trim_end_matches('/').to_string())
+                .filter(|s| !s.is_empty()),
             name: site_file.name.clone(),
             hash: checksum.clone(),
             ext: site_file.ext.clone(),
@@ -347,10 +460,14 @@ pub fn write_translations<P: Into<Utf8PathBuf>>(
                 .into_iter()
                 .map(encoding_to_cmd_encoding)
                 .collect(),
-            available_languages: Some(languages),
+            available_languages: available_languages.clone(),
+            available_formats: None,
+            available_themes: available_themes.clone(),
             mime: crate::mime::mime_from_ext(&site_file.ext).to_string(),
         };
         out.asset_metadata.push(metadata.clone());
+        crate::mobile_asset_code::register_mobile_asset_metadata(out, &metadata);
+        crate::asset_manifest::register_asset_manifest_metadata(out, &metadata);
 
         // Register metadata for asset code generation if configured
         if let Some((asset_code_path, data_provider)) = &out.asset_code_generation {
@@ -364,6 +481,28 @@ pub fn write_translations<P: Into<Utf8PathBuf>>(
     }
 }
 
+/// True for `<name>.<ext>` and `<name>.<hash>.<ext>`, the files or folders previously
+/// written for the site file, but not for others sharing its prefix like `<name>-print.<ext>`
+fn is_previous_output(file_name: &str, site_file: &SiteFile) -> bool {
+    let Some(rest) = file_name
+        .strip_prefix(site_file.name.as_str())
+        .and_then(|rest| rest.strip_prefix('.'))
+        .and_then(|rest| rest.strip_suffix(site_file.ext.as_str()))
+    else {
+        return false;
+    };
+    rest.is_empty() || rest.strip_suffix('.').is_some_and(is_checksum)
+}
+
+/// True for the url safe base64 of a u64, as written by [`checksum_from`]
+fn is_checksum(s: &str) -> bool {
+    s.len() == 12
+        && s.ends_with('=')
+        && s[..11]
+            .bytes()
+            .all(|b| b.is_ascii_alphanumeric() || b == b'-' || b == b'_')
+}
+
 fn checksum_for_all<'a>(bytes_it: impl Iterator<Item = &'a [u8]>) -> String {
     let mut checksummer = SeaHasher::new();
     bytes_it.for_each(|bytes| checksummer.write(bytes));
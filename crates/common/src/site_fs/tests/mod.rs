@@ -4,7 +4,9 @@ use icu_locid::langid;
 
 use crate::site_fs::{
     Asset, AssetEncodings, AssetPath, SiteFile, TranslatedAssetPath, checksum_from, parse_site,
+    write_themes_to_site,
 };
+use builder_command::Output;
 
 fn create_tmp_dir(path: &str) -> Utf8PathBuf {
     let path = Utf8PathBuf::from(path);
@@ -303,3 +305,42 @@ fn multiple() {
         }
     );
 }
+
+#[test]
+fn themes_keep_entries_sharing_the_prefix() {
+    let tmp_dir = create_tmp_dir("src/tests/tmp/themes_keep_entries_sharing_the_prefix");
+    let mut output = [Output::new(&tmp_dir)];
+    output[0].checksum = true;
+    let themes = |css: &str| {
+        vec![
+            ("light".to_string(), css.as_bytes().to_vec()),
+            ("dark".to_string(), css.as_bytes().to_vec()),
+        ]
+    };
+
+    // main-print.scss is compiled before main.scss
+    write_themes_to_site(
+        &SiteFile::new("main-print", "css"),
+        &themes("print"),
+        &mut output,
+    );
+    write_themes_to_site(&SiteFile::new("main", "css"), &themes("v1"), &mut output);
+    write_themes_to_site(&SiteFile::new("main", "css"), &themes("v2"), &mut output);
+
+    let mut names: Vec<String> = tmp_dir
+        .ls()
+        .map(|p| p.file_name().unwrap().to_string())
+        .collect();
+    names.sort();
+    assert_eq!(
+        names,
+        [
+            format!("main-print.{}.css", checksum_for_themes("print")),
+            format!("main.{}.css", checksum_for_themes("v2")),
+        ]
+    );
+}
+
+fn checksum_for_themes(css: &str) -> String {
+    crate::site_fs::checksum_for_all([css.as_bytes(), css.as_bytes()].into_iter())
+}
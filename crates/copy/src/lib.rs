@@ -7,9 +7,10 @@ pub fn run(cmd: &mut CopyCmd) {
     log_command!("COPY", "Copying files from: {}", cmd.src_dir);
     log_operation!(
         "COPY",
-        "Recursive: {}, Extensions: {:?}",
+        "Recursive: {}, Extensions: {:?}, Image formats: {}",
         cmd.recursive,
-        cmd.file_extensions
+        cmd.file_extensions,
+        cmd.image_formats
     );
     log_operation!("COPY", "Output destinations: {}", cmd.output.len());
 
@@ -21,6 +22,7 @@ pub fn run(cmd: &mut CopyCmd) {
     copy_files_to_site(
         &cmd.src_dir,
         cmd.recursive,
+        cmd.image_formats,
         |file| {
             file.extension()
                 .is_some_and(|ext| cmd.file_extensions.contains(&ext.to_string()))
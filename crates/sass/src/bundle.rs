@@ -0,0 +1,216 @@
+use crate::{CompiledCss, Diagnostic, TransformOptions};
+use anyhow::{Result, anyhow};
+use camino_fs::Utf8Path;
+use lightningcss::{
+    bundler::{Bundler, FileProvider},
+    dependencies::{Dependency, DependencyOptions},
+    printer::PrinterOptions,
+    stylesheet::{MinifyOptions, ParserOptions},
+};
+use parcel_sourcemap::SourceMap;
+
+/// Bundles a plain CSS entry with the stylesheets it `@import`s, using Lightning CSS
+/// instead of Sass. The `url()`s of the imported stylesheets are rebased to be
+/// relative to the entry, like the urls of the entry itself.
+///
+/// The urls are relative to the directory of the entry, not to the output file, so
+/// the assets they point to must be placed relative to the written CSS file like
+/// they are to the entry.
+pub fn bundle(
+    entry: &Utf8Path,
+    options: TransformOptions,
+    source_map: bool,
+) -> Result<CompiledCss> {
+    let fs = FileProvider::new();
+    let mut map = source_map.then(|| SourceMap::new("/"));
+    let parser_options = ParserOptions {
+        css_modules: options.css_modules,
+        ..Default::default()
+    };
+    let mut bundler = Bundler::new(&fs, map.as_mut(), parser_options);
+    let mut stylesheet = match bundler.bundle(entry.as_std_path()) {
+        Ok(stylesheet) => stylesheet,
+        Err(err) => {
+            let diagnostic = Diagnostic::error(err.kind.to_string());
+            let diagnostic = match err.loc {
+                Some(loc) => {
+                    let source = std::fs::read_to_string(&loc.filename).unwrap_or_default();
+                    let source_line = source.lines().nth(loc.line as usize).unwrap_or_default();
+                    diagnostic
+                        .at(loc.filename, loc.line + 1, loc.column)
+                        .with_source_line(source_line, 1)
+                }
+                None => diagnostic,
+            };
+            return Err(diagnostic.into());
+        }
+    };
+    let purged = match options.purge {
+        Some(purger) => {
+            Some(purger.purge_stylesheet(&mut stylesheet, options.minify, options.targets)?)
+        }
+        None => None,
+    };
+    // Adds the vendor prefixes and lowers the syntax for the targets
+    stylesheet.minify(MinifyOptions {
+        targets: options.targets,
+        ..Default::default()
+    })?;
+
+    let out = stylesheet
+        .to_css(PrinterOptions {
+            minify: options.minify,
+            source_map: map.as_mut(),
+            targets: options.targets,
+            analyze_dependencies: Some(DependencyOptions {
+                remove_imports: false,
+            }),
+            ..Default::default()
+        })
+        .map_err(|err| anyhow!("{err}"))?;
+    drop(stylesheet);
+
+    let mut css = CompiledCss {
+        css: out.code,
+        map,
+        exports: out.exports,
+        purged: None,
+    };
+    // The urls are replaced by placeholders when the dependencies are analyzed
+    let entry_dir = entry.parent().unwrap_or(Utf8Path::new(""));
+    for dependency in out.dependencies.unwrap_or_default() {
+        let (placeholder, url) = match dependency {
+            Dependency::Url(url) => {
+                let file = Utf8Path::new(&url.loc.file_path);
+                let rebased = rebase_url(&url.url, file, entry_dir);
+                (url.placeholder, rebased)
+            }
+            // Imports that are left, like the imports of other sites
+            Dependency::Import(import) => (import.placeholder, import.url),
+        };
+        css.replace(&placeholder, &escape_url(&url))?;
+    }
+    css.purged = purged.map(|(stats, before)| stats.measure(before, css.css.len()));
+    Ok(css)
+}
+
+/// The url relative to the directory of the entry, for a url relative to the
+/// stylesheet it is in
+fn rebase_url(url: &str, file: &Utf8Path, entry_dir: &Utf8Path) -> String {
+    let is_relative = !url.is_empty()
+        && !url.starts_with(['/', '#'])
+        && !url.split('/').next().unwrap_or_default().contains(':');
+    if !is_relative {
+        return url.to_string();
+    }
+    let (path, suffix) = match url.find(['?', '#']) {
+        Some(i) => url.split_at(i),
+        None => (url, ""),
+    };
+    let file_dir = file.parent().unwrap_or(Utf8Path::new(""));
+    let target = normalize(&format!("{file_dir}/{path}"));
+    let base = normalize(entry_dir.as_str());
+
+    let common = target
+        .iter()
+        .zip(&base)
+        .take_while(|(target, base)| target == base)
+        .count();
+    let mut relative = vec![".."; base.len() - common];
+    relative.extend(target[common..].iter().map(String::as_str));
+    format!("{}{suffix}", relative.join("/"))
+}
+
+/// The components of a path with the `.` and `..` components resolved
+fn normalize(path: &str) -> Vec<String> {
+    let mut components: Vec<String> = Vec::new();
+    for component in path.split('/') {
+        match component {
+            "" | "." => {}
+            ".." if components.last().is_some_and(|last| last != "..") => {
+                components.pop();
+            }
+            _ => components.push(component.to_string()),
+        }
+    }
+    components
+}
+
+/// The placeholders are printed in double quotes
+fn escape_url(url: &str) -> String {
+    url.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use camino_fs::Utf8PathExt;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_bundle_imports() {
+        let dir = TempDir::new().unwrap();
+        let root = Utf8Path::from_path(dir.path()).unwrap();
+        let components = root.join("vendor/components");
+        components.mkdirs().unwrap();
+        components
+            .join("button.css")
+            .write(
+                ".button {\n  background: url(\"../icons/arrow.svg#right\");\n  color: red;\n}\n",
+            )
+            .unwrap();
+        root.join("styles").mkdirs().unwrap();
+        let entry = root.join("styles/main.css");
+        entry
+            .write("@import \"../vendor/components/button.css\";\n.logo { background: url(logo.png); }\n")
+            .unwrap();
+
+        let options = TransformOptions {
+            minify: true,
+            ..Default::default()
+        };
+        let mut css = bundle(&entry, options, true).unwrap();
+        assert_eq!(
+            css.css,
+            r#".button{color:red;background:url("../vendor/icons/arrow.svg#right")}.logo{background:url("logo.png")}"#
+        );
+        let map = css.map_json().unwrap().unwrap();
+        assert!(map.contains("button.css"));
+    }
+
+    #[test]
+    fn test_missing_import() {
+        let dir = TempDir::new().unwrap();
+        let root = Utf8Path::from_path(dir.path()).unwrap();
+        let entry = root.join("main.css");
+        entry
+            .write("@import \"missing.css\";\n.a { color: red; }\n")
+            .unwrap();
+
+        let err = bundle(&entry, Default::default(), false).unwrap_err();
+        let diagnostic = err.downcast::<Diagnostic>().unwrap();
+        let location = diagnostic.location.unwrap();
+        assert_eq!((location.file.as_str(), location.line), (entry.as_str(), 1));
+    }
+
+    #[test]
+    fn test_rebase_url() {
+        let file = Utf8Path::new("styles/vendor/button.css");
+        let entry_dir = Utf8Path::new("styles");
+        assert_eq!(rebase_url("icon.svg", file, entry_dir), "vendor/icon.svg");
+        assert_eq!(
+            rebase_url("../../img/a.png?v=2", file, entry_dir),
+            "../img/a.png?v=2"
+        );
+        assert_eq!(rebase_url("/img/a.png", file, entry_dir), "/img/a.png");
+        assert_eq!(
+            rebase_url("data:image/png;base64,AA", file, entry_dir),
+            "data:image/png;base64,AA"
+        );
+        assert_eq!(
+            rebase_url("https://cdn.example.com/a.png", file, entry_dir),
+            "https://cdn.example.com/a.png"
+        );
+        assert_eq!(escape_url("say \"hi\".svg"), r#"say \"hi\".svg"#);
+    }
+}
@@ -0,0 +1,211 @@
+use anyhow::{Result, anyhow, bail};
+use builder_command::CssModules;
+use camino_fs::Utf8Path;
+use common::{log_operation, site_fs::SiteFile, warn_cargo};
+use lightningcss::css_modules::{
+    Config, CssModuleExport, CssModuleExports, CssModuleReference, Pattern,
+};
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
+const HEADER: &str = "// Scoped CSS names generated by builder from the Sass CSS modules\n// This file is auto-generated. Do not edit manually.\n";
+
+/// The Lightning CSS configuration for the CSS modules of a [`SassCmd`](builder_command::SassCmd)
+pub fn css_modules_config(css_modules: &CssModules) -> Result<Config<'_>> {
+    let mut config = Config {
+        dashed_idents: css_modules.dashed_idents,
+        ..Default::default()
+    };
+    if let Some(pattern) = &css_modules.pattern {
+        config.pattern = Pattern::parse(pattern)
+            .map_err(|err| anyhow!("Invalid CSS modules pattern '{pattern}': {err:?}"))?;
+    }
+    Ok(config)
+}
+
+/// Writes the Rust and TypeScript modules with the scoped names of the stylesheets
+pub fn write_css_modules(
+    css_modules: &CssModules,
+    modules: &[(SiteFile, CssModuleExports)],
+) -> Result<()> {
+    if let Some(path) = &css_modules.rust_module {
+        write_module(path, &generate_rust_module(modules)?)?;
+    }
+    if let Some(path) = &css_modules.typescript_module {
+        write_module(path, &generate_typescript_module(modules))?;
+    }
+    Ok(())
+}
+
+fn write_module(path: &Utf8Path, code: &str) -> Result<()> {
+    if let Some(parent) = path.parent()
+        && !parent.as_str().is_empty()
+    {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::write(path, code)?;
+    log_operation!("SASS", "Wrote CSS module names to: {}", path);
+    Ok(())
+}
+
+/// Generates a Rust module for each stylesheet with a constant for each exported
+/// class and keyframes name. Custom properties are in a nested `vars` module.
+pub fn generate_rust_module(modules: &[(SiteFile, CssModuleExports)]) -> Result<String> {
+    let mut code = HEADER.to_string();
+    for (site_file, exports) in modules {
+        let (names, vars): (Vec<_>, Vec<_>) = sorted(exports)
+            .into_iter()
+            .partition(|(local, _)| !local.starts_with("--"));
+
+        writeln!(code, "\n/// Scoped names of `{site_file}`").unwrap();
+        writeln!(code, "pub mod {} {{", module_name(site_file)).unwrap();
+        write_rust_consts(&mut code, &names, "    ")?;
+        if !vars.is_empty() {
+            if !names.is_empty() {
+                code.push('\n');
+            }
+            code.push_str("    pub mod vars {\n");
+            write_rust_consts(&mut code, &vars, "        ")?;
+            code.push_str("    }\n");
+        }
+        code.push_str("}\n");
+    }
+    Ok(code)
+}
+
+fn write_rust_consts(code: &mut String, exports: &[(&str, String)], indent: &str) -> Result<()> {
+    let mut const_names = BTreeMap::new();
+    for (local, scoped) in exports {
+        let name = const_name(local);
+        if let Some(other) = const_names.insert(name.clone(), local) {
+            bail!("CSS names '{other}' and '{local}' both generate the constant {name}");
+        }
+        writeln!(code, "{indent}/// `{local}`").unwrap();
+        writeln!(code, "{indent}pub const {name}: &str = {scoped:?};").unwrap();
+    }
+    Ok(())
+}
+
+/// Generates a TypeScript object for each stylesheet with the scoped names by the
+/// names in the stylesheet
+pub fn generate_typescript_module(modules: &[(SiteFile, CssModuleExports)]) -> String {
+    let mut code = HEADER.to_string();
+    for (site_file, exports) in modules {
+        writeln!(code, "\n/** Scoped names of `{site_file}` */").unwrap();
+        writeln!(code, "export const {} = {{", module_name(site_file)).unwrap();
+        for (local, scoped) in sorted(exports) {
+            writeln!(code, "  {local:?}: {scoped:?},").unwrap();
+        }
+        code.push_str("} as const;\n");
+    }
+    code
+}
+
+/// The exports sorted by name, with the names they compose
+fn sorted(exports: &CssModuleExports) -> Vec<(&str, String)> {
+    let sorted: BTreeMap<_, _> = exports.iter().collect();
+    sorted
+        .into_iter()
+        .map(|(local, export)| (local.as_str(), class_names(local, export)))
+        .collect()
+}
+
+/// The scoped name followed by the names composed with `composes`
+fn class_names(local: &str, export: &CssModuleExport) -> String {
+    let mut names = export.name.clone();
+    for reference in &export.composes {
+        let name = match reference {
+            CssModuleReference::Local { name } | CssModuleReference::Global { name } => name,
+            CssModuleReference::Dependency { name, specifier } => {
+                warn_cargo!(
+                    "'{}' composes '{}' from {}, which is not resolved in the generated names",
+                    local,
+                    name,
+                    specifier
+                );
+                continue;
+            }
+        };
+        names.push(' ');
+        names.push_str(name);
+    }
+    names
+}
+
+/// The module of a stylesheet, from its path in the site like `admin/users.css`
+fn module_name(site_file: &SiteFile) -> String {
+    let path = match &site_file.site_dir {
+        Some(dir) => format!("{dir}_{}", site_file.name),
+        None => site_file.name.clone(),
+    };
+    identifier(&path.to_lowercase())
+}
+
+/// The constant for a name like `btn-primary` or `--brand-color`
+fn const_name(local: &str) -> String {
+    identifier(&local.trim_start_matches('-').to_uppercase())
+}
+
+fn identifier(name: &str) -> String {
+    let ident: String = name
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect();
+    if ident.starts_with(|c: char| c.is_ascii_digit()) {
+        format!("_{ident}")
+    } else {
+        ident
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{CompiledCss, TransformOptions};
+
+    static BUTTON_SCSS: &str = r#"
+        :root { --brand-color: #0af; }
+        .btn { padding: 4px; }
+        .btn-primary { composes: btn; color: var(--brand-color); animation: fade-in 1s; }
+        @keyframes fade-in { from { opacity: 0; } }
+    "#;
+
+    fn compile_module(css_modules: &CssModules) -> Vec<(SiteFile, CssModuleExports)> {
+        let css = grass::from_string(BUTTON_SCSS, &Default::default()).unwrap();
+        let mut css = CompiledCss::new(css);
+        let options = TransformOptions {
+            css_modules: Some(css_modules_config(css_modules).unwrap()),
+            ..Default::default()
+        };
+        css.transform("styles/button.scss", options).unwrap();
+
+        let site_file = SiteFile::new("button", "css").with_dir("components");
+        vec![(site_file, css.exports.unwrap())]
+    }
+
+    #[test]
+    fn test_rust_module() {
+        let css_modules = CssModules::new()
+            .pattern("[local]_[hash]")
+            .dashed_idents(true);
+        let modules = compile_module(&css_modules);
+        insta::assert_snapshot!(generate_rust_module(&modules).unwrap());
+    }
+
+    #[test]
+    fn test_typescript_module() {
+        let modules = compile_module(&CssModules::new().pattern("[local]_[hash]"));
+        insta::assert_snapshot!(generate_typescript_module(&modules));
+    }
+
+    #[test]
+    fn test_const_names() {
+        assert_eq!(const_name("btn-primary"), "BTN_PRIMARY");
+        assert_eq!(const_name("--brand-color"), "BRAND_COLOR");
+        assert_eq!(const_name("2col"), "_2COL");
+        assert_eq!(
+            module_name(&SiteFile::new("user-list", "css").with_dir("admin")),
+            "admin_user_list"
+        );
+    }
+}
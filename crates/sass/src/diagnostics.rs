@@ -0,0 +1,353 @@
+use codemap::SpanLoc;
+use common::{error_cargo, log_operation, warn_cargo};
+use std::fmt::{self, Display};
+use std::sync::Mutex;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    Error,
+    /// Warnings, including the deprecation warnings of dart-sass
+    Warning,
+}
+
+/// A position in a stylesheet, with the line and column starting from 1
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Location {
+    pub file: String,
+    pub line: u32,
+    pub column: u32,
+}
+
+/// An error or warning of a compiler about a place in a stylesheet
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub message: String,
+    pub location: Option<Location>,
+    /// The source line of the location, marked like in the messages of dart-sass
+    pub snippet: Option<String>,
+}
+
+impl Diagnostic {
+    pub fn error<S: Into<String>>(message: S) -> Self {
+        Self {
+            severity: Severity::Error,
+            message: message.into(),
+            location: None,
+            snippet: None,
+        }
+    }
+
+    pub fn warning<S: Into<String>>(message: S) -> Self {
+        Self {
+            severity: Severity::Warning,
+            ..Self::error(message)
+        }
+    }
+
+    pub fn at<S: Into<String>>(mut self, file: S, line: u32, column: u32) -> Self {
+        self.location = Some(Location {
+            file: file.into(),
+            line,
+            column,
+        });
+        self
+    }
+
+    /// Adds the source line of the location with `len` characters marked from the column
+    pub fn with_source_line(mut self, source_line: &str, len: usize) -> Self {
+        if let Some(location) = &self.location {
+            self.snippet = Some(snippet(source_line, location, len));
+        }
+        self
+    }
+
+    /// Renames the file of the location, e.g. from the staged copy to the original
+    pub fn rename_file(&mut self, rename: impl Fn(&str) -> String) {
+        if let Some(location) = &mut self.location {
+            location.file = rename(&location.file);
+        }
+    }
+
+    /// Reports the diagnostic as `cargo::error` or `cargo::warning` lines when running
+    /// under cargo, and logs it otherwise
+    pub fn emit(&self) {
+        // Keeps the lines together when entries are compiled in parallel
+        let _stdout = std::io::stdout().lock();
+        for line in self.to_string().lines() {
+            match self.severity {
+                Severity::Error => error_cargo!(line),
+                Severity::Warning => warn_cargo!(line),
+            }
+        }
+    }
+}
+
+impl Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.message)?;
+        if let Some(Location { file, line, column }) = &self.location {
+            write!(f, "\n  --> {file}:{line}:{column}")?;
+        }
+        if let Some(snippet) = &self.snippet {
+            write!(f, "\n{snippet}")?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for Diagnostic {}
+
+fn snippet(source_line: &str, location: &Location, len: usize) -> String {
+    let number = location.line.to_string();
+    let gutter = " ".repeat(number.len());
+    // Tabs are kept so that the marker lines up with the source
+    let indent: String = source_line
+        .chars()
+        .take(location.column.saturating_sub(1) as usize)
+        .map(|c| if c == '\t' { '\t' } else { ' ' })
+        .collect();
+    let marker = "^".repeat(len.max(1));
+    format!("{gutter} ,\n{number} | {source_line}\n{gutter} | {indent}{marker}\n{gutter} '")
+}
+
+/// Parses the errors and warnings that dart-sass writes to stderr, e.g.
+///
+/// ```text
+/// Deprecation Warning [import]: Sass @import rules are deprecated.
+///
+///   ,
+/// 1 | @import "theme";
+///   |         ^^^^^^^
+///   '
+///     styles/main.scss 1:9  root stylesheet
+/// ```
+pub fn parse_sass_output(output: &str) -> Vec<Diagnostic> {
+    let mut diagnostics: Vec<Diagnostic> = Vec::new();
+    let mut message_ended = false;
+    for line in output.lines() {
+        if let Some(diagnostic) = parse_header(line) {
+            diagnostics.push(diagnostic);
+            message_ended = false;
+            continue;
+        }
+        let Some(diagnostic) = diagnostics.last_mut() else {
+            continue;
+        };
+        if is_snippet_line(line) {
+            message_ended = true;
+            let snippet = diagnostic.snippet.get_or_insert_default();
+            if !snippet.is_empty() {
+                snippet.push('\n');
+            }
+            snippet.push_str(line);
+        } else if let Some(location) = parse_trace_line(line) {
+            message_ended = true;
+            // The first frame of the stack trace is where the message is from
+            diagnostic.location.get_or_insert(location);
+        } else if !message_ended {
+            diagnostic.message.push('\n');
+            diagnostic.message.push_str(line);
+        }
+    }
+    for diagnostic in &mut diagnostics {
+        diagnostic
+            .message
+            .truncate(diagnostic.message.trim_end().len());
+    }
+    diagnostics
+}
+
+fn parse_header(line: &str) -> Option<Diagnostic> {
+    if let Some(message) = line.strip_prefix("Error: ") {
+        Some(Diagnostic::error(message))
+    } else if line.starts_with("Deprecation Warning") || line.starts_with("DEPRECATION WARNING") {
+        Some(Diagnostic::warning(line))
+    } else {
+        line.strip_prefix("WARNING: ")
+            .or_else(|| line.strip_prefix("Warning: "))
+            .map(Diagnostic::warning)
+    }
+}
+
+/// Lines like `3 | color: $primary;` and the lines around them, with the unicode
+/// or the ascii box drawing characters
+fn is_snippet_line(line: &str) -> bool {
+    if !line.starts_with(|c: char| c == ' ' || c.is_ascii_digit()) {
+        return false;
+    }
+    let rest = line.trim_start_matches(|c: char| c == ' ' || c.is_ascii_digit());
+    let mut chars = rest.chars();
+    matches!(chars.next(), Some('|' | ',' | '\'' | '│' | '╷' | '╵'))
+        && matches!(chars.next(), None | Some(' '))
+}
+
+/// A frame of the stack trace like `    styles/main.scss 3:10  root stylesheet`
+fn parse_trace_line(line: &str) -> Option<Location> {
+    if !line.starts_with(' ') {
+        return None;
+    }
+    let frame = line.trim().split("  ").next()?;
+    let (file, position) = frame.rsplit_once(' ')?;
+    let (line, column) = position.split_once(':')?;
+    Some(Location {
+        file: file.to_string(),
+        line: line.parse().ok()?,
+        column: column.parse().ok()?,
+    })
+}
+
+/// The error of the built-in grass compiler
+pub fn from_grass_error(err: grass::Error) -> Diagnostic {
+    match err.kind() {
+        grass::ErrorKind::ParseError { message, loc, .. } => {
+            let len = if loc.begin.line == loc.end.line {
+                loc.end.column.saturating_sub(loc.begin.column)
+            } else {
+                loc.file.source_line(loc.begin.line).len() - loc.begin.column
+            };
+            at_span(Diagnostic::error(message), &loc, len)
+        }
+        grass::ErrorKind::IoError(err) => Diagnostic::error(err.to_string()),
+        grass::ErrorKind::FromUtf8Error(err) => Diagnostic::error(err),
+        kind => Diagnostic::error(format!("{kind:?}")),
+    }
+}
+
+fn at_span(diagnostic: Diagnostic, loc: &SpanLoc, len: usize) -> Diagnostic {
+    diagnostic
+        .at(
+            loc.file.name(),
+            loc.begin.line as u32 + 1,
+            loc.begin.column as u32 + 1,
+        )
+        .with_source_line(loc.file.source_line(loc.begin.line), len)
+}
+
+/// Collects the `@warn` messages of the grass compiler, which would otherwise be
+/// written to stderr
+#[derive(Debug, Default)]
+pub struct DiagnosticLogger {
+    warnings: Mutex<Vec<Diagnostic>>,
+}
+
+impl DiagnosticLogger {
+    pub fn into_diagnostics(self) -> Vec<Diagnostic> {
+        self.warnings.into_inner().unwrap()
+    }
+}
+
+impl grass::Logger for DiagnosticLogger {
+    fn debug(&self, location: SpanLoc, message: &str) {
+        log_operation!(
+            "SASS",
+            "{}:{} DEBUG: {}",
+            location.file.name(),
+            location.begin.line + 1,
+            message
+        );
+    }
+
+    fn warn(&self, location: SpanLoc, message: &str) {
+        // Strings are quoted by grass, but not by dart-sass
+        let message = message
+            .strip_prefix('"')
+            .and_then(|m| m.strip_suffix('"'))
+            .unwrap_or(message);
+        let len = location.end.column.saturating_sub(location.begin.column);
+        let warning = at_span(Diagnostic::warning(message), &location, len);
+        self.warnings.lock().unwrap().push(warning);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    static SASS_OUTPUT: &str = r#"Deprecation Warning [import]: Sass @import rules are deprecated and will be removed in Dart Sass 3.0.0.
+
+More info and automated migrator: https://sass-lang.com/d/import
+
+  ,
+1 | @import "theme";
+  |         ^^^^^^^
+  '
+    styles/main.scss 1:9  root stylesheet
+
+WARNING: The primary color is too light
+    styles/_theme.scss 4:1  @use
+    styles/main.scss 2:1    root stylesheet
+
+Error: Undefined variable.
+  ,
+3 |   color: $primary;
+  |          ^^^^^^^^
+  '
+  styles/main.scss 3:10  root stylesheet
+"#;
+
+    #[test]
+    fn test_parse_sass_output() {
+        let diagnostics = parse_sass_output(SASS_OUTPUT);
+        assert_eq!(diagnostics.len(), 3);
+
+        let deprecation = &diagnostics[0];
+        assert_eq!(deprecation.severity, Severity::Warning);
+        assert!(
+            deprecation
+                .message
+                .starts_with("Deprecation Warning [import]")
+        );
+        assert!(
+            deprecation
+                .message
+                .ends_with("https://sass-lang.com/d/import")
+        );
+        assert_eq!(
+            deprecation.location,
+            Some(Location {
+                file: "styles/main.scss".to_string(),
+                line: 1,
+                column: 9
+            })
+        );
+
+        let warning = &diagnostics[1];
+        assert_eq!(warning.message, "The primary color is too light");
+        assert_eq!(
+            warning.location.as_ref().unwrap().file,
+            "styles/_theme.scss"
+        );
+        assert_eq!(warning.snippet, None);
+
+        let error = &diagnostics[2];
+        assert_eq!(error.severity, Severity::Error);
+        assert_eq!(
+            error.to_string(),
+            "Undefined variable.\n  --> styles/main.scss:3:10\n  ,\n3 |   color: $primary;\n  |          ^^^^^^^^\n  '"
+        );
+    }
+
+    #[test]
+    fn test_grass_diagnostics() {
+        let logger = DiagnosticLogger::default();
+        let options = grass::Options::default().logger(&logger);
+        let err = grass::from_string(
+            "@warn \"Too light\";\n.a {\n\tcolor: $primary;\n}",
+            &options,
+        )
+        .unwrap_err();
+        let error = from_grass_error(*err);
+        assert_eq!(error.message, "Undefined variable.");
+        assert_eq!(error.location.as_ref().unwrap().line, 3);
+        assert_eq!(
+            error.snippet.as_deref(),
+            Some("  ,\n3 | \tcolor: $primary;\n  | \t       ^^^^^^^^\n  '")
+        );
+
+        let warnings = logger.into_diagnostics();
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(warnings[0].message, "Too light");
+        assert_eq!(warnings[0].location.as_ref().unwrap().line, 1);
+    }
+}
@@ -0,0 +1,132 @@
+use anyhow::{Context, Result, bail};
+use builder_command::SassCmd;
+use camino_fs::{Utf8Path, Utf8PathBuf};
+use common::site_fs::SiteFile;
+
+/// An entry stylesheet and the CSS file it is compiled to
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SassEntry {
+    pub scss: Utf8PathBuf,
+    pub site_file: SiteFile,
+}
+
+/// Expands the entries and glob patterns of the command to the entry stylesheets,
+/// in order and without duplicates. Fails when two stylesheets are written to the
+/// same CSS file.
+pub fn entry_files(sass_cmd: &SassCmd) -> Result<Vec<SassEntry>> {
+    let mut entries: Vec<SassEntry> = Vec::new();
+    for pattern in std::iter::once(&sass_cmd.in_scss).chain(&sass_cmd.entries) {
+        for entry in expand(pattern)? {
+            if entries.iter().any(|e| e.scss == entry.scss) {
+                continue;
+            }
+            if let Some(other) = entries.iter().find(|e| e.site_file == entry.site_file) {
+                bail!(
+                    "Sass entries {} and {} are both written to {}",
+                    other.scss,
+                    entry.scss,
+                    entry.site_file
+                );
+            }
+            entries.push(entry);
+        }
+    }
+    Ok(entries)
+}
+
+fn is_glob(path: &str) -> bool {
+    path.contains(['*', '?', '['])
+}
+
+fn expand(pattern: &Utf8Path) -> Result<Vec<SassEntry>> {
+    if !is_glob(pattern.as_str()) {
+        let name = pattern
+            .file_stem()
+            .with_context(|| format!("Invalid sass entry: {pattern}"))?;
+        return Ok(vec![SassEntry {
+            scss: pattern.to_path_buf(),
+            site_file: SiteFile::new(name, "css"),
+        }]);
+    }
+
+    // The directories before the first wildcard are not part of the output path
+    let base: Utf8PathBuf = pattern
+        .components()
+        .take_while(|component| !is_glob(component.as_str()))
+        .collect();
+
+    let mut entries = Vec::new();
+    for path in glob::glob(pattern.as_str())? {
+        let path = Utf8PathBuf::try_from(path?)?;
+        let Some(name) = path.file_stem() else {
+            continue;
+        };
+        if name.starts_with('_') || !path.is_file() {
+            continue;
+        }
+        let mut site_file = SiteFile::new(name, "css");
+        if let Some(dir) = path.strip_prefix(&base).ok().and_then(Utf8Path::parent)
+            && !dir.as_str().is_empty()
+        {
+            site_file = site_file.with_dir(dir.as_str());
+        }
+        entries.push(SassEntry {
+            scss: path,
+            site_file,
+        });
+    }
+    if entries.is_empty() {
+        bail!("No sass entries match {pattern}");
+    }
+    Ok(entries)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use camino_fs::Utf8PathExt;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_glob_entries() {
+        let dir = TempDir::new().unwrap();
+        let root = Utf8Path::from_path(dir.path()).unwrap();
+        for file in [
+            "pages/home.scss",
+            "pages/_mixins.scss",
+            "pages/admin/users.scss",
+            "main.scss",
+        ] {
+            let path = root.join(file);
+            path.parent().unwrap().mkdirs().unwrap();
+            path.write("a { b: c }").unwrap();
+        }
+
+        let cmd = SassCmd::new(root.join("main.scss"))
+            .add_entry(root.join("pages/**/*.scss"))
+            .add_entry(root.join("pages/home.scss"));
+        let entries = entry_files(&cmd).unwrap();
+        let site_files: Vec<_> = entries
+            .iter()
+            .map(|entry| {
+                (
+                    entry.site_file.site_dir.as_deref(),
+                    entry.site_file.name.as_str(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            site_files,
+            vec![(None, "main"), (Some("admin"), "users"), (None, "home")]
+        );
+
+        // pages/home.scss and home.scss are both written to home.css
+        root.join("home.scss").write("a { b: c }").unwrap();
+        let cmd = SassCmd::new(root.join("pages/*.scss")).add_entry(root.join("home.scss"));
+        let err = entry_files(&cmd).unwrap_err().to_string();
+        assert!(err.contains("pages/home.scss") && err.contains(root.join("home.scss").as_str()));
+
+        let cmd = SassCmd::new(root.join("missing/*.scss"));
+        assert!(entry_files(&cmd).is_err());
+    }
+}
@@ -0,0 +1,442 @@
+use anyhow::{Context, Result, bail};
+use camino_fs::{Utf8Path, Utf8PathBuf, Utf8PathExt};
+use std::collections::BTreeMap;
+use std::ops::Range;
+use std::path::Component;
+use tempfile::TempDir;
+
+const PKG_SCHEME: &str = "pkg:";
+
+/// Resolves `@use "pkg:<crate>/<path>"` to `<path>` in the directory of that crate
+#[derive(Debug, Default)]
+pub struct PkgImporter {
+    /// Crate directories by crate name, with `-` replaced by `_`
+    packages: BTreeMap<String, Utf8PathBuf>,
+}
+
+impl PkgImporter {
+    /// Finds the crates of the current workspace and its dependencies with `cargo metadata`
+    pub fn from_cargo_metadata() -> Result<Self> {
+        let metadata = cargo_metadata::MetadataCommand::new()
+            .exec()
+            .context("Failed to run cargo metadata to resolve pkg: urls")?;
+        let mut importer = Self::default();
+        for package in metadata.packages {
+            if let Some(dir) = package.manifest_path.parent() {
+                importer = importer.with_package(package.name.as_str(), dir);
+            }
+        }
+        Ok(importer)
+    }
+
+    pub fn with_package<P: Into<Utf8PathBuf>>(mut self, name: &str, dir: P) -> Self {
+        self.packages.insert(name.replace('-', "_"), dir.into());
+        self
+    }
+
+    /// Returns the path a `pkg:` url points to, without resolving partials and extensions
+    pub fn resolve_url(&self, url: &str) -> Result<Utf8PathBuf> {
+        let rest = url.strip_prefix(PKG_SCHEME).unwrap_or(url);
+        let (name, path) = rest.split_once('/').unwrap_or((rest, ""));
+        match self.packages.get(&name.replace('-', "_")) {
+            Some(dir) if path.is_empty() => Ok(dir.clone()),
+            Some(dir) => Ok(dir.join(path)),
+            None => bail!("Unknown crate '{name}' in sass url '{url}'"),
+        }
+    }
+}
+
+/// The entry stylesheet and load paths to compile, with `pkg:` urls resolved.
+///
+/// Neither dart-sass nor grass know about `pkg:` urls, so when the stylesheets use
+/// them, all stylesheets reachable from the entry are copied to a temporary directory
+/// that mirrors their absolute paths, with the `pkg:` urls rewritten to relative paths.
+/// Both compilers then compile the same copies.
+#[derive(Debug)]
+pub struct SassInput {
+    pub entry: Utf8PathBuf,
+    pub load_paths: Vec<Utf8PathBuf>,
+    /// The directory with the copies, removed when dropped
+    staging: Option<TempDir>,
+}
+
+impl SassInput {
+    /// Prepares the entry for compiling, getting the importer on the first `pkg:` url.
+    /// With `always_stage` the stylesheets are copied even without `pkg:` urls, so
+    /// that generated stylesheets can be added next to them.
+    pub fn prepare<'i>(
+        entry: &Utf8Path,
+        load_paths: &[Utf8PathBuf],
+        importer: impl FnOnce() -> Result<&'i PkgImporter>,
+        always_stage: bool,
+    ) -> Result<Self> {
+        let mut graph = ImportGraph {
+            load_paths,
+            importer: None,
+            make_importer: Some(importer),
+            files: BTreeMap::new(),
+            uses_pkg: false,
+        };
+        let entry = entry
+            .canonicalize_utf8()
+            .with_context(|| format!("Sass entry not found: {entry}"))?;
+        graph.visit(&entry)?;
+
+        if !graph.uses_pkg && !always_stage {
+            return Ok(Self {
+                entry,
+                load_paths: load_paths.to_vec(),
+                staging: None,
+            });
+        }
+
+        let staging = TempDir::new()?;
+        let root = Utf8Path::from_path(staging.path())
+            .context("Temporary directory is not UTF-8")?
+            .to_path_buf();
+        for (path, file) in &graph.files {
+            let mirrored = mirror(&root, path);
+            let mut content = file.content.clone();
+            // Replace from the end so that the earlier ranges stay valid
+            for (range, target) in file.pkg_urls.iter().rev() {
+                let relative = relative_path(path.parent().unwrap(), target);
+                content.replace_range(range.clone(), relative.as_str());
+            }
+            if let Some(parent) = mirrored.parent() {
+                parent.mkdirs()?;
+            }
+            mirrored.write(content)?;
+        }
+
+        Ok(Self {
+            entry: mirror(&root, &entry),
+            load_paths: load_paths
+                .iter()
+                .map(|path| {
+                    path.canonicalize_utf8()
+                        .map(|path| mirror(&root, &path))
+                        .unwrap_or_else(|_| path.clone())
+                })
+                .collect(),
+            staging: Some(staging),
+        })
+    }
+
+    /// True when the stylesheets were copied to resolve `pkg:` urls
+    pub fn is_staged(&self) -> bool {
+        self.staging.is_some()
+    }
+
+    /// The directory with the copies of the stylesheets, when staged
+    pub(crate) fn staging_dir(&self) -> Option<&Utf8Path> {
+        self.staging
+            .as_ref()
+            .and_then(|staging| Utf8Path::from_path(staging.path()))
+    }
+
+    /// The stylesheet a path reported by the compiler was copied from
+    pub fn original_path(&self, path: &Utf8Path) -> Utf8PathBuf {
+        let staged = self
+            .staging
+            .as_ref()
+            .and_then(|staging| path.strip_prefix(staging.path()).ok());
+        match staged {
+            Some(relative) => Utf8Path::new("/").join(relative),
+            None => path.to_path_buf(),
+        }
+    }
+}
+
+#[derive(Debug)]
+struct StagedFile {
+    content: String,
+    /// The ranges of `pkg:` urls in the content and the paths they point to
+    pkg_urls: Vec<(Range<usize>, Utf8PathBuf)>,
+}
+
+struct ImportGraph<'a, 'i, F> {
+    load_paths: &'a [Utf8PathBuf],
+    importer: Option<&'i PkgImporter>,
+    make_importer: Option<F>,
+    files: BTreeMap<Utf8PathBuf, StagedFile>,
+    uses_pkg: bool,
+}
+
+impl<'i, F: FnOnce() -> Result<&'i PkgImporter>> ImportGraph<'_, 'i, F> {
+    fn importer(&mut self) -> Result<&'i PkgImporter> {
+        if let Some(make_importer) = self.make_importer.take() {
+            self.importer = Some(make_importer()?);
+        }
+        Ok(self.importer.unwrap())
+    }
+
+    fn visit(&mut self, path: &Utf8Path) -> Result<()> {
+        if self.files.contains_key(path) {
+            return Ok(());
+        }
+        let content = path.read_string()?;
+        let dir = path.parent().unwrap().to_path_buf();
+        let mut pkg_urls = Vec::new();
+        let mut imports = Vec::new();
+
+        for (url, range) in import_urls(&content) {
+            if url.starts_with(PKG_SCHEME) {
+                self.uses_pkg = true;
+                let target = self.importer()?.resolve_url(url)?;
+                if let Some(file) = resolve_stylesheet(&target) {
+                    imports.push(file);
+                }
+                pkg_urls.push((range, target));
+            } else if !is_external(url) {
+                let file = resolve_stylesheet(&dir.join(url)).or_else(|| {
+                    self.load_paths
+                        .iter()
+                        .find_map(|load_path| resolve_stylesheet(&load_path.join(url)))
+                });
+                imports.extend(file);
+            }
+        }
+
+        self.files
+            .insert(path.to_path_buf(), StagedFile { content, pkg_urls });
+        for import in imports {
+            if let Ok(import) = import.canonicalize_utf8() {
+                self.visit(&import)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Urls handled by the compiler itself, like `sass:math`, or left as CSS imports
+fn is_external(url: &str) -> bool {
+    url.starts_with("sass:")
+        || url.starts_with("http://")
+        || url.starts_with("https://")
+        || url.starts_with("//")
+        || url.ends_with(".css")
+}
+
+/// Finds the file for a url like Sass does: with or without `_` for partials, with
+/// the `.scss`, `.sass` or `.css` extension, or the `_index` file of a directory
+fn resolve_stylesheet(path: &Utf8Path) -> Option<Utf8PathBuf> {
+    let name = path.file_name()?;
+    let partial = path.with_file_name(format!("_{name}"));
+    if matches!(path.extension(), Some("scss" | "sass" | "css")) {
+        return [path.to_path_buf(), partial]
+            .into_iter()
+            .find(|p| p.is_file());
+    }
+    ["scss", "sass", "css"]
+        .iter()
+        .flat_map(|ext| {
+            [
+                partial.with_extension(ext),
+                path.with_extension(format!("{}{ext}", extension_prefix(path))),
+            ]
+        })
+        .chain(["_index.scss", "index.scss", "_index.sass", "index.sass"].map(|f| path.join(f)))
+        .find(|p| p.is_file())
+}
+
+/// Keeps dots in file names like `theme.dark` when adding the extension
+fn extension_prefix(path: &Utf8Path) -> String {
+    path.extension()
+        .map(|ext| format!("{ext}."))
+        .unwrap_or_default()
+}
+
+/// The path of the copy of `path` below `root`. The root of `path` is dropped,
+/// a Windows prefix like `C:` is kept as a folder so that drives don't collide.
+fn mirror(root: &Utf8Path, path: &Utf8Path) -> Utf8PathBuf {
+    let mut mirrored = root.to_path_buf();
+    for component in path.as_std_path().components() {
+        // the path is UTF-8, so are its components
+        let part = component.as_os_str().to_string_lossy();
+        match component {
+            Component::Prefix(_) => mirrored.push(part.replace([':', '\\', '/', '?'], "")),
+            Component::RootDir | Component::CurDir => {}
+            _ => mirrored.push(part.as_ref()),
+        }
+    }
+    mirrored
+}
+
+/// The relative url from the directory `from` to `to`, both absolute
+fn relative_path(from: &Utf8Path, to: &Utf8Path) -> String {
+    let from: Vec<_> = from.components().collect();
+    let to: Vec<_> = to.components().collect();
+    let common = from.iter().zip(&to).take_while(|(a, b)| a == b).count();
+    let mut parts = vec![".."; from.len() - common];
+    parts.extend(to[common..].iter().map(|c| c.as_str()));
+    if parts.first() != Some(&"..") {
+        parts.insert(0, ".");
+    }
+    parts.join("/")
+}
+
+/// The urls of the `@use`, `@forward` and `@import` rules with the byte range of
+/// each url (without the quotes), skipping comments
+fn import_urls(content: &str) -> Vec<(&str, Range<usize>)> {
+    let bytes = content.as_bytes();
+    let mut urls = Vec::new();
+    let mut pos = 0;
+    while pos < bytes.len() {
+        match bytes[pos] {
+            b'/' if bytes.get(pos + 1) == Some(&b'/') => {
+                pos = content[pos..]
+                    .find('\n')
+                    .map_or(bytes.len(), |end| pos + end);
+            }
+            b'/' if bytes.get(pos + 1) == Some(&b'*') => {
+                pos = content[pos + 2..]
+                    .find("*/")
+                    .map_or(bytes.len(), |end| pos + 2 + end + 2);
+            }
+            b'"' | b'\'' => pos = string_end(bytes, pos).unwrap_or(bytes.len()),
+            b'@' => {
+                let rule = &content[pos + 1..];
+                let keyword_len = ["use", "forward", "import"]
+                    .iter()
+                    .find(|keyword| {
+                        rule.starts_with(*keyword)
+                            && rule[keyword.len()..].starts_with(char::is_whitespace)
+                    })
+                    .map(|keyword| keyword.len());
+                pos += 1;
+                let Some(keyword_len) = keyword_len else {
+                    continue;
+                };
+                pos += keyword_len;
+                // A comma separated list of urls for @import, a single one otherwise
+                loop {
+                    while bytes.get(pos).is_some_and(u8::is_ascii_whitespace) {
+                        pos += 1;
+                    }
+                    if !matches!(bytes.get(pos), Some(b'"' | b'\'')) {
+                        break;
+                    }
+                    // An unterminated url is left for the compiler to report
+                    let Some(end) = string_end(bytes, pos) else {
+                        break;
+                    };
+                    urls.push((&content[pos + 1..end - 1], pos + 1..end - 1));
+                    pos = end;
+                    while bytes.get(pos).is_some_and(u8::is_ascii_whitespace) {
+                        pos += 1;
+                    }
+                    if bytes.get(pos) != Some(&b',') {
+                        break;
+                    }
+                    pos += 1;
+                }
+            }
+            _ => pos += 1,
+        }
+    }
+    urls
+}
+
+/// The position after the closing quote of the string starting at `start`, None
+/// when the string is not closed
+fn string_end(bytes: &[u8], start: usize) -> Option<usize> {
+    let quote = bytes[start];
+    let mut pos = start + 1;
+    while pos < bytes.len() && bytes[pos] != quote {
+        if bytes[pos] == b'\\' {
+            pos += 1;
+        }
+        pos += 1;
+    }
+    (pos < bytes.len()).then_some(pos + 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mirror() {
+        let root = Utf8Path::new("/tmp/staging");
+        assert_eq!(
+            mirror(root, Utf8Path::new("/home/user/pkg/_index.scss")),
+            "/tmp/staging/home/user/pkg/_index.scss"
+        );
+    }
+
+    #[test]
+    fn test_import_urls() {
+        let scss = r#"
+            @use "sass:math";
+            // @use "commented";
+            /* @import "also-commented"; */
+            @use 'pkg:theme/colors' as c;
+            @import "a", "b";
+            .x { content: "@use 'not-a-rule'"; }
+        "#;
+        let urls: Vec<_> = import_urls(scss).into_iter().map(|(url, _)| url).collect();
+        assert_eq!(urls, vec!["sass:math", "pkg:theme/colors", "a", "b"]);
+    }
+
+    #[test]
+    fn test_relative_path() {
+        assert_eq!(
+            relative_path("/a/b/styles".into(), "/a/theme/colors".into()),
+            "../../theme/colors"
+        );
+        assert_eq!(relative_path("/a".into(), "/a/b/c".into()), "./b/c");
+    }
+
+    #[test]
+    fn test_unterminated_url() {
+        assert!(import_urls("@use \"").is_empty());
+        assert!(import_urls("@use 'a\\'").is_empty());
+        let urls: Vec<_> = import_urls("@import \"a\", \"b")
+            .into_iter()
+            .map(|(url, _)| url)
+            .collect();
+        assert_eq!(urls, ["a"]);
+    }
+
+    #[test]
+    fn test_pkg_urls_are_staged() {
+        let dir = TempDir::new().unwrap();
+        let root = Utf8Path::from_path(dir.path()).unwrap();
+        let theme = root.join("theme");
+        theme.join("styles").mkdirs().unwrap();
+        theme
+            .join("styles/_colors.scss")
+            .write("$primary: blue;")
+            .unwrap();
+        let app = root.join("app");
+        app.mkdirs().unwrap();
+        app.join("_button.scss")
+            .write("@use \"pkg:theme/styles/colors\";\n.button { color: colors.$primary; }")
+            .unwrap();
+        app.join("main.scss").write("@use \"button\";").unwrap();
+
+        let importer = PkgImporter::default().with_package("theme", &theme);
+        let input =
+            SassInput::prepare(&app.join("main.scss"), &[], || Ok(&importer), false).unwrap();
+        assert!(input.is_staged());
+        assert_eq!(
+            input.original_path(&input.entry),
+            app.join("main.scss").canonicalize_utf8().unwrap()
+        );
+        let css = grass::from_path(&input.entry, &Default::default()).unwrap();
+        assert!(css.contains("color: blue"));
+
+        // Without pkg: urls the stylesheets are compiled in place
+        app.join("_button.scss")
+            .write(".button { color: red; }")
+            .unwrap();
+        let input = SassInput::prepare(
+            &app.join("main.scss"),
+            &[],
+            || panic!("The importer is only created for pkg: urls"),
+            false,
+        )
+        .unwrap();
+        assert!(!input.is_staged());
+    }
+}
@@ -1,107 +1,549 @@
-use builder_command::SassCmd;
-use common::site_fs::{SiteFile, write_file_to_site};
-use common::{Timer, log_command, log_operation, log_trace};
-use lightningcss::{
-    printer::PrinterOptions,
-    stylesheet::StyleSheet,
-    targets::{Browsers, Targets},
-};
-use std::process::Command;
+mod bundle;
+mod css_modules;
+mod diagnostics;
+mod entries;
+mod importer;
+mod purge;
+mod source_map;
+mod targets;
+mod variables;
+
+use anyhow::{Result, anyhow};
+use builder_command::{SassCmd, SassValue, SourceMapMode, Theme, ThemeLayout};
+use camino_fs::{Utf8Path, Utf8PathBuf};
+use common::site_fs::{SiteFile, site_file_name, write_file_to_site, write_themes_to_site};
+use common::{Timer, log_command, log_operation, log_trace, warn_cargo};
+use lightningcss::targets::Targets;
+use std::num::NonZeroUsize;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::{process::Command, sync::OnceLock, thread};
 use which::which;
 
+pub use bundle::bundle;
+pub use css_modules::{
+    css_modules_config, generate_rust_module, generate_typescript_module, write_css_modules,
+};
+pub use diagnostics::{
+    Diagnostic, DiagnosticLogger, Location, Severity, from_grass_error, parse_sass_output,
+};
+pub use entries::{SassEntry, entry_files};
+pub use importer::{PkgImporter, SassInput};
+pub use purge::{PurgeStats, Purger};
+pub use source_map::{CompiledCss, TransformOptions};
+pub use targets::{DEFAULT_QUERY, ResolvedTargets, resolve_targets};
+pub use variables::VARIABLES_MODULE;
+
 pub fn run(sass_cmd: &mut SassCmd) {
     let _timer = Timer::new("SASS processing");
-    log_command!("SASS", "Processing file: {}", sass_cmd.in_scss);
+    let entries = entry_files(sass_cmd).unwrap_or_else(|err| fail_with(&sass_cmd.in_scss, err));
+    log_command!(
+        "SASS",
+        "Processing {} file(s) from: {}",
+        entries.len(),
+        sass_cmd.in_scss
+    );
     log_operation!(
         "SASS",
-        "Optimize: {}, Replacements: {}",
+        "Optimize: {}, Replacements: {}, Source map: {:?}",
         sass_cmd.optimize,
-        sass_cmd.replacements.len()
+        sass_cmd.replacements.len(),
+        sass_cmd.source_map
     );
 
+    let targets = if sass_cmd.optimize {
+        let dir = Utf8PathBuf::try_from(std::env::current_dir().unwrap()).unwrap();
+        let resolved = resolve_targets(&sass_cmd.browser_targets, &dir).unwrap();
+        log_command!("SASS", "Browser targets from {}", resolved.source);
+        log_trace!("SASS", "Resolved browsers: {:?}", resolved.browsers);
+        Targets::from(resolved.browsers)
+    } else {
+        Targets::default()
+    };
+
+    let purger = match &sass_cmd.purge {
+        Some(_) if sass_cmd.css_modules.is_some() => {
+            warn_cargo!(
+                "The purge of {} is skipped: CSS modules are used through their generated module, not by the class names in the content files",
+                sass_cmd.in_scss
+            );
+            None
+        }
+        Some(purge) => {
+            Some(Purger::scan(purge).unwrap_or_else(|err| fail_with(&sass_cmd.in_scss, err)))
+        }
+        None => None,
+    };
+    let purger = purger.as_ref();
+
+    // Resolved with `cargo metadata` by the first stylesheet with a `pkg:` url and
+    // shared by all entries and themes
+    let importer = OnceLock::new();
+    let importer = || {
+        importer
+            .get_or_init(|| PkgImporter::from_cargo_metadata().map_err(|err| format!("{err:#}")))
+            .as_ref()
+            .map_err(|err| anyhow!("{err}"))
+    };
+
+    // The entries are compiled in parallel and written in order, as writing
+    // updates the hashes and asset metadata of the outputs
+    let cmd: &SassCmd = sass_cmd;
+    let jobs: Vec<(&SassEntry, Option<&Theme>)> = entries
+        .iter()
+        .flat_map(|entry| {
+            entry_themes(cmd, entry)
+                .into_iter()
+                .map(move |theme| (entry, theme))
+        })
+        .collect();
+    // A worker per core takes the next job, as each job can start a sass process
+    let workers = thread::available_parallelism()
+        .map_or(1, NonZeroUsize::get)
+        .min(jobs.len());
+    let (jobs, next_job) = (&jobs, &AtomicUsize::new(0));
+    let compiled: Vec<CompiledCss> = thread::scope(|scope| {
+        let handles: Vec<_> = (0..workers)
+            .map(|_| {
+                scope.spawn(move || {
+                    let mut compiled = Vec::new();
+                    loop {
+                        let index = next_job.fetch_add(1, Ordering::Relaxed);
+                        let Some(&(entry, theme)) = jobs.get(index) else {
+                            break compiled;
+                        };
+                        let css = compile(cmd, &entry.scss, theme, targets, purger, importer);
+                        compiled.push((index, css));
+                    }
+                })
+            })
+            .collect();
+        let mut compiled: Vec<(usize, CompiledCss)> = handles
+            .into_iter()
+            .flat_map(|handle| {
+                handle
+                    .join()
+                    .unwrap_or_else(|panic| std::panic::resume_unwind(panic))
+            })
+            .collect();
+        compiled.sort_by_key(|(index, _)| *index);
+        compiled.into_iter().map(|(_, css)| css).collect()
+    });
+
+    let mut modules = Vec::new();
+    let mut compiled = compiled.into_iter();
+    for entry in &entries {
+        let themes: Vec<Option<String>> = entry_themes(sass_cmd, entry)
+            .into_iter()
+            .map(|theme| theme.map(|theme| theme.name.clone()))
+            .collect();
+        let mut variants: Vec<_> = themes.into_iter().zip(compiled.by_ref()).collect();
+        // One module is generated for all themes, so their scoped names must match
+        let exports = variants[0].1.exports.take();
+        if let Some((Some(theme), _)) = variants[1..].iter().find(|(_, css)| css.exports != exports)
+        {
+            panic!(
+                "The CSS module names of {} differ for theme '{theme}': the themes must have the same class names and the pattern can't use [content-hash]",
+                entry.scss
+            );
+        }
+        if let Some(exports) = exports {
+            modules.push((entry.site_file.clone(), exports));
+        }
+        if variants[0].0.is_none() {
+            let (_, css) = variants.pop().unwrap();
+            log_operation!(
+                "SASS",
+                "Writing {} ({} bytes) from {}",
+                entry.site_file,
+                css.css.len(),
+                entry.scss
+            );
+            write_css(sass_cmd, &entry.site_file, css);
+            continue;
+        }
+        let themes = variants
+            .into_iter()
+            .map(|(theme, css)| (theme.unwrap(), css))
+            .collect();
+        match sass_cmd.theme_layout {
+            ThemeLayout::Files => write_theme_files(sass_cmd, entry, themes),
+            ThemeLayout::Folder => write_theme_folder(sass_cmd, entry, themes),
+        }
+    }
+    if let Some(css_modules) = &sass_cmd.css_modules {
+        write_css_modules(css_modules, &modules).unwrap();
+    }
+}
+
+fn write_css(sass_cmd: &mut SassCmd, site_file: &SiteFile, mut css: CompiledCss) {
+    match sass_cmd.source_map {
+        SourceMapMode::External => {
+            let Some(map) = css.map_json().unwrap() else {
+                write_file_to_site(site_file, css.css.as_bytes(), &mut sass_cmd.output);
+                return;
+            };
+            let mut map_file = SiteFile::new(&site_file.name, "css.map");
+            map_file.site_dir = site_file.site_dir.clone();
+
+            // The url of the map depends on whether the output is hashed
+            for out in &mut sass_cmd.output {
+                let url = site_file_name(&map_file, map.as_bytes(), out);
+                let out = std::slice::from_mut(out);
+                write_file_to_site(&map_file, map.as_bytes(), out);
+                let css = css.with_source_mapping_url(&url);
+                write_file_to_site(site_file, css.as_bytes(), out);
+            }
+        }
+        SourceMapMode::Inline => {
+            let css = css.with_inline_map().unwrap();
+            write_file_to_site(site_file, css.as_bytes(), &mut sass_cmd.output);
+        }
+        SourceMapMode::None => {
+            write_file_to_site(site_file, css.css.as_bytes(), &mut sass_cmd.output);
+        }
+    }
+}
+
+/// The themes an entry is compiled with, None when it is compiled once without a theme
+fn entry_themes<'a>(sass_cmd: &'a SassCmd, entry: &SassEntry) -> Vec<Option<&'a Theme>> {
+    if sass_cmd.themes.is_empty() || entry.scss.extension() == Some("css") {
+        vec![None]
+    } else {
+        sass_cmd.themes.iter().map(Some).collect()
+    }
+}
+
+/// Writes the stylesheet of each theme as `<name>.<theme>.css`
+fn write_theme_files(
+    sass_cmd: &mut SassCmd,
+    entry: &SassEntry,
+    themes: Vec<(String, CompiledCss)>,
+) {
+    for (theme, css) in themes {
+        let mut site_file = SiteFile::new(
+            format!("{}.{theme}", entry.site_file.name),
+            &entry.site_file.ext,
+        );
+        site_file.site_dir = entry.site_file.site_dir.clone();
+        log_operation!(
+            "SASS",
+            "Writing {} ({} bytes) from {} with theme '{}'",
+            site_file,
+            css.css.len(),
+            entry.scss,
+            theme
+        );
+        write_css(sass_cmd, &site_file, css);
+    }
+}
+
+/// Writes the stylesheets of the themes into a folder named like the stylesheet, with
+/// the source maps inlined as the folder can't hold them
+fn write_theme_folder(
+    sass_cmd: &mut SassCmd,
+    entry: &SassEntry,
+    themes: Vec<(String, CompiledCss)>,
+) {
+    if sass_cmd.source_map == SourceMapMode::External {
+        warn_cargo!(
+            "The source maps of {} are inlined, external source maps are not supported for themes written to a folder",
+            entry.site_file
+        );
+    }
+    let theme_and_bytes: Vec<(String, Vec<u8>)> = themes
+        .into_iter()
+        .map(|(theme, mut css)| {
+            let css = match sass_cmd.source_map {
+                SourceMapMode::None => css.css,
+                SourceMapMode::Inline | SourceMapMode::External => css.with_inline_map().unwrap(),
+            };
+            (theme, css.into_bytes())
+        })
+        .collect();
+    log_operation!(
+        "SASS",
+        "Writing {} theme(s) of {} from {}",
+        theme_and_bytes.len(),
+        entry.site_file,
+        entry.scss
+    );
+    write_themes_to_site(&entry.site_file, &theme_and_bytes, &mut sass_cmd.output);
+}
+
+/// The variables of the `builder` module, with `$theme` and the variables of the theme
+/// after the variables of the command, so that they override them
+fn theme_variables(sass_cmd: &SassCmd, theme: Option<&Theme>) -> Vec<(String, SassValue)> {
+    let mut variables = sass_cmd.variables.clone();
+    if let Some(theme) = theme {
+        variables.push(("theme".to_string(), SassValue::from(theme.name.as_str())));
+        variables.extend(theme.variables.iter().cloned());
+    }
+    variables
+}
+
+/// The sources of dart-sass maps are absolute `file:` urls and the files of its
+/// messages are relative to the current directory, also when they are staged. They
+/// are made relative to the current directory and point to the original stylesheets,
+/// so that they don't contain the paths of the build machine
+fn source_name(input: &SassInput, source: &str) -> String {
+    let mut path = Utf8PathBuf::from(source.strip_prefix("file://").unwrap_or(source));
+    if path.is_relative()
+        && let Ok(absolute) = path.canonicalize_utf8()
+    {
+        path = absolute;
+    }
+    let path = input.original_path(&path);
+    std::env::current_dir()
+        .ok()
+        .and_then(|dir| path.strip_prefix(dir).ok().map(Utf8Path::to_string))
+        .unwrap_or_else(|| path.to_string())
+}
+
+/// Compiles one entry stylesheet and applies the replacements and optimization
+fn compile<'i>(
+    sass_cmd: &SassCmd,
+    in_scss: &Utf8Path,
+    theme: Option<&Theme>,
+    targets: Targets,
+    purger: Option<&Purger>,
+    importer: impl FnOnce() -> Result<&'i PkgImporter>,
+) -> CompiledCss {
+    if in_scss.extension() == Some("css") {
+        return bundle_css(sass_cmd, in_scss, targets, purger);
+    }
+    if let Some(theme) = theme {
+        log_operation!("SASS", "Compiling {in_scss} with theme '{}'", theme.name);
+    }
+    let variables = theme_variables(sass_cmd, theme);
+    let inject = !variables.is_empty() || !sass_cmd.module_config.is_empty();
+    let mut input = SassInput::prepare(in_scss, &sass_cmd.load_paths, importer, inject)
+        .unwrap_or_else(|err| fail_with(in_scss, err));
+    if input.is_staged() {
+        log_operation!("SASS", "Staged {in_scss} in {}", input.entry);
+    }
+    if inject {
+        input
+            .inject(&variables, &sass_cmd.module_config)
+            .unwrap_or_else(|err| fail_with(in_scss, err));
+        log_operation!(
+            "SASS",
+            "Injected {} variable(s) and configured {} module(s)",
+            variables.len(),
+            sass_cmd.module_config.len()
+        );
+    }
+
     let mut css = if let Ok(sass) = which("sass") {
-        log_operation!("SASS", "Compiling with external sass binary: {:?}", sass);
+        log_operation!(
+            "SASS",
+            "Compiling {in_scss} with external sass binary: {sass:?}"
+        );
 
+        let source_map_args = match sass_cmd.source_map {
+            SourceMapMode::None => vec!["--no-source-map"],
+            SourceMapMode::Inline | SourceMapMode::External => {
+                vec!["--embed-sources", "--embed-source-map"]
+            }
+        };
         let cmd = Command::new(sass)
-            .args([
-                "--embed-sources",
-                "--embed-source-map",
-                sass_cmd.in_scss.as_str(),
-            ])
+            .args(["--no-color", "--no-unicode"])
+            .args(source_map_args)
+            .args(
+                input
+                    .load_paths
+                    .iter()
+                    .map(|path| format!("--load-path={path}")),
+            )
+            .arg(input.entry.as_str())
             .output()
             .unwrap();
         let out = String::from_utf8(cmd.stdout).unwrap();
         let err = String::from_utf8(cmd.stderr).unwrap();
 
+        let mut diagnostics = parse_sass_output(&err);
+        for diagnostic in &mut diagnostics {
+            diagnostic.rename_file(|file| source_name(&input, file));
+        }
         if !cmd.status.success() {
-            panic!("External sass binary failed: {err}{out}")
+            if !diagnostics.iter().any(|d| d.severity == Severity::Error) {
+                diagnostics.push(Diagnostic::error(format!(
+                    "External sass binary failed for {in_scss}: {err}{out}"
+                )));
+            }
+            fail(in_scss, &diagnostics);
         }
+        report(&diagnostics);
         log_operation!(
             "SASS",
             "External sass compilation successful ({} bytes)",
             out.len()
         );
-        out
+        let mut css = CompiledCss::from_embedded(out).unwrap();
+        css.rename_sources(|source| source_name(&input, source))
+            .unwrap();
+        css
     } else {
         log_operation!(
             "SASS",
-            "Using built-in grass compiler (no external sass found)"
+            "Compiling {in_scss} with built-in grass compiler (no external sass found)"
         );
-        let css = grass::from_path(&sass_cmd.in_scss, &Default::default()).unwrap();
+        let logger = DiagnosticLogger::default();
+        let options = grass::Options::default()
+            .load_paths(&input.load_paths)
+            .logger(&logger);
+        let result = grass::from_path(&input.entry, &options);
+        drop(options);
+
+        let mut diagnostics = logger.into_diagnostics();
+        let css = result.unwrap_or_else(|err| {
+            diagnostics.push(from_grass_error(*err));
+            String::new()
+        });
+        for diagnostic in &mut diagnostics {
+            diagnostic.rename_file(|file| source_name(&input, file));
+        }
+        if diagnostics.iter().any(|d| d.severity == Severity::Error) {
+            fail(in_scss, &diagnostics);
+        }
+        report(&diagnostics);
         log_operation!("SASS", "Grass compilation successful ({} bytes)", css.len());
-        css
-    };
-    if !sass_cmd.replacements.is_empty() {
-        let original_len = css.len();
-        for (from, to) in &sass_cmd.replacements {
-            css = css.replace(from, to);
-            log_trace!("SASS", "Replacement: '{}' -> '{}'", from, to);
+        if sass_cmd.source_map != SourceMapMode::None {
+            warn_cargo!(
+                "No source map for {}: the built-in grass compiler doesn't produce source maps, install dart-sass for them",
+                in_scss
+            );
         }
+        CompiledCss::new(css)
+    };
+    apply_replacements(sass_cmd, in_scss, &mut css);
+    optimize(sass_cmd, in_scss, css, targets, purger)
+}
+
+/// Scopes, purges and minifies the CSS with Lightning CSS, as configured
+fn optimize(
+    sass_cmd: &SassCmd,
+    in_scss: &Utf8Path,
+    mut css: CompiledCss,
+    targets: Targets,
+    purger: Option<&Purger>,
+) -> CompiledCss {
+    let css_modules = sass_cmd
+        .css_modules
+        .as_ref()
+        .map(|css_modules| css_modules_config(css_modules).unwrap());
+    if !sass_cmd.optimize && css_modules.is_none() && purger.is_none() {
+        return css;
+    }
+    if css_modules.is_some() {
+        log_operation!("SASS", "Scoping names of {in_scss} as a CSS module");
+    }
+    if sass_cmd.optimize {
+        log_operation!("SASS", "Optimizing CSS with Lightning CSS");
+    }
+
+    let original_size = css.css.len();
+    let options = TransformOptions {
+        minify: sass_cmd.optimize,
+        targets,
+        css_modules,
+        purge: purger,
+    };
+    if let Err(err) = css.transform(in_scss.as_str(), options) {
+        fail(in_scss, &[lightningcss_diagnostic(err)]);
+    }
+
+    if sass_cmd.optimize {
+        let savings =
+            (original_size.saturating_sub(css.css.len())) as f64 / original_size as f64 * 100.0;
         log_operation!(
             "SASS",
-            "Applied {} replacements ({} -> {} bytes)",
-            sass_cmd.replacements.len(),
-            original_len,
-            css.len()
+            "CSS optimization complete: {} -> {} bytes ({}% reduction{})",
+            original_size,
+            css.css.len(),
+            savings as i32,
+            purge_summary(&css)
         );
+    } else if css.purged.is_some() {
+        log_operation!("SASS", "CSS purge complete{}", purge_summary(&css));
     }
+    css
+}
 
-    let site_file = SiteFile::new(sass_cmd.in_scss.file_stem().unwrap(), "css");
+fn purge_summary(css: &CompiledCss) -> String {
+    match css.purged {
+        Some(purged) => format!(
+            ", {} bytes purged with {} selectors and {} rules",
+            purged.bytes, purged.selectors, purged.rules
+        ),
+        None => String::new(),
+    }
+}
 
-    if sass_cmd.optimize {
-        log_operation!("SASS", "Optimizing CSS with Lightning CSS");
+/// The errors of Lightning CSS are diagnostics, except for the rare printer errors
+fn lightningcss_diagnostic(err: anyhow::Error) -> Diagnostic {
+    err.downcast::<Diagnostic>()
+        .unwrap_or_else(|err| Diagnostic::error(format!("Lightning CSS failed: {err}")))
+}
 
-        let stylesheet = StyleSheet::parse(&css, Default::default()).unwrap();
+fn report(diagnostics: &[Diagnostic]) {
+    for diagnostic in diagnostics {
+        diagnostic.emit();
+    }
+}
 
-        let targets = Targets {
-            browsers: Browsers::from_browserslist([
-                ">0.3%, defaults, supports es6-module, maintained node versions",
-            ])
-            .unwrap(),
-            ..Default::default()
-        };
+/// Reports the diagnostics and fails the build. The panic is resumed rather than
+/// raised, so that the diagnostics aren't followed by a panic message.
+fn fail(in_scss: &Utf8Path, diagnostics: &[Diagnostic]) -> ! {
+    report(diagnostics);
+    std::panic::resume_unwind(Box::new(format!("Sass compilation of {in_scss} failed")))
+}
 
-        let original_size = css.len();
-        let out_css = stylesheet
-            .to_css(PrinterOptions {
-                minify: true,
-                targets,
-                ..Default::default()
-            })
-            .unwrap();
+/// Fails the build with an error of the configuration or the inputs, like a missing
+/// entry or an unknown crate in a `pkg:` url
+fn fail_with(in_scss: &Utf8Path, err: anyhow::Error) -> ! {
+    fail(in_scss, &[Diagnostic::error(format!("{err:#}"))])
+}
 
-        let savings =
-            ((original_size - out_css.code.len()) as f64 / original_size as f64 * 100.0) as i32;
-        log_operation!(
+/// Bundles a plain CSS entry with Lightning CSS, then applies the replacements and
+/// transforms it like the compiled Sass
+fn bundle_css(
+    sass_cmd: &SassCmd,
+    in_scss: &Utf8Path,
+    targets: Targets,
+    purger: Option<&Purger>,
+) -> CompiledCss {
+    log_operation!("SASS", "Bundling {in_scss} with Lightning CSS");
+    let source_map = sass_cmd.source_map != SourceMapMode::None;
+    let mut css = bundle(in_scss, TransformOptions::default(), source_map)
+        .unwrap_or_else(|err| fail(in_scss, &[lightningcss_diagnostic(err)]));
+    log_operation!("SASS", "Bundled {in_scss} ({} bytes)", css.css.len());
+    apply_replacements(sass_cmd, in_scss, &mut css);
+    optimize(sass_cmd, in_scss, css, targets, purger)
+}
+
+fn apply_replacements(sass_cmd: &SassCmd, in_scss: &Utf8Path, css: &mut CompiledCss) {
+    if sass_cmd.replacements.is_empty() {
+        return;
+    }
+    let original_len = css.css.len();
+    for (from, to) in &sass_cmd.replacements {
+        let count = css.replace(from, to).unwrap();
+        if count == 0 && sass_cmd.strict_replacements {
+            panic!("Replacement of '{from}' matched nothing in the CSS of {in_scss}");
+        }
+        log_trace!(
             "SASS",
-            "CSS optimization complete: {} -> {} bytes ({}% reduction)",
-            original_size,
-            out_css.code.len(),
-            savings
+            "Replacement: '{}' -> '{}' ({} matches)",
+            from,
+            to,
+            count
         );
-        write_file_to_site(&site_file, out_css.code.as_bytes(), &mut sass_cmd.output);
-    } else {
-        log_operation!("SASS", "Writing unoptimized CSS ({} bytes)", css.len());
-        write_file_to_site(&site_file, css.as_bytes(), &mut sass_cmd.output);
     }
+    log_operation!(
+        "SASS",
+        "Applied {} replacements ({} -> {} bytes)",
+        sass_cmd.replacements.len(),
+        original_len,
+        css.css.len()
+    );
 }
@@ -0,0 +1,253 @@
+use anyhow::{Context, Result};
+use builder_command::Purge;
+use camino_fs::Utf8PathExt;
+use common::log_operation;
+use glob::Pattern;
+use lightningcss::{
+    printer::PrinterOptions,
+    rules::{CssRule, CssRuleList},
+    selector::{Component, Selector},
+    stylesheet::StyleSheet,
+    targets::Targets,
+};
+use std::collections::HashSet;
+
+/// What was removed from a stylesheet by the purge
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct PurgeStats {
+    pub selectors: usize,
+    pub rules: usize,
+    /// The difference in size of the printed CSS
+    pub bytes: usize,
+}
+
+impl PurgeStats {
+    /// Sets the removed bytes from the length of the CSS printed before purging and
+    /// the length of the final CSS
+    pub fn measure(mut self, before: usize, after: usize) -> Self {
+        self.bytes = before.saturating_sub(after);
+        self
+    }
+}
+
+/// The class names and ids used in the content files of a [`Purge`]
+#[derive(Debug)]
+pub struct Purger {
+    used: HashSet<String>,
+    safelist: Vec<Pattern>,
+}
+
+impl Purger {
+    pub fn new(used: HashSet<String>, safelist: &[String]) -> Result<Self> {
+        let safelist = safelist
+            .iter()
+            .map(|pattern| {
+                Pattern::new(pattern)
+                    .with_context(|| format!("Invalid purge safelist pattern '{pattern}'"))
+            })
+            .collect::<Result<_>>()?;
+        Ok(Self { used, safelist })
+    }
+
+    /// Scans the content files for the names that can be class names or ids
+    pub fn scan(purge: &Purge) -> Result<Self> {
+        let mut used = HashSet::new();
+        let mut files = 0;
+        for pattern in &purge.content {
+            let paths = glob::glob(pattern)
+                .with_context(|| format!("Invalid purge content pattern '{pattern}'"))?;
+            for path in paths {
+                let path = camino_fs::Utf8PathBuf::try_from(path?)?;
+                if path.is_file() {
+                    add_tokens(&mut used, &path.read_string()?);
+                    files += 1;
+                }
+            }
+        }
+        log_operation!(
+            "SASS",
+            "Found {} names to keep in {} content file(s)",
+            used.len(),
+            files
+        );
+        Self::new(used, &purge.safelist)
+    }
+
+    fn is_used(&self, name: &str) -> bool {
+        self.used.contains(name) || self.safelist.iter().any(|pattern| pattern.matches(name))
+    }
+
+    /// Purges the stylesheet. Returns the stats and the length of the CSS printed like
+    /// the stylesheet before purging, which the removed bytes are measured against
+    /// with [`PurgeStats::measure`] once the purged stylesheet is printed.
+    pub fn purge_stylesheet(
+        &self,
+        stylesheet: &mut StyleSheet,
+        minify: bool,
+        targets: Targets,
+    ) -> Result<(PurgeStats, usize)> {
+        let options = PrinterOptions {
+            minify,
+            targets,
+            ..Default::default()
+        };
+        let before = stylesheet.to_css(options)?.code.len();
+        let (selectors, rules) = self.purge(&mut stylesheet.rules);
+        let stats = PurgeStats {
+            selectors,
+            rules,
+            bytes: 0,
+        };
+        Ok((stats, before))
+    }
+
+    /// Removes the selectors with unused class names or ids, and the rules that have
+    /// no selectors left. Returns the number of removed selectors and rules.
+    pub fn purge<R>(&self, rules: &mut CssRuleList<R>) -> (usize, usize) {
+        let (mut selectors, mut removed) = (0, 0);
+        rules.0.retain_mut(|rule| {
+            let keep = match rule {
+                CssRule::Style(style) => {
+                    let before = style.selectors.0.len();
+                    style
+                        .selectors
+                        .0
+                        .retain(|selector| self.is_selector_used(selector));
+                    selectors += before - style.selectors.0.len();
+                    if style.selectors.0.is_empty() {
+                        false
+                    } else {
+                        let (s, r) = self.purge(&mut style.rules);
+                        selectors += s;
+                        removed += r;
+                        true
+                    }
+                }
+                CssRule::Media(media) => {
+                    self.purge_block(&mut media.rules, &mut selectors, &mut removed)
+                }
+                CssRule::Supports(supports) => {
+                    self.purge_block(&mut supports.rules, &mut selectors, &mut removed)
+                }
+                CssRule::LayerBlock(layer) => {
+                    self.purge_block(&mut layer.rules, &mut selectors, &mut removed)
+                }
+                CssRule::Container(container) => {
+                    self.purge_block(&mut container.rules, &mut selectors, &mut removed)
+                }
+                CssRule::StartingStyle(starting_style) => {
+                    self.purge_block(&mut starting_style.rules, &mut selectors, &mut removed)
+                }
+                _ => true,
+            };
+            if !keep {
+                removed += 1;
+            }
+            keep
+        });
+        (selectors, removed)
+    }
+
+    /// Purges the rules of an at-rule, which is kept when some of its rules are left
+    fn purge_block<R>(
+        &self,
+        rules: &mut CssRuleList<R>,
+        selectors: &mut usize,
+        removed: &mut usize,
+    ) -> bool {
+        let was_empty = rules.0.is_empty();
+        let (s, r) = self.purge(rules);
+        *selectors += s;
+        *removed += r;
+        was_empty || !rules.0.is_empty()
+    }
+
+    /// The class names and ids of the selector itself must be used, the ones in
+    /// arguments like `:not(.hidden)` don't matter
+    fn is_selector_used(&self, selector: &Selector) -> bool {
+        selector
+            .iter_raw_match_order()
+            .all(|component| match component {
+                Component::Class(name) | Component::ID(name) => self.is_used(&name.0),
+                _ => true,
+            })
+    }
+}
+
+/// Adds the words of the content, like `btn-primary` in `class="btn btn-primary"`
+/// or `classList.add('btn-primary')`. Words with `:`, `/` and `.` are also added
+/// whole, for names like `md:flex` and `w-1/2`.
+fn add_tokens(used: &mut HashSet<String>, content: &str) {
+    let is_name = |c: char| c.is_alphanumeric() || c == '-' || c == '_';
+    for word in content.split(|c: char| !is_name(c) && !":/.".contains(c)) {
+        let word = word.trim_matches(|c: char| !is_name(c));
+        if word.is_empty() {
+            continue;
+        }
+        if !word.chars().all(is_name) {
+            used.extend(word.split(|c: char| !is_name(c)).map(str::to_string));
+        }
+        used.insert(word.to_string());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lightningcss::stylesheet::ParserOptions;
+
+    fn purged(css: &str, content: &str, safelist: &[&str]) -> (String, (usize, usize)) {
+        let mut used = HashSet::new();
+        add_tokens(&mut used, content);
+        let safelist: Vec<_> = safelist.iter().map(|s| s.to_string()).collect();
+        let purger = Purger::new(used, &safelist).unwrap();
+
+        let mut stylesheet = StyleSheet::parse(css, ParserOptions::default()).unwrap();
+        let removed = purger.purge(&mut stylesheet.rules);
+        let options = PrinterOptions {
+            minify: true,
+            ..Default::default()
+        };
+        (stylesheet.to_css(options).unwrap().code, removed)
+    }
+
+    #[test]
+    fn test_purge_rules() {
+        let css = r#"
+            body { margin: 0; }
+            .btn, .btn-large { padding: 4px; }
+            #app .card:not(.hidden) { color: red; }
+            .md\:flex { display: flex; }
+            @media (min-width: 600px) { .modal { display: block; } }
+            .is-active { font-weight: bold; }
+        "#;
+        let content = r#"view! { <div id="app" class="card md:flex"><button class="btn">"#;
+        let (css, removed) = purged(css, content, &["is-*"]);
+        assert_eq!(
+            css,
+            r"body{margin:0}.btn{padding:4px}#app .card:not(.hidden){color:red}.md\:flex{display:flex}.is-active{font-weight:700}"
+        );
+        // .btn-large and .modal, with the media rule that is left empty
+        assert_eq!(removed, (2, 2));
+    }
+
+    #[test]
+    fn test_tokens() {
+        let mut used = HashSet::new();
+        add_tokens(
+            &mut used,
+            "el.classList.add('is-open'); class=\"w-1/2 sm:hidden\"; format!(\"btn-{}\", kind)",
+        );
+        for name in [
+            "is-open",
+            "w-1/2",
+            "w-1",
+            "2",
+            "sm:hidden",
+            "hidden",
+            "btn-",
+        ] {
+            assert!(used.contains(name), "{name}");
+        }
+    }
+}
@@ -0,0 +1,409 @@
+use crate::{Diagnostic, PurgeStats, Purger};
+use anyhow::{Result, anyhow};
+use lightningcss::{
+    css_modules::{self, CssModuleExports},
+    printer::PrinterOptions,
+    stylesheet::{MinifyOptions, ParserOptions, StyleSheet},
+    targets::Targets,
+};
+use parcel_sourcemap::SourceMap;
+
+const SOURCE_MAPPING_URL: &str = "/*# sourceMappingURL=";
+
+/// CSS with the source map back to the stylesheets it was compiled from.
+///
+/// The columns of the map are byte offsets in the lines of the CSS, like the
+/// maps written by Lightning CSS.
+#[derive(Debug)]
+pub struct CompiledCss {
+    pub css: String,
+    pub map: Option<SourceMap>,
+    /// The scoped names by their name in the stylesheet, when compiled as a CSS module
+    pub exports: Option<CssModuleExports>,
+    /// What was removed by the purge, when purged
+    pub purged: Option<PurgeStats>,
+}
+
+/// How the CSS is transformed by Lightning CSS
+#[derive(Debug, Default)]
+pub struct TransformOptions<'a> {
+    pub minify: bool,
+    pub targets: Targets,
+    pub css_modules: Option<css_modules::Config<'a>>,
+    /// Removes the unused rules before the CSS is printed
+    pub purge: Option<&'a Purger>,
+}
+
+impl CompiledCss {
+    pub fn new(css: String) -> Self {
+        Self {
+            css,
+            map: None,
+            exports: None,
+            purged: None,
+        }
+    }
+
+    /// Splits the source map embedded with `--embed-source-map` from the CSS
+    pub fn from_embedded(mut css: String) -> Result<Self> {
+        let Some(start) = css.rfind(SOURCE_MAPPING_URL) else {
+            return Ok(Self::new(css));
+        };
+        let url = css[start + SOURCE_MAPPING_URL.len()..]
+            .trim_end()
+            .trim_end_matches("*/")
+            .trim();
+        let map = SourceMap::from_data_url("/", url)
+            .map_err(|err| anyhow!("Invalid source map from sass: {err}"))?;
+        css.truncate(start);
+        css.truncate(css.trim_end().len());
+        Ok(Self {
+            css,
+            map: Some(map),
+            exports: None,
+            purged: None,
+        })
+    }
+
+    /// Renames the sources of the map, e.g. from the absolute `file:` urls of
+    /// dart-sass to paths relative to the crate
+    pub fn rename_sources(&mut self, rename: impl Fn(&str) -> String) -> Result<()> {
+        if let Some(map) = &self.map {
+            let mut renamed = SourceMap::new("/");
+            copy_sources(map, &mut renamed, rename)?;
+            for mapping in map.get_mappings() {
+                renamed.add_mapping(
+                    mapping.generated_line,
+                    mapping.generated_column,
+                    mapping.original,
+                );
+            }
+            self.map = Some(renamed);
+        }
+        Ok(())
+    }
+
+    /// Replaces all occurrences of `from` like [`str::replace`], moving the mappings
+    /// after each occurrence by the difference in length. Returns the number of
+    /// occurrences.
+    pub fn replace(&mut self, from: &str, to: &str) -> Result<usize> {
+        let Some(map) = &self.map else {
+            let count = self.css.matches(from).count();
+            self.css = self.css.replace(from, to);
+            return Ok(count);
+        };
+
+        // The start and end of each occurrence, and its start in the new CSS
+        let mut edits = Vec::new();
+        let mut css = String::with_capacity(self.css.len());
+        let mut last = 0;
+        for (start, _) in self.css.match_indices(from) {
+            css.push_str(&self.css[last..start]);
+            edits.push((start, start + from.len(), css.len()));
+            css.push_str(to);
+            last = start + from.len();
+        }
+        if edits.is_empty() {
+            return Ok(0);
+        }
+        css.push_str(&self.css[last..]);
+
+        let old_lines = LineIndex::new(&self.css);
+        let new_lines = LineIndex::new(&css);
+        let mut remapped = SourceMap::new("/");
+        copy_sources(map, &mut remapped, str::to_string)?;
+        for mapping in map.get_mappings() {
+            let offset = old_lines.offset(mapping.generated_line, mapping.generated_column);
+            let offset = match edits.partition_point(|(start, _, _)| *start <= offset) {
+                0 => offset,
+                i => {
+                    let (_, end, new_start) = edits[i - 1];
+                    if offset < end {
+                        // Inside the replaced text, mapped to the replacement
+                        new_start
+                    } else {
+                        new_start + to.len() + offset - end
+                    }
+                }
+            };
+            let (line, column) = new_lines.position(offset);
+            remapped.add_mapping(line, column, mapping.original);
+        }
+        self.css = css;
+        self.map = Some(remapped);
+        Ok(edits.len())
+    }
+
+    /// Transforms the CSS with Lightning CSS, composing its source map with the map
+    /// of the input so that the result maps back to the original stylesheets.
+    /// The filename is hashed into the scoped names of CSS modules.
+    pub fn transform(&mut self, filename: &str, options: TransformOptions) -> Result<()> {
+        // Lightning CSS remaps to the source map of its input when it is inlined
+        let input = match &mut self.map {
+            Some(map) => format!(
+                "{}\n{SOURCE_MAPPING_URL}{} */",
+                self.css,
+                map.to_data_url(None)?
+            ),
+            None => self.css.clone(),
+        };
+        let parser_options = ParserOptions {
+            filename: filename.to_string(),
+            css_modules: options.css_modules,
+            ..Default::default()
+        };
+        let mut stylesheet = match StyleSheet::parse(&input, parser_options) {
+            Ok(stylesheet) => stylesheet,
+            Err(err) => {
+                let diagnostic = Diagnostic::error(err.kind.to_string());
+                let diagnostic = match err.loc {
+                    Some(loc) => self.locate(diagnostic, filename, loc.line, loc.column),
+                    None => diagnostic,
+                };
+                return Err(diagnostic.into());
+            }
+        };
+        let purged = match options.purge {
+            Some(purger) => {
+                Some(purger.purge_stylesheet(&mut stylesheet, options.minify, options.targets)?)
+            }
+            None => None,
+        };
+        // Adds the vendor prefixes and lowers the syntax for the targets
+        stylesheet.minify(MinifyOptions {
+            targets: options.targets,
+            ..Default::default()
+        })?;
+
+        let mut map = self.map.is_some().then(|| SourceMap::new("/"));
+        let out = stylesheet
+            .to_css(PrinterOptions {
+                minify: options.minify,
+                source_map: map.as_mut(),
+                targets: options.targets,
+                ..Default::default()
+            })
+            .map_err(|err| anyhow!("{err}"))?;
+        if let Some((stats, before)) = purged {
+            self.purged = Some(stats.measure(before, out.code.len()));
+        }
+        self.css = out.code;
+        self.map = map;
+        self.exports = out.exports;
+        Ok(())
+    }
+
+    /// Locates a diagnostic at a line, starting from 0, and a column, starting from 1,
+    /// of the CSS. The position is mapped to the original stylesheet when there is a
+    /// source map.
+    fn locate(
+        &mut self,
+        diagnostic: Diagnostic,
+        filename: &str,
+        line: u32,
+        column: u32,
+    ) -> Diagnostic {
+        let original = self.map.as_mut().and_then(|map| {
+            let original = map
+                .find_closest_mapping(line, column.saturating_sub(1))?
+                .original?;
+            let source = map.get_source(original.source).ok()?.to_string();
+            let content = map.get_source_content(original.source).ok();
+            let source_line = content
+                .and_then(|content| content.lines().nth(original.original_line as usize))
+                .map(str::to_string);
+            Some((source, original, source_line))
+        });
+        match original {
+            Some((source, original, source_line)) => {
+                let diagnostic = diagnostic.at(
+                    source,
+                    original.original_line + 1,
+                    original.original_column + 1,
+                );
+                match source_line {
+                    Some(source_line) => diagnostic.with_source_line(&source_line, 1),
+                    None => diagnostic,
+                }
+            }
+            None => {
+                let source_line = self.css.lines().nth(line as usize).unwrap_or_default();
+                Diagnostic {
+                    message: format!("{} (in the compiled CSS)", diagnostic.message),
+                    ..diagnostic
+                }
+                .at(filename, line + 1, column)
+                .with_source_line(source_line, 1)
+            }
+        }
+    }
+
+    /// The source map as JSON
+    pub fn map_json(&mut self) -> Result<Option<String>> {
+        Ok(match &mut self.map {
+            Some(map) => Some(map.to_json(None)?),
+            None => None,
+        })
+    }
+
+    /// The CSS with a comment pointing browsers to the source map at `url`
+    pub fn with_source_mapping_url(&self, url: &str) -> String {
+        format!("{}\n{SOURCE_MAPPING_URL}{url} */\n", self.css)
+    }
+
+    /// The CSS with the source map embedded as a data url
+    pub fn with_inline_map(&mut self) -> Result<String> {
+        match &mut self.map {
+            Some(map) => {
+                let url = map.to_data_url(None)?;
+                Ok(self.with_source_mapping_url(&url))
+            }
+            None => Ok(self.css.clone()),
+        }
+    }
+}
+
+/// Adds the sources with their content and the names of `from` to `to`, keeping
+/// their indices so that the mappings of `from` can be added as they are
+fn copy_sources(
+    from: &SourceMap,
+    to: &mut SourceMap,
+    rename: impl Fn(&str) -> String,
+) -> Result<()> {
+    for (index, source) in from.get_sources().iter().enumerate() {
+        // Sources are deduplicated by name, so renaming must not merge them
+        let source = rename(source);
+        if to.get_source_index(&source)?.is_some() {
+            to.add_source(&format!("{source}#{index}"));
+        } else {
+            to.add_source(&source);
+        }
+        if let Ok(content) = from.get_source_content(index as u32) {
+            to.set_source_content(index, content)?;
+        }
+    }
+    to.add_names(from.get_names().clone());
+    Ok(())
+}
+
+/// Converts between byte offsets and line and column positions
+struct LineIndex {
+    starts: Vec<usize>,
+    len: usize,
+}
+
+impl LineIndex {
+    fn new(text: &str) -> Self {
+        let starts = std::iter::once(0)
+            .chain(text.match_indices('\n').map(|(i, _)| i + 1))
+            .collect();
+        Self {
+            starts,
+            len: text.len(),
+        }
+    }
+
+    fn offset(&self, line: u32, column: u32) -> usize {
+        let start = self.starts.get(line as usize).copied().unwrap_or(self.len);
+        (start + column as usize).min(self.len)
+    }
+
+    fn position(&self, offset: usize) -> (u32, u32) {
+        let line = self.starts.partition_point(|start| *start <= offset) - 1;
+        (line as u32, (offset - self.starts[line]) as u32)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use parcel_sourcemap::OriginalLocation;
+
+    /// A map from each rule of `a {}\nb {}` to line 5 and 9 of `in.scss`
+    fn compiled(css: &str) -> CompiledCss {
+        let mut map = SourceMap::new("/");
+        map.add_source("in.scss");
+        map.set_source_content(0, "...").unwrap();
+        for (line, original_line) in [(0, 5), (1, 9)] {
+            map.add_mapping(
+                line,
+                0,
+                Some(OriginalLocation::new(original_line, 0, 0, None)),
+            );
+        }
+        CompiledCss {
+            css: css.to_string(),
+            map: Some(map),
+            exports: None,
+            purged: None,
+        }
+    }
+
+    fn original_line(css: &mut CompiledCss, needle: &str) -> Option<u32> {
+        let offset = css.css.find(needle)?;
+        let (line, column) = LineIndex::new(&css.css).position(offset);
+        let mapping = css.map.as_mut()?.find_closest_mapping(line, column)?;
+        Some(mapping.original?.original_line)
+    }
+
+    #[test]
+    fn test_replace_moves_mappings() {
+        let mut css = compiled(".a{color:red}\n.b{color:red}");
+        assert_eq!(css.replace(".a", ".first\n.a").unwrap(), 1);
+        assert_eq!(css.replace(".c", ".d").unwrap(), 0);
+        assert_eq!(css.css, ".first\n.a{color:red}\n.b{color:red}");
+        let mappings: Vec<_> = css
+            .map
+            .as_ref()
+            .unwrap()
+            .get_mappings()
+            .iter()
+            .map(|m| (m.generated_line, m.generated_column))
+            .collect();
+        assert_eq!(mappings, vec![(0, 0), (2, 0)]);
+        assert_eq!(original_line(&mut css, ".b"), Some(9));
+    }
+
+    #[test]
+    fn test_optimize_keeps_original_positions() {
+        let mut css = compiled(".a { color: red; }\n.b { color: blue; }");
+        let options = TransformOptions {
+            minify: true,
+            ..Default::default()
+        };
+        css.transform("in.css", options).unwrap();
+        assert_eq!(css.css, ".a{color:red}.b{color:#00f}");
+        assert_eq!(css.map.as_ref().unwrap().get_sources(), &vec!["in.scss"]);
+        assert_eq!(original_line(&mut css, ".a"), Some(5));
+        assert_eq!(original_line(&mut css, ".b"), Some(9));
+    }
+
+    #[test]
+    fn test_parse_error_in_original() {
+        let mut css = compiled(".a{color:red}\n@media (x {.b{color:red}}");
+        let err = css.transform("in.css", Default::default()).unwrap_err();
+        let diagnostic = err.downcast::<Diagnostic>().unwrap();
+        let location = diagnostic.location.unwrap();
+        assert_eq!((location.file.as_str(), location.line), ("in.scss", 10));
+
+        let mut css = CompiledCss::new(".a{color:red}\n@media (x {.b{color:red}}".to_string());
+        let err = css.transform("in.css", Default::default()).unwrap_err();
+        let diagnostic = err.downcast::<Diagnostic>().unwrap();
+        assert!(diagnostic.message.ends_with("(in the compiled CSS)"));
+        assert_eq!(diagnostic.location.unwrap().line, 2);
+    }
+
+    #[test]
+    fn test_embedded_map() {
+        let mut css = compiled(".a{}\n.b{}");
+        let embedded = css.with_inline_map().unwrap();
+        let mut parsed = CompiledCss::from_embedded(embedded).unwrap();
+        assert_eq!(parsed.css, ".a{}\n.b{}");
+        parsed
+            .rename_sources(|source| format!("styles/{source}"))
+            .unwrap();
+        let map = parsed.map_json().unwrap().unwrap();
+        assert!(map.contains(r#""sources":["styles/in.scss"]"#));
+        assert!(map.contains(r#""sourcesContent":["..."]"#));
+    }
+}
@@ -0,0 +1,204 @@
+use anyhow::{Context, Result, bail};
+use builder_command::BrowserTargets;
+use camino_fs::{Utf8Path, Utf8PathBuf, Utf8PathExt};
+use lightningcss::targets::Browsers;
+
+/// The query used when the project doesn't configure browsers
+pub const DEFAULT_QUERY: &str = ">0.3%, defaults, supports es6-module, maintained node versions";
+
+/// The browsers to optimize for and where they were configured, for the logs
+#[derive(Debug)]
+pub struct ResolvedTargets {
+    pub browsers: Option<Browsers>,
+    pub source: String,
+}
+
+/// Resolves the browser targets, looking up the project configuration relative to `dir`
+pub fn resolve_targets(targets: &BrowserTargets, dir: &Utf8Path) -> Result<ResolvedTargets> {
+    let (query, source) = match targets {
+        BrowserTargets::Versions(versions) => {
+            return Ok(ResolvedTargets {
+                browsers: Some(browsers_from_versions(versions)?),
+                source: "explicit versions".to_string(),
+            });
+        }
+        BrowserTargets::Query(query) => (query.clone(), "SassCmd query".to_string()),
+        BrowserTargets::Project => {
+            if let Some(file) = find_browserslist_file(dir) {
+                (
+                    parse_browserslist_file(&file.read_string()?),
+                    file.to_string(),
+                )
+            } else if let Some(query) = cargo_metadata_query(dir)? {
+                (query, "package.metadata.builder.browserslist".to_string())
+            } else {
+                (DEFAULT_QUERY.to_string(), "default query".to_string())
+            }
+        }
+    };
+    let browsers = Browsers::from_browserslist([query.as_str()])
+        .with_context(|| format!("Invalid browserslist query '{query}' from {source}"))?;
+    Ok(ResolvedTargets {
+        browsers,
+        source: format!("{source} ({query})"),
+    })
+}
+
+fn browsers_from_versions(versions: &[(String, String)]) -> Result<Browsers> {
+    let mut browsers = Browsers::default();
+    for (browser, version) in versions {
+        let field = match browser.as_str() {
+            "android" => &mut browsers.android,
+            "chrome" => &mut browsers.chrome,
+            "edge" => &mut browsers.edge,
+            "firefox" => &mut browsers.firefox,
+            "ie" => &mut browsers.ie,
+            "ios_saf" => &mut browsers.ios_saf,
+            "opera" => &mut browsers.opera,
+            "safari" => &mut browsers.safari,
+            "samsung" => &mut browsers.samsung,
+            _ => bail!("Unknown browser '{browser}' in browser targets"),
+        };
+        *field = Some(parse_version(version).with_context(|| {
+            format!("Invalid version '{version}' for {browser} in browser targets")
+        })?);
+    }
+    Ok(browsers)
+}
+
+/// Encodes `major[.minor[.patch]]` the way Lightning CSS stores versions
+fn parse_version(version: &str) -> Option<u32> {
+    let mut parts = version.split('.').map(|part| part.parse::<u32>().ok());
+    let major = parts.next()??;
+    let minor = parts.next().unwrap_or(Some(0))?;
+    let patch = parts.next().unwrap_or(Some(0))?;
+    if parts.next().is_some() || minor > 255 || patch > 255 {
+        return None;
+    }
+    Some((major << 16) | (minor << 8) | patch)
+}
+
+/// Finds a `browserslist` or `.browserslistrc` file in the directory or a parent
+fn find_browserslist_file(dir: &Utf8Path) -> Option<Utf8PathBuf> {
+    dir.ancestors()
+        .flat_map(|dir| [dir.join("browserslist"), dir.join(".browserslistrc")])
+        .find(|file| file.is_file())
+}
+
+/// The queries of a browserslist file for production: the lines outside of sections
+/// and in the `[production]` section, without comments
+fn parse_browserslist_file(content: &str) -> String {
+    let mut queries = Vec::new();
+    let mut in_section = true;
+    for line in content.lines() {
+        let line = line.split('#').next().unwrap_or_default().trim();
+        if let Some(sections) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+            in_section = sections.split_whitespace().any(|env| env == "production");
+        } else if in_section && !line.is_empty() {
+            queries.push(line);
+        }
+    }
+    queries.join(", ")
+}
+
+/// The `package.metadata.builder.browserslist` of the crate in the directory, as a
+/// query string or a list of queries
+fn cargo_metadata_query(dir: &Utf8Path) -> Result<Option<String>> {
+    let manifest = dir.join("Cargo.toml");
+    if !manifest.is_file() {
+        return Ok(None);
+    }
+    let metadata = cargo_metadata::MetadataCommand::new()
+        .manifest_path(&manifest)
+        .no_deps()
+        .exec()
+        .context("Failed to run cargo metadata to read the browserslist")?;
+    let package = metadata
+        .packages
+        .iter()
+        .find(|package| package.manifest_path.parent() == Some(dir));
+    Ok(package.and_then(|package| metadata_query(&package.metadata)))
+}
+
+fn metadata_query(metadata: &serde_json::Value) -> Option<String> {
+    match &metadata["builder"]["browserslist"] {
+        serde_json::Value::String(query) => Some(query.clone()),
+        serde_json::Value::Array(queries) => Some(
+            queries
+                .iter()
+                .filter_map(serde_json::Value::as_str)
+                .collect::<Vec<_>>()
+                .join(", "),
+        ),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_explicit_versions() {
+        let targets = BrowserTargets::versions([("safari", "15.4"), ("chrome", "110")]);
+        let resolved = resolve_targets(&targets, Utf8Path::new(".")).unwrap();
+        let browsers = resolved.browsers.unwrap();
+        assert_eq!(browsers.safari, Some(15 << 16 | 4 << 8));
+        assert_eq!(browsers.chrome, Some(110 << 16));
+        assert_eq!(browsers.firefox, None);
+
+        let targets = BrowserTargets::versions([("netscape", "4")]);
+        assert!(resolve_targets(&targets, Utf8Path::new(".")).is_err());
+        assert_eq!(parse_version("15.x"), None);
+    }
+
+    #[test]
+    fn test_browserslist_file() {
+        let content = "# Embedded WebView\nsafari >= 15\n\n[development]\nlast 1 chrome version\n\n[production staging]\nios_saf >= 15 # iPad\n";
+        assert_eq!(
+            parse_browserslist_file(content),
+            "safari >= 15, ios_saf >= 15"
+        );
+
+        let dir = TempDir::new().unwrap();
+        let root = Utf8Path::from_path(dir.path()).unwrap();
+        let styles = root.join("styles");
+        styles.mkdirs().unwrap();
+        root.join(".browserslistrc").write(content).unwrap();
+
+        let resolved = resolve_targets(&BrowserTargets::Project, &styles).unwrap();
+        assert!(resolved.source.contains(".browserslistrc"));
+        let browsers = resolved.browsers.unwrap();
+        assert_eq!(browsers.chrome, None);
+        assert!(browsers.safari.is_some());
+    }
+
+    #[test]
+    fn test_metadata_query() {
+        let metadata =
+            serde_json::json!({ "builder": { "browserslist": ["safari >= 15", "chrome >= 110"] } });
+        assert_eq!(
+            metadata_query(&metadata).unwrap(),
+            "safari >= 15, chrome >= 110"
+        );
+        assert_eq!(metadata_query(&serde_json::json!(null)), None);
+    }
+
+    #[test]
+    fn test_targets_prefix_and_lower() {
+        let targets = BrowserTargets::query("safari 12");
+        let resolved = resolve_targets(&targets, Utf8Path::new(".")).unwrap();
+        let mut css = crate::CompiledCss::new(
+            ".a { user-select: none; color: lab(50% 40 30); inset: 0; }".to_string(),
+        );
+        let options = crate::TransformOptions {
+            minify: true,
+            targets: resolved.browsers.into(),
+            ..Default::default()
+        };
+        css.transform("in.css", options).unwrap();
+        assert!(css.css.contains("-webkit-user-select:none"), "{}", css.css);
+        assert!(!css.css.contains("inset"), "{}", css.css);
+    }
+}
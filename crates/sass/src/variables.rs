@@ -0,0 +1,205 @@
+use crate::SassInput;
+use anyhow::{Context, Result, bail};
+use builder_command::SassValue;
+use camino_fs::Utf8PathExt;
+
+/// The url of the generated module with the variables of a `SassCmd`
+pub const VARIABLES_MODULE: &str = "builder";
+
+impl SassInput {
+    /// Adds the generated `builder` module with the variables to the load paths, and
+    /// compiles the entry through a wrapper that configures the modules before the
+    /// entry uses them. The wrapper is written next to the staged entry, so that the
+    /// module urls resolve like in the entry.
+    pub fn inject(
+        &mut self,
+        variables: &[(String, SassValue)],
+        module_config: &[(String, Vec<(String, SassValue)>)],
+    ) -> Result<()> {
+        let staging = self
+            .staging_dir()
+            .context("Variables can only be injected into staged stylesheets")?
+            .to_path_buf();
+
+        let module_dir = staging.join("__builder");
+        module_dir.mkdirs()?;
+        module_dir
+            .join(format!("_{VARIABLES_MODULE}.scss"))
+            .write(variables_module(variables)?)?;
+        self.load_paths.push(module_dir);
+
+        let entry_name = self.entry.file_name().unwrap().to_string();
+        let stem = self.entry.file_stem().unwrap();
+        let wrapper = self.entry.with_file_name(format!("__builder_{stem}.scss"));
+        wrapper.write(wrapper_stylesheet(&entry_name, module_config)?)?;
+        self.entry = wrapper;
+        Ok(())
+    }
+}
+
+fn variables_module(variables: &[(String, SassValue)]) -> Result<String> {
+    let mut module = String::from("// Variables passed to Sass by builder\n");
+    for (name, value) in variables {
+        module.push_str(&format!("${}: {};\n", variable_name(name)?, render(value)?));
+    }
+    Ok(module)
+}
+
+fn wrapper_stylesheet(
+    entry_name: &str,
+    module_config: &[(String, Vec<(String, SassValue)>)],
+) -> Result<String> {
+    let mut wrapper = format!("// Configures modules for {entry_name}, generated by builder\n");
+    for (i, (url, variables)) in module_config.iter().enumerate() {
+        let config = variables
+            .iter()
+            .map(|(name, value)| Ok(format!("${}: {}", variable_name(name)?, render(value)?)))
+            .collect::<Result<Vec<_>>>()?;
+        wrapper.push_str(&format!(
+            "@use {} as builder-config-{i} with ({});\n",
+            quote(url),
+            config.join(", ")
+        ));
+    }
+    wrapper.push_str(&format!("@use {} as builder-entry;\n", quote(entry_name)));
+    Ok(wrapper)
+}
+
+fn variable_name(name: &str) -> Result<&str> {
+    let name = name.strip_prefix('$').unwrap_or(name);
+    let valid = name.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-');
+    if !valid {
+        bail!("Invalid Sass variable name '{name}'");
+    }
+    Ok(name)
+}
+
+/// Writes the value with the Sass syntax of its type, checking that numbers and
+/// colors are what they claim to be
+pub fn render(value: &SassValue) -> Result<String> {
+    Ok(match value {
+        SassValue::String(s) => quote(s),
+        SassValue::Number(number) if is_number(number) => number.clone(),
+        SassValue::Number(number) => bail!("Invalid Sass number '{number}'"),
+        SassValue::Color(color) if is_color(color) => color.clone(),
+        SassValue::Color(color) => bail!("Invalid Sass color '{color}'"),
+        SassValue::Bool(b) => b.to_string(),
+        SassValue::Null => "null".to_string(),
+        SassValue::List(values) => {
+            let values = values.iter().map(render).collect::<Result<Vec<_>>>()?;
+            match values.len() {
+                1 => format!("({},)", values[0]),
+                _ => format!("({})", values.join(", ")),
+            }
+        }
+        SassValue::Map(entries) => {
+            let entries = entries
+                .iter()
+                .map(|(key, value)| Ok(format!("{}: {}", quote(key), render(value)?)))
+                .collect::<Result<Vec<_>>>()?;
+            format!("({})", entries.join(", "))
+        }
+    })
+}
+
+fn quote(s: &str) -> String {
+    let escaped = s
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\a ");
+    format!("\"{escaped}\"")
+}
+
+/// A number like `-1.5` with an optional unit like `px` or `%`
+fn is_number(number: &str) -> bool {
+    let digits = number.strip_prefix('-').unwrap_or(number);
+    let unit_start = digits
+        .find(|c: char| !c.is_ascii_digit() && c != '.')
+        .unwrap_or(digits.len());
+    let (value, unit) = digits.split_at(unit_start);
+    value.parse::<f64>().is_ok()
+        && !value.ends_with('.')
+        && (unit == "%" || unit.chars().all(|c| c.is_ascii_alphabetic()))
+}
+
+/// A hex color, a color keyword or a color function like `rgb(0 170 255 / 50%)`
+fn is_color(color: &str) -> bool {
+    if let Some(hex) = color.strip_prefix('#') {
+        return matches!(hex.len(), 3 | 4 | 6 | 8) && hex.chars().all(|c| c.is_ascii_hexdigit());
+    }
+    let (name, args) = match color.split_once('(') {
+        Some((name, args)) => match args.strip_suffix(')') {
+            Some(args) => (name, args),
+            None => return false,
+        },
+        None => (color, ""),
+    };
+    !name.is_empty()
+        && name.chars().all(|c| c.is_ascii_alphabetic())
+        && args
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || " .,%/-".contains(c))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use camino_fs::Utf8Path;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_render_values() {
+        let map = SassValue::Map(vec![
+            ("primary".to_string(), SassValue::color("#0af")),
+            ("gap".to_string(), SassValue::number(4, "px")),
+        ]);
+        assert_eq!(render(&map).unwrap(), r##"("primary": #0af, "gap": 4px)"##);
+        assert_eq!(
+            render(&SassValue::List(vec![1.5.into()])).unwrap(),
+            "(1.5,)"
+        );
+        assert_eq!(render(&"say \"hi\"".into()).unwrap(), r#""say \"hi\"""#);
+        assert_eq!(
+            render(&SassValue::color("rgb(0 170 255 / 50%)")).unwrap(),
+            "rgb(0 170 255 / 50%)"
+        );
+
+        assert!(render(&SassValue::Number("4px; } body { x: y".to_string())).is_err());
+        assert!(render(&SassValue::color("#0afz")).is_err());
+        assert!(render(&SassValue::color("red; }")).is_err());
+        assert!(variable_name("1st").is_err());
+    }
+
+    #[test]
+    fn test_inject() {
+        let dir = TempDir::new().unwrap();
+        let root = Utf8Path::from_path(dir.path()).unwrap();
+        root.join("_theme.scss")
+            .write("$primary: red !default;\n.button { color: $primary; }")
+            .unwrap();
+        root.join("main.scss")
+            .write("@use \"builder\";\n@use \"theme\";\n.logo { background: url(builder.$cdn + \"/logo.png\"); }")
+            .unwrap();
+
+        let mut input =
+            SassInput::prepare(&root.join("main.scss"), &[], || unreachable!(), true).unwrap();
+        input
+            .inject(
+                &[("cdn".to_string(), "https://cdn.example.com".into())],
+                &[(
+                    "theme".to_string(),
+                    vec![("primary".to_string(), SassValue::color("#0af"))],
+                )],
+            )
+            .unwrap();
+
+        let options = grass::Options::default().load_paths(&input.load_paths);
+        let css = grass::from_path(&input.entry, &options).unwrap();
+        assert!(css.contains("color: #0af"));
+        assert!(css.contains("https://cdn.example.com/logo.png"));
+        assert!(!css.contains("red"));
+    }
+}